@@ -12,7 +12,7 @@ fn stage1(sh: &mut boxxy::Shell, _args: Vec<String>) -> Result<(), boxxy::Error>
 
 fn stage2(sh: &mut boxxy::Shell, _args: Vec<String>) -> Result<(), boxxy::Error> {
     shprintln!(sh, "[*] starting stage2");
-    sniffglue::sandbox::activate_stage2(false).unwrap();
+    sniffglue::sandbox::activate_stage2(false, &[]).unwrap();
     shprintln!(sh, "[+] activated!");
     Ok(())
 }
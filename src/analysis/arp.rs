@@ -0,0 +1,231 @@
+use crate::analysis::{self, elapsed};
+use crate::bounded::BoundedMap;
+use crate::structs::arp::ARP;
+use crate::structs::dhcp::DHCP;
+use crate::structs::ether::Ether;
+use crate::structs::ipv4::IPv4;
+use crate::structs::raw::Raw;
+use crate::structs::udp::UDP;
+use serde::Serialize;
+use std::net::Ipv4Addr;
+use std::time::{Duration, SystemTime};
+
+const MAX_ENTRIES: usize = 4096;
+const MAX_FLOOD_COUNTERS: usize = 1024;
+/// A mac that claimed an ip this recently and claims it again is fighting over it
+const CONFLICT_WINDOW: Duration = Duration::from_secs(60);
+const FLOOD_WINDOW: Duration = Duration::from_secs(10);
+const FLOOD_THRESHOLD: u32 = 20;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Alert {
+    MappingChanged {
+        ip: Ipv4Addr,
+        old: [u8; 6],
+        new: [u8; 6],
+    },
+    IpConflict {
+        ip: Ipv4Addr,
+        macs: [[u8; 6]; 2],
+    },
+    GratuitousFlood {
+        mac: [u8; 6],
+        count: u32,
+    },
+}
+
+struct Entry {
+    mac: [u8; 6],
+    last_seen: SystemTime,
+    previous: Option<([u8; 6], SystemTime)>,
+}
+
+struct FloodCounter {
+    window_start: SystemTime,
+    count: u32,
+    alerted: bool,
+}
+
+/// Keeps an ip to mac table built from arp replies and dhcp acks
+#[derive(Default)]
+pub struct ArpWatch {
+    table: BoundedMap<Ipv4Addr, Entry, MAX_ENTRIES>,
+    gratuitous: BoundedMap<[u8; 6], FloodCounter, MAX_FLOOD_COUNTERS>,
+}
+
+impl ArpWatch {
+    pub fn process(
+        &mut self,
+        timestamp: SystemTime,
+        packet: &Raw,
+        alerts: &mut Vec<analysis::Alert>,
+    ) {
+        match packet.ether() {
+            Some(Ether::Arp(ARP::Reply(arp_pkt))) => {
+                if arp_pkt.src_addr == arp_pkt.dest_addr {
+                    self.gratuitous(timestamp, arp_pkt.src_mac.0, alerts);
+                }
+                self.claim(timestamp, arp_pkt.src_addr, arp_pkt.src_mac.0, alerts);
+            }
+            Some(Ether::Arp(ARP::Request(arp_pkt))) if arp_pkt.src_addr == arp_pkt.dest_addr => {
+                self.gratuitous(timestamp, arp_pkt.src_mac.0, alerts);
+                self.claim(timestamp, arp_pkt.src_addr, arp_pkt.src_mac.0, alerts);
+            }
+            Some(Ether::IPv4(_, IPv4::UDP(_, UDP::DHCP(DHCP::ACK(ack))))) => {
+                self.claim(timestamp, ack.yiaddr, ack.chaddr, alerts);
+            }
+            _ => (),
+        }
+    }
+
+    fn claim(
+        &mut self,
+        timestamp: SystemTime,
+        ip: Ipv4Addr,
+        mac: [u8; 6],
+        alerts: &mut Vec<analysis::Alert>,
+    ) {
+        if ip.is_unspecified() {
+            // arp probes and dhcp acks without an address
+            return;
+        }
+
+        let Some(entry) = self.table.touch(timestamp, &ip) else {
+            self.table.insert(
+                timestamp,
+                ip,
+                Entry {
+                    mac,
+                    last_seen: timestamp,
+                    previous: None,
+                },
+            );
+            return;
+        };
+
+        if entry.mac != mac {
+            let alert = match entry.previous {
+                Some((previous, seen))
+                    if previous == mac && elapsed(seen, timestamp) < CONFLICT_WINDOW =>
+                {
+                    Alert::IpConflict {
+                        ip,
+                        macs: [entry.mac, mac],
+                    }
+                }
+                _ => Alert::MappingChanged {
+                    ip,
+                    old: entry.mac,
+                    new: mac,
+                },
+            };
+            alerts.push(analysis::Alert::Arp(alert));
+            entry.previous = Some((entry.mac, entry.last_seen));
+            entry.mac = mac;
+        }
+        entry.last_seen = timestamp;
+    }
+
+    fn gratuitous(
+        &mut self,
+        timestamp: SystemTime,
+        mac: [u8; 6],
+        alerts: &mut Vec<analysis::Alert>,
+    ) {
+        let counter = self
+            .gratuitous
+            .touch_or_insert_with(timestamp, mac, || FloodCounter {
+                window_start: timestamp,
+                count: 0,
+                alerted: false,
+            });
+
+        if elapsed(counter.window_start, timestamp) >= FLOOD_WINDOW {
+            counter.window_start = timestamp;
+            counter.count = 0;
+            counter.alerted = false;
+        }
+
+        counter.count += 1;
+        if counter.count >= FLOOD_THRESHOLD && !counter.alerted {
+            counter.alerted = true;
+            alerts.push(analysis::Alert::Arp(Alert::GratuitousFlood {
+                mac,
+                count: counter.count,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pktparse::arp::{ArpPacket, HardwareAddressType, Operation, ProtocolAddressType};
+    use pktparse::ethernet::{EtherType, EthernetFrame, MacAddress};
+
+    const ROUTER: [u8; 6] = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+    const ATTACKER: [u8; 6] = [0x66, 0x66, 0x66, 0x66, 0x66, 0x66];
+
+    fn reply(ip: &str, mac: [u8; 6]) -> Raw {
+        Raw::Ether(
+            EthernetFrame {
+                source_mac: MacAddress(mac),
+                dest_mac: MacAddress([0xff; 6]),
+                ethertype: EtherType::ARP,
+            },
+            Ether::Arp(ARP::Reply(ArpPacket {
+                hw_addr_type: HardwareAddressType::Ethernet,
+                proto_addr_type: ProtocolAddressType::IPv4,
+                hw_addr_size: 6,
+                proto_addr_size: 4,
+                operation: Operation::Reply,
+                src_mac: MacAddress(mac),
+                src_addr: ip.parse().unwrap(),
+                dest_mac: MacAddress([0xff; 6]),
+                dest_addr: "192.168.1.23".parse().unwrap(),
+            })),
+        )
+    }
+
+    #[test]
+    fn detect_spoofing() {
+        let mut watch = ArpWatch::default();
+        let mut alerts = Vec::new();
+        let now = SystemTime::UNIX_EPOCH;
+        let ip = "192.168.1.1".parse().unwrap();
+
+        watch.process(now, &reply("192.168.1.1", ROUTER), &mut alerts);
+        watch.process(now, &reply("192.168.1.1", ROUTER), &mut alerts);
+        assert_eq!(alerts, vec![]);
+
+        watch.process(now, &reply("192.168.1.1", ATTACKER), &mut alerts);
+        watch.process(now, &reply("192.168.1.1", ROUTER), &mut alerts);
+        assert_eq!(
+            alerts,
+            vec![
+                analysis::Alert::Arp(Alert::MappingChanged {
+                    ip,
+                    old: ROUTER,
+                    new: ATTACKER,
+                }),
+                analysis::Alert::Arp(Alert::IpConflict {
+                    ip,
+                    macs: [ATTACKER, ROUTER],
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn bounded_table() {
+        let mut watch = ArpWatch::default();
+        let mut alerts = Vec::new();
+        for i in 0..(MAX_ENTRIES as u32 + 100) {
+            let ip = Ipv4Addr::from(0x0a00_0000 + i);
+            let timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(i.into());
+            watch.claim(timestamp, ip, ROUTER, &mut alerts);
+        }
+        assert_eq!(watch.table.len(), MAX_ENTRIES);
+        assert!(!watch.table.contains_key(&Ipv4Addr::from(0x0a00_0000)));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::analysis::{Segment, Transport};
+use crate::centrifuge;
+use crate::errors::*;
+use crate::sandbox::output::OutputDir;
+use crate::structs::raw::Raw;
+use crate::structs::tcp::TCP;
+use crate::structs::tls::TLS;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Compare the certificates of a packet with the time it was captured, instead of the time the capture is read
+pub fn check_expiry(timestamp: SystemTime, packet: &mut Raw) {
+    let certificates = match centrifuge::tcp::connection(packet) {
+        Some((_, _, TCP::TLS(TLS::ServerHello(server_hello)))) => &mut server_hello.certificates,
+        Some((_, _, TCP::TLS(TLS::Certificate(certificates)))) => certificates,
+        _ => return,
+    };
+    let now = match timestamp.duration_since(UNIX_EPOCH) {
+        Ok(now) => now.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs() as i64),
+    };
+    for cert in certificates {
+        cert.expired = cert.expires < now;
+    }
+}
+
+/// Writes every certificate seen on the wire into a directory, named after its sha256
+pub struct CertificateDump {
+    dir: Arc<OutputDir>,
+}
+
+impl CertificateDump {
+    pub fn new(dir: Arc<OutputDir>) -> CertificateDump {
+        CertificateDump { dir }
+    }
+
+    pub fn process(&mut self, packet: &Raw) {
+        let Some(segment) = Segment::from_raw(packet) else {
+            return;
+        };
+
+        let certificates = match segment.transport {
+            Transport::TCP(_, TCP::TLS(TLS::ServerHello(server_hello))) => {
+                &server_hello.certificates
+            }
+            Transport::TCP(_, TCP::TLS(TLS::Certificate(certificates))) => certificates,
+            _ => return,
+        };
+
+        for cert in certificates {
+            match self.dir.create(&format!("{}.der", cert.sha256), &cert.der) {
+                Ok(true) => debug!("Saved certificate of {:?}", cert.subject),
+                Ok(false) => (),
+                Err(err) => warn!("Failed to save certificate: {:#}", err),
+            }
+        }
+    }
+}
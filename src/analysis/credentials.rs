@@ -0,0 +1,841 @@
+use crate::analysis::{Event, Segment, Transport};
+use crate::bounded::BoundedMap;
+use crate::centrifuge::{irc, mail, telnet};
+use crate::structs::ftp::FTP;
+use crate::structs::http::{Http, Request, Response};
+use crate::structs::mail::Mail;
+use crate::structs::postgres;
+use crate::structs::raw::Raw;
+use crate::structs::redis::Redis;
+use crate::structs::tcp::TCP;
+use crate::structs::telnet::{Command, Prompt, Telnet};
+use bstr::ByteSlice;
+use data_encoding::{BASE64, HEXLOWER};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::time::SystemTime;
+
+const MAX_SESSIONS: usize = 4096;
+/// Findings that are remembered for deduplication, the least recently seen is forgotten first
+const MAX_FINDINGS: usize = 4096;
+/// Limit for a line that is typed into a telnet session
+const MAX_LINE_LENGTH: usize = 256;
+
+const USER_FIELDS: &[&str] = &[
+    "user",
+    "username",
+    "user_name",
+    "userid",
+    "login",
+    "log",
+    "uname",
+    "usr",
+    "email",
+    "mail",
+    "account",
+];
+pub const PASSWORD_FIELDS: &[&str] = &[
+    "pass",
+    "password",
+    "passwd",
+    "passwort",
+    "pwd",
+    "pw",
+    "secret",
+    "passphrase",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Credential {
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    pub protocol: &'static str,
+    /// How the credential was sent, eg. basic, form or auth-plain
+    pub mechanism: &'static str,
+    /// The http host or the irc nick
+    pub host: Option<String>,
+    pub username: Option<String>,
+    /// A password, token or challenge response
+    pub secret: Option<String>,
+}
+
+#[derive(Default)]
+enum Expect {
+    #[default]
+    Nothing,
+    /// Base64 encoded authzid, authcid and password
+    Plain,
+    /// Base64 encoded username, followed by the password
+    LoginUser,
+    LoginPassword,
+    /// Keystrokes after a telnet login prompt
+    TelnetUser,
+    TelnetPassword,
+}
+
+struct Session {
+    username: Option<String>,
+    expect: Expect,
+    /// Keystrokes of a telnet line, a character can span several of them
+    line: Vec<u8>,
+    /// Server challenge of an ntlm handshake
+    challenge: Option<[u8; 8]>,
+}
+
+impl Session {
+    fn new() -> Session {
+        Session {
+            username: None,
+            expect: Expect::Nothing,
+            line: Vec::new(),
+            challenge: None,
+        }
+    }
+}
+
+/// Collects credentials that are sent in cleartext and reports each of them once
+#[derive(Default)]
+pub struct CredentialLog {
+    sessions: BoundedMap<(SocketAddr, SocketAddr), Session, MAX_SESSIONS>,
+    seen: BoundedMap<Credential, (), MAX_FINDINGS>,
+}
+
+impl CredentialLog {
+    pub fn process(&mut self, timestamp: SystemTime, packet: &Raw, events: &mut Vec<Event>) {
+        let Some(segment) = Segment::from_raw(packet) else {
+            return;
+        };
+        let Transport::TCP(_, tcp) = segment.transport else {
+            return;
+        };
+
+        let mut findings = Vec::new();
+        match tcp {
+            TCP::HTTP(Http::Request(request)) => {
+                let session = self.session(timestamp, (segment.src, segment.dst));
+                http_request(&segment, session, request, &mut findings);
+            }
+            TCP::HTTP(Http::Response(response)) => {
+                if let Some(challenge) = http_challenge(response) {
+                    let session = self.session(timestamp, (segment.dst, segment.src));
+                    session.challenge = Some(challenge);
+                }
+            }
+            TCP::FTP(FTP::Command(command)) => {
+                let line = match &command.argument {
+                    Some(argument) => format!("{} {}", command.command, argument),
+                    None => command.command.clone(),
+                };
+                self.text(timestamp, &segment, line.as_bytes(), &mut findings)
+            }
+            TCP::Mail(Mail::Commands(_, commands)) => {
+                for command in commands {
+                    let line = command.line();
+                    self.text(timestamp, &segment, line.as_bytes(), &mut findings);
+                }
+            }
+            // messages from the server have a prefix, except for pings
+            TCP::IRC(messages) if !irc::is_port(segment.src.port()) => {
+                let session = self.session(timestamp, (segment.src, segment.dst));
+                let mut found = Found {
+                    client: segment.src,
+                    server: segment.dst,
+                    protocol: "irc",
+                    findings: &mut findings,
+                };
+                for message in messages.iter().filter(|x| x.prefix.is_none()) {
+                    command(session, &message.line(), &mut found);
+                }
+            }
+            TCP::Postgres(messages) => {
+                let session = self.session(timestamp, (segment.src, segment.dst));
+                let mut found = Found {
+                    client: segment.src,
+                    server: segment.dst,
+                    protocol: "postgres",
+                    findings: &mut findings,
+                };
+                for message in messages {
+                    match message {
+                        postgres::Message::Startup(startup) => {
+                            session.username = startup.user.clone()
+                        }
+                        postgres::Message::Password(password) => {
+                            // the client hashes the password if the server asks for md5
+                            let mechanism = if password.len() == 35 && password.starts_with("md5") {
+                                "md5"
+                            } else {
+                                "cleartext"
+                            };
+                            found.push(
+                                mechanism,
+                                None,
+                                session.username.clone(),
+                                Some(password.clone()),
+                            );
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            TCP::Redis(Redis::Commands(commands)) => {
+                let mut found = Found {
+                    client: segment.src,
+                    server: segment.dst,
+                    protocol: "redis",
+                    findings: &mut findings,
+                };
+                for command in commands {
+                    redis_auth(&command.args, &mut found);
+                }
+            }
+            TCP::Telnet(telnet) => self.telnet(timestamp, &segment, telnet, &mut findings),
+            TCP::Text(text) => self.text(timestamp, &segment, text.as_bytes(), &mut findings),
+            TCP::Binary(data) => self.text(timestamp, &segment, data, &mut findings),
+            _ => (),
+        }
+
+        for credential in findings {
+            // clients use a new source port for every connection
+            let mut key = credential.clone();
+            key.client.set_port(0);
+            if self.seen.touch(timestamp, &key).is_none() {
+                self.seen.insert(timestamp, key, ());
+                events.push(Event::Credential(credential));
+            }
+        }
+    }
+
+    fn session(&mut self, timestamp: SystemTime, key: (SocketAddr, SocketAddr)) -> &mut Session {
+        self.sessions
+            .touch_or_insert_with(timestamp, key, Session::new)
+    }
+
+    fn text(
+        &mut self,
+        timestamp: SystemTime,
+        segment: &Segment,
+        data: &[u8],
+        findings: &mut Vec<Credential>,
+    ) {
+        if let Some(protocol) = service(segment.dst.port()) {
+            let session = self.session(timestamp, (segment.src, segment.dst));
+            let mut found = Found {
+                client: segment.src,
+                server: segment.dst,
+                protocol,
+                findings,
+            };
+            for line in data.lines() {
+                command(session, &line.to_str_lossy(), &mut found);
+            }
+        }
+    }
+
+    fn telnet(
+        &mut self,
+        timestamp: SystemTime,
+        segment: &Segment,
+        telnet: &Telnet,
+        findings: &mut Vec<Credential>,
+    ) {
+        if segment.dst.port() == telnet::PORT {
+            let session = self.session(timestamp, (segment.src, segment.dst));
+            // `telnet -l` sends the username and the server only asks for the password
+            for command in &telnet.commands {
+                if let Command::Environment(variables) = command
+                    && let Some((_, Some(user))) = variables.iter().find(|(name, _)| name == "USER")
+                {
+                    session.username = Some(user.clone());
+                }
+            }
+            let mut found = Found {
+                client: segment.src,
+                server: segment.dst,
+                protocol: "telnet",
+                findings,
+            };
+            telnet_keystrokes(session, telnet.text.as_bytes(), &mut found);
+        } else if let Some(prompt) = telnet.prompt {
+            let session = self.session(timestamp, (segment.dst, segment.src));
+            session.expect = match prompt {
+                Prompt::Login => Expect::TelnetUser,
+                Prompt::Password => Expect::TelnetPassword,
+            };
+            session.line.clear();
+        }
+    }
+}
+
+/// Well known ports of line based protocols
+pub fn service(port: u16) -> Option<&'static str> {
+    match port {
+        21 => Some("ftp"),
+        23 => Some("telnet"),
+        25 | 587 => Some("smtp"),
+        110 => Some("pop3"),
+        143 => Some("imap"),
+        194 | 6665..=6669 => Some("irc"),
+        _ => None,
+    }
+}
+
+struct Found<'a> {
+    client: SocketAddr,
+    server: SocketAddr,
+    protocol: &'static str,
+    findings: &'a mut Vec<Credential>,
+}
+
+impl Found<'_> {
+    fn push(
+        &mut self,
+        mechanism: &'static str,
+        host: Option<String>,
+        username: Option<String>,
+        secret: Option<String>,
+    ) {
+        self.findings.push(Credential {
+            client: self.client,
+            server: self.server,
+            protocol: self.protocol,
+            mechanism,
+            host,
+            username,
+            secret,
+        });
+    }
+}
+
+fn command(session: &mut Session, line: &str, found: &mut Found) {
+    let line = line.trim_end_matches(['\r', '\n']);
+
+    match std::mem::take(&mut session.expect) {
+        Expect::Nothing | Expect::TelnetUser | Expect::TelnetPassword => (),
+        // the client may cancel the exchange
+        _ if line == "*" => return,
+        Expect::Plain => {
+            if let Some((username, password)) = sasl_plain(line) {
+                found.push("auth-plain", None, Some(username), Some(password));
+            }
+            return;
+        }
+        Expect::LoginUser => {
+            session.username = base64_text(line);
+            session.expect = Expect::LoginPassword;
+            return;
+        }
+        Expect::LoginPassword => {
+            if let Some(password) = base64_text(line) {
+                found.push("auth-login", None, session.username.take(), Some(password));
+            }
+            return;
+        }
+    }
+
+    let mut words = line.split(' ');
+    let mut cmd = words.next().unwrap_or_default().to_ascii_uppercase();
+    // imap commands start with a tag
+    if found.protocol == "imap" {
+        cmd = words.next().unwrap_or_default().to_ascii_uppercase();
+    }
+    let args = words.collect::<Vec<_>>();
+
+    match (found.protocol, cmd.as_str()) {
+        ("ftp" | "pop3", "USER") => session.username = Some(args.join(" ")),
+        ("ftp" | "pop3", "PASS") => {
+            found.push("login", None, session.username.take(), Some(args.join(" ")))
+        }
+        ("pop3", "APOP") if args.len() == 2 => found.push(
+            "apop",
+            None,
+            Some(args[0].to_string()),
+            Some(args[1].to_string()),
+        ),
+        ("imap", "LOGIN") => {
+            let args = mail::imap_strings(&args.join(" "));
+            if let [username, password, ..] = args.as_slice() {
+                found.push(
+                    "login",
+                    None,
+                    Some(username.clone()),
+                    Some(password.clone()),
+                );
+            }
+        }
+        ("smtp" | "pop3", "AUTH") | ("imap", "AUTHENTICATE") => {
+            let mechanism = args.first().map(|x| x.to_ascii_uppercase());
+            match (mechanism.as_deref(), args.get(1)) {
+                (Some("PLAIN"), Some(initial)) => {
+                    if let Some((username, password)) = sasl_plain(initial) {
+                        found.push("auth-plain", None, Some(username), Some(password));
+                    }
+                }
+                (Some("PLAIN"), None) => session.expect = Expect::Plain,
+                (Some("LOGIN"), Some(initial)) => {
+                    session.username = base64_text(initial);
+                    session.expect = Expect::LoginPassword;
+                }
+                (Some("LOGIN"), None) => session.expect = Expect::LoginUser,
+                _ => (),
+            }
+        }
+        ("irc", "NICK") => session.username = args.first().map(|x| x.to_string()),
+        ("irc", "PASS") => found.push(
+            "pass",
+            None,
+            session.username.clone(),
+            Some(args.join(" ").trim_start_matches(':').to_string()),
+        ),
+        ("irc", "OPER") if args.len() >= 2 => found.push(
+            "oper",
+            session.username.clone(),
+            Some(args[0].to_string()),
+            Some(args[1..].join(" ")),
+        ),
+        ("irc", "PRIVMSG")
+            if args
+                .first()
+                .is_some_and(|x| x.eq_ignore_ascii_case("nickserv")) =>
+        {
+            let text = args[1..].join(" ");
+            let mut words = text.trim_start_matches(':').split(' ');
+            if words
+                .next()
+                .is_some_and(|x| x.eq_ignore_ascii_case("identify"))
+            {
+                nickserv_identify(session, &words.collect::<Vec<_>>(), found);
+            }
+        }
+        ("irc", "NICKSERV" | "NS")
+            if args
+                .first()
+                .is_some_and(|x| x.eq_ignore_ascii_case("identify")) =>
+        {
+            nickserv_identify(session, &args[1..], found);
+        }
+        _ => (),
+    }
+}
+
+fn nickserv_identify(session: &Session, args: &[&str], found: &mut Found) {
+    let (account, password) = match args {
+        [password] => (session.username.clone(), password),
+        [account, password, ..] => (Some(account.to_string()), password),
+        [] => return,
+    };
+    found.push(
+        "nickserv",
+        session.username.clone(),
+        account,
+        Some(password.to_string()),
+    );
+}
+
+/// `AUTH [username] password`, redis 6 also accepts the credentials with `HELLO`
+fn redis_auth(args: &[Vec<u8>], found: &mut Found) {
+    let args = args.iter().map(|x| x.to_str_lossy()).collect::<Vec<_>>();
+    let credentials = match args.first().map(|x| x.to_ascii_uppercase()).as_deref() {
+        Some("AUTH") => &args[1..],
+        Some("HELLO") => match args.iter().position(|x| x.eq_ignore_ascii_case("AUTH")) {
+            Some(i) if i + 2 < args.len() => &args[i + 1..i + 3],
+            _ => return,
+        },
+        _ => return,
+    };
+    let (username, password) = match credentials {
+        [password] => (None, password),
+        [username, password, ..] => (Some(username.to_string()), password),
+        [] => return,
+    };
+    found.push("auth", None, username, Some(password.to_string()));
+}
+
+/// Telnet clients usually send every keystroke in a separate packet
+fn telnet_keystrokes(session: &mut Session, data: &[u8], found: &mut Found) {
+    for &byte in data {
+        match byte {
+            b'\r' | b'\n' => {
+                let line = String::from_utf8_lossy(&std::mem::take(&mut session.line)).into_owned();
+                match std::mem::take(&mut session.expect) {
+                    Expect::TelnetUser => session.username = Some(line),
+                    Expect::TelnetPassword => {
+                        found.push("login", None, session.username.take(), Some(line))
+                    }
+                    expect => session.expect = expect,
+                }
+            }
+            // backspace and delete
+            0x08 | 0x7f => {
+                // remove the continuation bytes and the first byte of the last character
+                while let Some(byte) = session.line.pop()
+                    && byte & 0xc0 == 0x80
+                {}
+            }
+            byte => {
+                if matches!(session.expect, Expect::TelnetUser | Expect::TelnetPassword)
+                    && session.line.len() < MAX_LINE_LENGTH
+                {
+                    session.line.push(byte);
+                }
+            }
+        }
+    }
+}
+
+fn http_request(
+    segment: &Segment,
+    session: &mut Session,
+    request: &Request,
+    findings: &mut Vec<Credential>,
+) {
+    let mut found = Found {
+        client: segment.src,
+        server: segment.dst,
+        protocol: "http",
+        findings,
+    };
+    let host = request.host.clone();
+
+    for name in ["authorization", "proxy-authorization"] {
+        let Some(value) = request.header(name) else {
+            continue;
+        };
+        let value = value.to_str_lossy();
+        let (scheme, param) = value.trim().split_once(' ').unwrap_or((&value, ""));
+        let param = param.trim();
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "basic" => {
+                let decoded = base64_text(param);
+                let (username, password) = match decoded.as_deref().and_then(|x| x.split_once(':'))
+                {
+                    Some((username, password)) => (username.to_string(), password.to_string()),
+                    None => continue,
+                };
+                found.push("basic", host.clone(), Some(username), Some(password));
+            }
+            "bearer" => found.push("bearer", host.clone(), None, Some(param.to_string())),
+            "digest" => {
+                let username = param.split(',').find_map(|x| {
+                    let (key, value) = x.split_once('=')?;
+                    key.trim()
+                        .eq_ignore_ascii_case("username")
+                        .then(|| value.trim().trim_matches('"').to_string())
+                });
+                found.push("digest", host.clone(), username, Some(param.to_string()));
+            }
+            "ntlm" | "negotiate" => {
+                if let Some((username, response)) = BASE64
+                    .decode(param.as_bytes())
+                    .ok()
+                    .and_then(|x| ntlm_response(&x, session.challenge))
+                {
+                    found.push("ntlm", host.clone(), Some(username), Some(response));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // login forms, either as query string or url encoded body
+    if let Some((_, query)) = request.path.split_once('?') {
+        form(query.as_bytes(), "query", &host, &mut found);
+    }
+    if let Some(body) = request.decoded()
+        && body.content_type.as_deref().is_some_and(|x| {
+            x.to_ascii_lowercase()
+                .starts_with("application/x-www-form-urlencoded")
+        })
+    {
+        form(&body.data, "form", &host, &mut found);
+    }
+}
+
+fn form(data: &[u8], mechanism: &'static str, host: &Option<String>, found: &mut Found) {
+    let mut username = None;
+    let mut password = None;
+    for pair in data.split_str("&") {
+        let (key, value) = pair.split_once_str("=").unwrap_or((pair, b""));
+        let key = url_decode(key).to_ascii_lowercase();
+        let value = url_decode(value);
+        if USER_FIELDS.contains(&key.as_str()) {
+            username = Some(value);
+        } else if PASSWORD_FIELDS.contains(&key.as_str()) {
+            password = Some(value);
+        }
+    }
+    if let Some(password) = password {
+        found.push(mechanism, host.clone(), username, Some(password));
+    }
+}
+
+fn url_decode(data: &[u8]) -> String {
+    let mut out = Vec::with_capacity(data.len());
+    let mut idx = 0;
+    while idx < data.len() {
+        match data[idx] {
+            b'+' => out.push(b' '),
+            b'%' if idx + 3 <= data.len() => {
+                match std::str::from_utf8(&data[idx + 1..idx + 3])
+                    .ok()
+                    .and_then(|x| u8::from_str_radix(x, 16).ok())
+                {
+                    Some(byte) => {
+                        out.push(byte);
+                        idx += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        idx += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Server challenge of an ntlm handshake, sent in a WWW-Authenticate header
+fn http_challenge(response: &Response) -> Option<[u8; 8]> {
+    response
+        .headers
+        .iter()
+        .filter(|(key, _)| {
+            key.eq_ignore_ascii_case("www-authenticate")
+                || key.eq_ignore_ascii_case("proxy-authenticate")
+        })
+        .find_map(|(_, value)| {
+            let value = value.to_str().ok()?;
+            let (scheme, param) = value.trim().split_once(' ')?;
+            if !scheme.eq_ignore_ascii_case("ntlm") && !scheme.eq_ignore_ascii_case("negotiate") {
+                return None;
+            }
+            let message = BASE64.decode(param.trim().as_bytes()).ok()?;
+            ntlm_challenge(&message)
+        })
+}
+
+fn ntlm_message(message: &[u8], kind: u32) -> bool {
+    message.starts_with(b"NTLMSSP\0")
+        && message
+            .get(8..12)
+            .is_some_and(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]) == kind)
+}
+
+fn ntlm_challenge(message: &[u8]) -> Option<[u8; 8]> {
+    if !ntlm_message(message, 2) {
+        return None;
+    }
+    message.get(24..32)?.try_into().ok()
+}
+
+/// Format the authenticate message as `user::domain:challenge:response` like hashcat expects it
+fn ntlm_response(message: &[u8], challenge: Option<[u8; 8]>) -> Option<(String, String)> {
+    if !ntlm_message(message, 3) {
+        return None;
+    }
+
+    let field = |offset: usize| -> Option<&[u8]> {
+        let header = message.get(offset..offset + 8)?;
+        let len = usize::from(u16::from_le_bytes([header[0], header[1]]));
+        let start = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        message.get(start..start.checked_add(len)?)
+    };
+    let flags = message
+        .get(60..64)
+        .map_or(0, |x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]));
+    let text = |data: &[u8]| -> String {
+        if flags & 1 != 0 {
+            let units = data
+                .chunks_exact(2)
+                .map(|x| u16::from_le_bytes([x[0], x[1]]))
+                .collect::<Vec<_>>();
+            String::from_utf16_lossy(&units)
+        } else {
+            String::from_utf8_lossy(data).into_owned()
+        }
+    };
+
+    let lm = field(12)?;
+    let nt = field(20)?;
+    let domain = text(field(28)?);
+    let user = text(field(36)?);
+    if user.is_empty() {
+        // anonymous authentication
+        return None;
+    }
+
+    let challenge = challenge.map_or_else(|| "?".repeat(16), |x| HEXLOWER.encode(&x));
+    let response = if nt.len() > 24 {
+        // ntlmv2, the first 16 bytes are the proof
+        format!(
+            "{}::{}:{}:{}:{}",
+            user,
+            domain,
+            challenge,
+            HEXLOWER.encode(&nt[..16]),
+            HEXLOWER.encode(&nt[16..])
+        )
+    } else {
+        format!(
+            "{}::{}:{}:{}:{}",
+            user,
+            domain,
+            HEXLOWER.encode(lm),
+            HEXLOWER.encode(nt),
+            challenge
+        )
+    };
+
+    let username = if domain.is_empty() {
+        user
+    } else {
+        format!("{}\\{}", domain, user)
+    };
+    Some((username, response))
+}
+
+fn base64_text(data: &str) -> Option<String> {
+    let data = BASE64.decode(data.trim().as_bytes()).ok()?;
+    Some(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// Decode `authzid\0authcid\0password` of the sasl plain mechanism
+fn sasl_plain(data: &str) -> Option<(String, String)> {
+    let data = BASE64.decode(data.trim().as_bytes()).ok()?;
+    let mut fields = data.split(|x| *x == 0);
+    let _authzid = fields.next()?;
+    let username = fields.next()?;
+    let password = fields.next()?;
+    Some((
+        String::from_utf8_lossy(username).into_owned(),
+        String::from_utf8_lossy(password).into_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(protocol: &'static str, lines: &[&str]) -> Vec<Credential> {
+        let mut session = Session::new();
+        let mut findings = Vec::new();
+        let mut found = Found {
+            client: "192.0.2.1:40000".parse().unwrap(),
+            server: "192.0.2.2:21".parse().unwrap(),
+            protocol,
+            findings: &mut findings,
+        };
+        for line in lines {
+            command(&mut session, line, &mut found);
+        }
+        findings
+    }
+
+    fn secrets(findings: &[Credential]) -> Vec<(&str, Option<&str>, Option<&str>)> {
+        findings
+            .iter()
+            .map(|x| (x.mechanism, x.username.as_deref(), x.secret.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn mail_logins() {
+        let findings = lines("ftp", &["USER anonymous", "PASS guest@example.com"]);
+        assert_eq!(
+            secrets(&findings),
+            &[("login", Some("anonymous"), Some("guest@example.com"))]
+        );
+
+        let findings = lines("imap", &["a1 LOGIN alice \"p\\\"w d\""]);
+        assert_eq!(
+            secrets(&findings),
+            &[("login", Some("alice"), Some("p\"w d"))]
+        );
+
+        let findings = lines("imap", &["a2 AUTHENTICATE PLAIN", "AGJvYgBodW50ZXIy"]);
+        assert_eq!(
+            secrets(&findings),
+            &[("auth-plain", Some("bob"), Some("hunter2"))]
+        );
+
+        let findings = lines(
+            "smtp",
+            &["AUTH LOGIN", "*", "AUTH LOGIN Y2Fyb2w=", "c2VjcmV0"],
+        );
+        assert_eq!(
+            secrets(&findings),
+            &[("auth-login", Some("carol"), Some("secret"))]
+        );
+    }
+
+    #[test]
+    fn telnet_login() {
+        let mut session = Session::new();
+        let mut findings = Vec::new();
+        let mut found = Found {
+            client: "192.0.2.1:40000".parse().unwrap(),
+            server: "192.0.2.2:23".parse().unwrap(),
+            protocol: "telnet",
+            findings: &mut findings,
+        };
+        session.expect = Expect::TelnetUser;
+        telnet_keystrokes(&mut session, b"frank\r\n", &mut found);
+        session.expect = Expect::TelnetPassword;
+        for keystroke in ["p", "\u{e4}", "\u{df}", "\x7f", "\u{20ac}", "\r"] {
+            telnet_keystrokes(&mut session, keystroke.as_bytes(), &mut found);
+        }
+        assert_eq!(
+            secrets(&findings),
+            &[("login", Some("frank"), Some("p\u{e4}\u{20ac}"))]
+        );
+    }
+
+    #[test]
+    fn login_form() {
+        let mut findings = Vec::new();
+        let mut found = Found {
+            client: "192.0.2.1:40000".parse().unwrap(),
+            server: "192.0.2.2:80".parse().unwrap(),
+            protocol: "http",
+            findings: &mut findings,
+        };
+        form(
+            b"user=dave&pwd=a%2Bb+c&remember=1",
+            "form",
+            &None,
+            &mut found,
+        );
+        form(b"q=search", "query", &None, &mut found);
+        assert_eq!(secrets(&findings), &[("form", Some("dave"), Some("a+b c"))]);
+    }
+
+    #[test]
+    fn ntlmv1() {
+        let mut message = b"NTLMSSP\0\x03\0\0\0".to_vec();
+        let lm = [0x11; 24];
+        let nt = [0x22; 24];
+        let mut data = Vec::new();
+        for field in [&lm[..], &nt[..], b"DOM", b"erin", b""] {
+            let offset = 64 + data.len() as u32;
+            message.extend_from_slice(&(field.len() as u16).to_le_bytes());
+            message.extend_from_slice(&(field.len() as u16).to_le_bytes());
+            message.extend_from_slice(&offset.to_le_bytes());
+            data.extend_from_slice(field);
+        }
+        message.extend_from_slice(&[0; 8]);
+        message.extend_from_slice(&0u32.to_le_bytes());
+        message.extend_from_slice(&data);
+
+        let (username, response) = ntlm_response(&message, Some([0xaa; 8])).unwrap();
+        assert_eq!(username, "DOM\\erin");
+        assert_eq!(
+            response,
+            format!(
+                "erin::DOM:{}:{}:aaaaaaaaaaaaaaaa",
+                "11".repeat(24),
+                "22".repeat(24)
+            )
+        );
+    }
+}
@@ -0,0 +1,354 @@
+use crate::analysis::{self, Event, Segment, Transport, elapsed};
+use crate::bounded::BoundedMap;
+use crate::structs::dns::{DNS, QueryType, Record, Request, Response, ResponseCode};
+use crate::structs::raw::Raw;
+use crate::structs::udp::UDP;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
+
+const MAX_QUERIES: usize = 4096;
+/// Queries without a response are logged after this time
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Answered queries are kept around to detect racing replies
+const LINGER: Duration = Duration::from_secs(10);
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Transaction {
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    pub id: u16,
+    pub questions: Vec<(QueryType, String)>,
+    pub requested: SystemTime,
+    /// Missing if the query was never answered
+    pub code: Option<ResponseCode>,
+    pub answers: Vec<(String, Record)>,
+    pub response_time: Option<Duration>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Alert {
+    /// A response for a pending query arrived from an address the query wasn't sent to
+    WrongSource {
+        client: SocketAddr,
+        server: SocketAddr,
+        source: SocketAddr,
+        id: u16,
+        questions: Vec<(QueryType, String)>,
+    },
+    /// A response doesn't repeat the questions of the query it claims to answer
+    QuestionMismatch {
+        client: SocketAddr,
+        server: SocketAddr,
+        id: u16,
+        questions: Vec<(QueryType, String)>,
+        answered: Vec<(QueryType, String)>,
+    },
+    /// A query was answered twice with different answers
+    RacingReply {
+        client: SocketAddr,
+        server: SocketAddr,
+        id: u16,
+        questions: Vec<(QueryType, String)>,
+        first: Vec<(String, Record)>,
+        second: Vec<(String, Record)>,
+    },
+}
+
+/// Client, server and id
+type QueryKey = (SocketAddr, SocketAddr, u16);
+
+struct Query {
+    transaction: Transaction,
+    /// Set once the transaction was logged
+    done: Option<SystemTime>,
+}
+
+/// Matches dns responses with their queries and flags suspicious answers
+#[derive(Default)]
+pub struct DnsLog {
+    queries: BoundedMap<QueryKey, Query, MAX_QUERIES>,
+    /// The server of an unanswered query by client and id, to find the query a response from
+    /// the wrong source claims to answer
+    pending: HashMap<(SocketAddr, u16), SocketAddr>,
+    last_scan: Option<SystemTime>,
+}
+
+impl DnsLog {
+    pub fn process(&mut self, timestamp: SystemTime, packet: &Raw, events: &mut Vec<Event>) {
+        self.expire(timestamp, events);
+
+        let Some(segment) = Segment::from_raw(packet) else {
+            return;
+        };
+        let Transport::UDP(_, UDP::DNS(dns)) = segment.transport else {
+            return;
+        };
+
+        match dns {
+            DNS::Request(request) => self.query(timestamp, &segment, request),
+            DNS::Response(response) => self.response(timestamp, &segment, response, events),
+        }
+    }
+
+    fn query(&mut self, timestamp: SystemTime, segment: &Segment, request: &Request) {
+        // multicast dns and friends are answered by whoever feels responsible
+        if segment.dst.port() != 53 {
+            return;
+        }
+
+        let key = (segment.src, segment.dst, request.id);
+        if let Some(query) = self.queries.touch(timestamp, &key)
+            && query.done.is_none()
+        {
+            // retransmit, keep the time of the original query
+            return;
+        }
+
+        if let Some((evicted, _)) = self.queries.evict(&key) {
+            self.answered(evicted);
+        }
+        self.pending.insert((segment.src, request.id), segment.dst);
+        self.queries.insert(
+            timestamp,
+            key,
+            Query {
+                transaction: Transaction {
+                    client: segment.src,
+                    server: segment.dst,
+                    id: request.id,
+                    questions: request.questions.clone(),
+                    requested: timestamp,
+                    code: None,
+                    answers: Vec::new(),
+                    response_time: None,
+                },
+                done: None,
+            },
+        );
+    }
+
+    fn response(
+        &mut self,
+        timestamp: SystemTime,
+        segment: &Segment,
+        response: &Response,
+        events: &mut Vec<Event>,
+    ) {
+        let key = (segment.dst, segment.src, response.id);
+        let Some(query) = self.queries.touch(timestamp, &key) else {
+            // check if this is an answer for somebody else's pending query
+            if let Some(&server) = self.pending.get(&(segment.dst, response.id))
+                && let Some(query) = self.queries.get(&(segment.dst, server, response.id))
+            {
+                let t = &query.transaction;
+                events.push(Event::Alert(analysis::Alert::Dns(Alert::WrongSource {
+                    client: t.client,
+                    server: t.server,
+                    source: segment.src,
+                    id: t.id,
+                    questions: t.questions.clone(),
+                })));
+            }
+            return;
+        };
+        let t = &mut query.transaction;
+
+        if response.questions != t.questions {
+            events.push(Event::Alert(analysis::Alert::Dns(
+                Alert::QuestionMismatch {
+                    client: t.client,
+                    server: t.server,
+                    id: t.id,
+                    questions: t.questions.clone(),
+                    answered: response.questions.clone(),
+                },
+            )));
+            return;
+        }
+
+        if query.done.is_some() {
+            // identical duplicates are just retransmits
+            if response.answers != t.answers {
+                events.push(Event::Alert(analysis::Alert::Dns(Alert::RacingReply {
+                    client: t.client,
+                    server: t.server,
+                    id: t.id,
+                    questions: t.questions.clone(),
+                    first: t.answers.clone(),
+                    second: response.answers.clone(),
+                })));
+            }
+            return;
+        }
+
+        t.code = Some(response.code);
+        t.answers = response.answers.clone();
+        t.response_time = Some(elapsed(t.requested, timestamp));
+        query.done = Some(timestamp);
+        events.push(Event::Dns(t.clone()));
+        self.answered(key);
+    }
+
+    /// The query doesn't wait for a response anymore
+    fn answered(&mut self, (client, server, id): QueryKey) {
+        if self.pending.get(&(client, id)) == Some(&server) {
+            self.pending.remove(&(client, id));
+        }
+    }
+
+    /// Log all queries that are still waiting for a response
+    pub fn finish(&mut self, events: &mut Vec<Event>) {
+        self.pending.clear();
+        let mut transactions = self
+            .queries
+            .drain()
+            .filter(|(_, query)| query.done.is_none())
+            .map(|(_, query)| query.transaction)
+            .collect::<Vec<_>>();
+        transactions.sort_by_key(|transaction| transaction.requested);
+        events.extend(transactions.into_iter().map(Event::Dns));
+    }
+
+    fn expire(&mut self, timestamp: SystemTime, events: &mut Vec<Event>) {
+        match self.last_scan {
+            Some(last_scan) if elapsed(last_scan, timestamp) < SCAN_INTERVAL => return,
+            _ => self.last_scan = Some(timestamp),
+        }
+
+        let expired = self
+            .queries
+            .iter()
+            .filter(|(_, query)| match query.done {
+                Some(done) => elapsed(done, timestamp) >= LINGER,
+                None => elapsed(query.transaction.requested, timestamp) >= RESPONSE_TIMEOUT,
+            })
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+
+        let mut transactions = Vec::new();
+        for key in expired {
+            self.answered(key);
+            if let Some(query) = self.queries.remove(&key)
+                && query.done.is_none()
+            {
+                transactions.push(query.transaction);
+            }
+        }
+        transactions.sort_by_key(|transaction| transaction.requested);
+        events.extend(transactions.into_iter().map(Event::Dns));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fixtures;
+    use std::net::Ipv4Addr;
+
+    const CLIENT: &str = "192.168.1.23:40000";
+    const RESOLVER: &str = "192.168.1.1:53";
+
+    fn packet(src: &str, dst: &str, dns: DNS) -> Raw {
+        fixtures::udp(src, dst, UDP::DNS(dns))
+    }
+
+    fn questions(name: &str) -> Vec<(QueryType, String)> {
+        vec![(QueryType::A, name.to_string())]
+    }
+
+    fn query(id: u16, name: &str) -> Raw {
+        packet(CLIENT, RESOLVER, Request::new(id, questions(name)).wrap())
+    }
+
+    fn answer(src: &str, id: u16, name: &str, addr: &str) -> Raw {
+        let answers = vec![(name.to_string(), Record::A(addr.parse().unwrap()))];
+        let response = Response::new(id, ResponseCode::NoError, questions(name), answers);
+        packet(src, CLIENT, response.wrap())
+    }
+
+    #[test]
+    fn match_and_detect_spoofing() {
+        let mut log = DnsLog::default();
+        let mut events = Vec::new();
+        let now = SystemTime::UNIX_EPOCH;
+        let ms = Duration::from_millis;
+
+        log.process(now, &query(1, "example.com"), &mut events);
+        log.process(now, &query(2, "example.org"), &mut events);
+        log.process(
+            now + ms(5),
+            &answer("10.6.6.6:53", 1, "example.com", "10.6.6.6"),
+            &mut events,
+        );
+        log.process(
+            now + ms(20),
+            &answer(RESOLVER, 1, "example.com", "192.0.2.1"),
+            &mut events,
+        );
+        log.process(
+            now + ms(21),
+            &answer(RESOLVER, 1, "example.com", "192.0.2.1"),
+            &mut events,
+        );
+        log.process(
+            now + ms(22),
+            &answer(RESOLVER, 1, "example.com", "192.0.2.2"),
+            &mut events,
+        );
+        log.process(
+            now + RESPONSE_TIMEOUT,
+            &query(3, "example.net"),
+            &mut events,
+        );
+
+        let client = CLIENT.parse().unwrap();
+        let server = RESOLVER.parse().unwrap();
+        let answers =
+            |addr: [u8; 4]| vec![(String::from("example.com"), Record::A(Ipv4Addr::from(addr)))];
+        let resolved = Transaction {
+            client,
+            server,
+            id: 1,
+            questions: questions("example.com"),
+            requested: now,
+            code: Some(ResponseCode::NoError),
+            answers: answers([192, 0, 2, 1]),
+            response_time: Some(ms(20)),
+        };
+        let unanswered = Transaction {
+            client,
+            server,
+            id: 2,
+            questions: questions("example.org"),
+            requested: now,
+            code: None,
+            answers: Vec::new(),
+            response_time: None,
+        };
+        assert_eq!(
+            events,
+            vec![
+                Event::Alert(analysis::Alert::Dns(Alert::WrongSource {
+                    client,
+                    server,
+                    source: "10.6.6.6:53".parse().unwrap(),
+                    id: 1,
+                    questions: questions("example.com"),
+                })),
+                Event::Dns(resolved),
+                Event::Alert(analysis::Alert::Dns(Alert::RacingReply {
+                    client,
+                    server,
+                    id: 1,
+                    questions: questions("example.com"),
+                    first: answers([192, 0, 2, 1]),
+                    second: answers([192, 0, 2, 2]),
+                })),
+                Event::Dns(unanswered),
+            ]
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use crate::errors::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Client names for known ja3/ja4 fingerprints, loaded from a local file
+#[derive(Debug, Default)]
+pub struct Fingerprints {
+    names: HashMap<String, String>,
+}
+
+impl Fingerprints {
+    /// Each line contains a fingerprint, followed by whitespace and the name of the client
+    pub fn parse(text: &str) -> Result<Fingerprints> {
+        let mut names = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((fingerprint, name)) = line.split_once(char::is_whitespace) else {
+                bail!("Line {}: Expected a fingerprint and a name", i + 1);
+            };
+            names.insert(fingerprint.to_lowercase(), name.trim().to_string());
+        }
+        Ok(Fingerprints { names })
+    }
+
+    pub fn load(path: &Path) -> Result<Fingerprints> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read tls fingerprints: {:?}", path))?;
+        Fingerprints::parse(&text)
+            .with_context(|| format!("Failed to parse tls fingerprints: {:?}", path))
+    }
+
+    #[inline]
+    pub fn lookup(&self, fingerprint: &str) -> Option<&str> {
+        self.names.get(fingerprint).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let fingerprints = Fingerprints::parse(
+            "# comment\n\
+             t13d1516h2_8daaf6152771_02713d6af862\tChrome 120\n\
+             E7D705A3286E19EA42F587B344EE6865  curl 7.x\n",
+        )
+        .unwrap();
+        assert_eq!(
+            fingerprints.lookup("t13d1516h2_8daaf6152771_02713d6af862"),
+            Some("Chrome 120")
+        );
+        assert_eq!(
+            fingerprints.lookup("e7d705a3286e19ea42f587b344ee6865"),
+            Some("curl 7.x")
+        );
+        assert!(Fingerprints::parse("e7d705a3286e19ea42f587b344ee6865\n").is_err());
+    }
+}
@@ -0,0 +1,441 @@
+use crate::analysis::{Event, Segment, Transport, elapsed};
+use crate::bounded::BoundedMap;
+use crate::structs::dns::DNS;
+use crate::structs::http::Http;
+use crate::structs::raw::Raw;
+use crate::structs::tcp::TCP;
+use crate::structs::tls::TLS;
+use crate::structs::udp::UDP;
+use pktparse::tcp::TcpHeader;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
+
+const MAX_FLOWS: usize = 16384;
+const TCP_TIMEOUT: Duration = Duration::from_secs(300);
+const UDP_TIMEOUT: Duration = Duration::from_secs(60);
+/// Closed flows are kept around for a bit to absorb trailing acks and retransmits
+const LINGER: Duration = Duration::from_secs(10);
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Protocol {
+    TCP,
+    UDP,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum TcpState {
+    SynSent,
+    SynReceived,
+    Established,
+    Closing,
+    Closed,
+    Reset,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Application {
+    TLS(Option<String>),
+    QUIC(Option<String>),
+    HTTP(Option<String>),
+    DNS(Option<String>),
+}
+
+impl Application {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Application::TLS(_) => "tls",
+            Application::QUIC(_) => "quic",
+            Application::HTTP(_) => "http",
+            Application::DNS(_) => "dns",
+        }
+    }
+
+    pub fn hostname(&self) -> Option<&str> {
+        match *self {
+            Application::TLS(ref name)
+            | Application::QUIC(ref name)
+            | Application::HTTP(ref name)
+            | Application::DNS(ref name) => name.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum EndReason {
+    Closed,
+    Reset,
+    Timeout,
+    Evicted,
+    Active,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Flow {
+    pub protocol: Protocol,
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    pub first_seen: SystemTime,
+    pub last_seen: SystemTime,
+    pub client_packets: u64,
+    pub client_bytes: u64,
+    pub server_packets: u64,
+    pub server_bytes: u64,
+    pub tcp_state: Option<TcpState>,
+    pub application: Option<Application>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Record {
+    pub reason: EndReason,
+    pub flow: Flow,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Summary {
+    pub flows: u64,
+    pub tcp: u64,
+    pub udp: u64,
+    pub packets: u64,
+    pub bytes: u64,
+    pub applications: BTreeMap<&'static str, u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FlowKey {
+    protocol: Protocol,
+    a: SocketAddr,
+    b: SocketAddr,
+}
+
+impl FlowKey {
+    fn new(protocol: Protocol, src: SocketAddr, dst: SocketAddr) -> FlowKey {
+        let (a, b) = if src <= dst { (src, dst) } else { (dst, src) };
+        FlowKey { protocol, a, b }
+    }
+}
+
+struct Entry {
+    flow: Flow,
+    client_fin: bool,
+    server_fin: bool,
+    ended: bool,
+}
+
+impl Entry {
+    fn new(
+        timestamp: SystemTime,
+        protocol: Protocol,
+        client: SocketAddr,
+        server: SocketAddr,
+    ) -> Entry {
+        Entry {
+            flow: Flow {
+                protocol,
+                client,
+                server,
+                first_seen: timestamp,
+                last_seen: timestamp,
+                client_packets: 0,
+                client_bytes: 0,
+                server_packets: 0,
+                server_bytes: 0,
+                tcp_state: None,
+                application: None,
+            },
+            client_fin: false,
+            server_fin: false,
+            ended: false,
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        if self.ended {
+            LINGER
+        } else if self.flow.protocol == Protocol::TCP {
+            TCP_TIMEOUT
+        } else {
+            UDP_TIMEOUT
+        }
+    }
+
+    /// Returns a reason if this packet ended the flow
+    fn update_tcp(&mut self, tcp_hdr: &TcpHeader, from_client: bool) -> Option<EndReason> {
+        let state = self.flow.tcp_state;
+        let next = if tcp_hdr.flag_rst {
+            TcpState::Reset
+        } else if tcp_hdr.flag_fin {
+            if from_client {
+                self.client_fin = true;
+            } else {
+                self.server_fin = true;
+            }
+            if self.client_fin && self.server_fin {
+                TcpState::Closed
+            } else {
+                TcpState::Closing
+            }
+        } else if tcp_hdr.flag_syn {
+            if tcp_hdr.flag_ack {
+                TcpState::SynReceived
+            } else {
+                TcpState::SynSent
+            }
+        } else {
+            match state {
+                Some(TcpState::Closing) => TcpState::Closing,
+                _ => TcpState::Established,
+            }
+        };
+        self.flow.tcp_state = Some(next);
+
+        match next {
+            TcpState::Reset => Some(EndReason::Reset),
+            TcpState::Closed => Some(EndReason::Closed),
+            _ => None,
+        }
+    }
+
+    fn identify(&mut self, transport: &Transport) {
+        let application = match transport {
+            Transport::TCP(_, TCP::TLS(TLS::ClientHello(ch))) => {
+                Application::TLS(ch.hostname.clone())
+            }
+            Transport::TCP(_, TCP::TLS(_)) => Application::TLS(None),
+            Transport::TCP(_, TCP::HTTP(Http::Request(req))) => Application::HTTP(req.host.clone()),
+            Transport::TCP(_, TCP::HTTP(_)) => Application::HTTP(None),
+            Transport::UDP(_, UDP::QUIC(quic)) => Application::QUIC(
+                quic.client_hello
+                    .as_ref()
+                    .and_then(|ch| ch.hostname.clone()),
+            ),
+            Transport::UDP(_, UDP::DNS(DNS::Request(req))) => {
+                Application::DNS(req.questions.first().map(|(_, name)| name.clone()))
+            }
+            Transport::UDP(_, UDP::DNS(_)) => Application::DNS(None),
+            _ => return,
+        };
+
+        // keep the first hostname we've seen
+        match &self.flow.application {
+            Some(known) if known.hostname().is_some() || application.hostname().is_none() => (),
+            _ => self.flow.application = Some(application),
+        }
+    }
+}
+
+/// Bidirectional flow table, emits a record when a flow ends or times out
+#[derive(Default)]
+pub struct FlowTable {
+    flows: BoundedMap<FlowKey, Entry, MAX_FLOWS>,
+    last_scan: Option<SystemTime>,
+    summary: Summary,
+}
+
+impl FlowTable {
+    pub fn process(&mut self, timestamp: SystemTime, packet: &Raw, events: &mut Vec<Event>) {
+        self.expire(timestamp, events);
+
+        let Some(segment) = Segment::from_raw(packet) else {
+            return;
+        };
+
+        let protocol = match segment.transport {
+            Transport::TCP(..) => Protocol::TCP,
+            Transport::UDP(..) => Protocol::UDP,
+        };
+        let key = FlowKey::new(protocol, segment.src, segment.dst);
+
+        let is_syn = matches!(segment.transport, Transport::TCP(tcp_hdr, _) if tcp_hdr.flag_syn && !tcp_hdr.flag_ack);
+        let reused = self.flows.get(&key).is_some_and(|e| e.ended && is_syn);
+        if reused {
+            self.flows.remove(&key);
+        }
+
+        if !self.flows.contains_key(&key) {
+            if let Some((_, evicted)) = self.flows.evict(&key)
+                && !evicted.ended
+            {
+                self.emit(EndReason::Evicted, evicted.flow, events);
+            }
+
+            // if we only see the syn/ack the receiver is the client
+            let (client, server) = match segment.transport {
+                Transport::TCP(tcp_hdr, _) if tcp_hdr.flag_syn && tcp_hdr.flag_ack => {
+                    (segment.dst, segment.src)
+                }
+                _ => (segment.src, segment.dst),
+            };
+            self.flows.insert(
+                timestamp,
+                key.clone(),
+                Entry::new(timestamp, protocol, client, server),
+            );
+
+            self.summary.flows += 1;
+            match protocol {
+                Protocol::TCP => self.summary.tcp += 1,
+                Protocol::UDP => self.summary.udp += 1,
+            }
+        }
+
+        let Some(entry) = self.flows.touch(timestamp, &key) else {
+            return;
+        };
+        self.summary.packets += 1;
+        self.summary.bytes += segment.length;
+
+        let from_client = entry.flow.client == segment.src;
+        if from_client {
+            entry.flow.client_packets += 1;
+            entry.flow.client_bytes += segment.length;
+        } else {
+            entry.flow.server_packets += 1;
+            entry.flow.server_bytes += segment.length;
+        }
+        if entry.flow.last_seen < timestamp {
+            entry.flow.last_seen = timestamp;
+        }
+
+        if entry.ended {
+            return;
+        }
+
+        entry.identify(&segment.transport);
+
+        if let Transport::TCP(tcp_hdr, _) = segment.transport
+            && let Some(reason) = entry.update_tcp(tcp_hdr, from_client)
+        {
+            entry.ended = true;
+            let flow = entry.flow.clone();
+            self.emit(reason, flow, events);
+        }
+    }
+
+    /// Flush all flows that are still active and return the summary
+    pub fn finish(&mut self, events: &mut Vec<Event>) {
+        let mut flows = self
+            .flows
+            .drain()
+            .map(|(_, e)| e)
+            .filter(|e| !e.ended)
+            .map(|e| e.flow)
+            .collect::<Vec<_>>();
+        flows.sort_by_key(|flow| flow.first_seen);
+        for flow in flows {
+            self.emit(EndReason::Active, flow, events);
+        }
+        events.push(Event::FlowSummary(std::mem::take(&mut self.summary)));
+    }
+
+    fn emit(&mut self, reason: EndReason, flow: Flow, events: &mut Vec<Event>) {
+        if let Some(application) = &flow.application {
+            *self
+                .summary
+                .applications
+                .entry(application.name())
+                .or_default() += 1;
+        }
+        events.push(Event::Flow(Record { reason, flow }));
+    }
+
+    fn expire(&mut self, timestamp: SystemTime, events: &mut Vec<Event>) {
+        match self.last_scan {
+            Some(last_scan) if elapsed(last_scan, timestamp) < SCAN_INTERVAL => return,
+            _ => self.last_scan = Some(timestamp),
+        }
+
+        let expired = self
+            .flows
+            .iter()
+            .filter(|(_, e)| elapsed(e.flow.last_seen, timestamp) >= e.timeout())
+            .map(|(k, _)| k.clone())
+            .collect::<Vec<_>>();
+
+        let mut flows = Vec::new();
+        for key in expired {
+            if let Some(entry) = self.flows.remove(&key)
+                && !entry.ended
+            {
+                flows.push(entry.flow);
+            }
+        }
+        flows.sort_by_key(|flow| flow.first_seen);
+        for flow in flows {
+            self.emit(EndReason::Timeout, flow, events);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fixtures;
+
+    const CLIENT: &str = "192.168.1.23:40000";
+    const SERVER: &str = "192.0.2.1:80";
+
+    fn segment(src: &str, dst: &str, flags: &str) -> Raw {
+        fixtures::tcp(src, dst, flags, TCP::Empty)
+    }
+
+    fn records(events: &[Event]) -> Vec<(EndReason, SocketAddr, u64, u64)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Flow(record) => Some((
+                    record.reason,
+                    record.flow.client,
+                    record.flow.client_packets,
+                    record.flow.server_packets,
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tcp_lifecycle() {
+        let mut table = FlowTable::default();
+        let mut events = Vec::new();
+        let now = SystemTime::UNIX_EPOCH;
+
+        table.process(now, &segment(CLIENT, SERVER, "S"), &mut events);
+        table.process(now, &segment(SERVER, CLIENT, "SA"), &mut events);
+        table.process(now, &segment(CLIENT, SERVER, "A"), &mut events);
+        table.process(now, &segment(CLIENT, SERVER, "FA"), &mut events);
+        assert_eq!(records(&events), vec![]);
+
+        table.process(now, &segment(SERVER, CLIENT, "FA"), &mut events);
+        // the final ack is counted but doesn't create a new flow
+        table.process(now, &segment(CLIENT, SERVER, "A"), &mut events);
+        let client = CLIENT.parse().unwrap();
+        assert_eq!(records(&events), vec![(EndReason::Closed, client, 3, 2)]);
+
+        // a flow we only see the syn/ack of is attributed to the receiver
+        events.clear();
+        table.process(now, &segment("192.0.2.1:443", CLIENT, "SA"), &mut events);
+        let later = now + TCP_TIMEOUT;
+        table.process(
+            later,
+            &segment(SERVER, "192.168.1.99:1234", "R"),
+            &mut events,
+        );
+        let server = SERVER.parse().unwrap();
+        assert_eq!(
+            records(&events),
+            vec![
+                (EndReason::Timeout, client, 0, 1),
+                (EndReason::Reset, server, 1, 0),
+            ]
+        );
+
+        table.finish(&mut events);
+        let Some(Event::FlowSummary(summary)) = events.last() else {
+            panic!("missing summary");
+        };
+        assert_eq!((summary.flows, summary.tcp, summary.packets), (3, 3, 8));
+    }
+}
@@ -0,0 +1,289 @@
+use crate::analysis::{Event, Segment, Transport, elapsed};
+use crate::bounded::BoundedMap;
+use crate::structs::http::{Http, Request, Response};
+use crate::structs::raw::Raw;
+use crate::structs::tcp::TCP;
+use bstr::ByteSlice;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
+
+const MAX_CONNECTIONS: usize = 4096;
+/// Pipelined requests that are still waiting for a response, per connection
+const MAX_PENDING: usize = 32;
+/// Requests without a response are logged after this time
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Transaction {
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    pub method: String,
+    pub host: Option<String>,
+    pub path: String,
+    pub requested: SystemTime,
+    /// Missing if the request was never answered
+    pub status: Option<u16>,
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    /// Time between the request and the first packet of the response
+    pub ttfb: Option<Duration>,
+}
+
+impl Transaction {
+    fn new(timestamp: SystemTime, segment: &Segment, request: &Request) -> Transaction {
+        Transaction {
+            client: segment.src,
+            server: segment.dst,
+            method: request.method.clone(),
+            host: request.host.clone(),
+            path: request.path.clone(),
+            requested: timestamp,
+            status: None,
+            content_type: None,
+            content_length: None,
+            ttfb: None,
+        }
+    }
+
+    fn answer(&mut self, timestamp: SystemTime, response: &Response) {
+        self.status = Some(response.code);
+        self.content_type = response
+            .header("content-type")
+            .map(|value| value.to_str_lossy().into_owned());
+        self.content_length = response
+            .header("content-length")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok());
+        self.ttfb = Some(elapsed(self.requested, timestamp));
+    }
+}
+
+#[derive(Default)]
+struct Connection {
+    pending: VecDeque<Transaction>,
+    /// Sequence numbers of the last request and response, to ignore retransmissions
+    request_seq: Option<u32>,
+    response_seq: Option<u32>,
+}
+
+/// Pairs http responses with the requests on the same connection
+#[derive(Default)]
+pub struct HttpLog {
+    connections: BoundedMap<(SocketAddr, SocketAddr), Connection, MAX_CONNECTIONS>,
+    last_scan: Option<SystemTime>,
+}
+
+impl HttpLog {
+    pub fn process(&mut self, timestamp: SystemTime, packet: &Raw, events: &mut Vec<Event>) {
+        self.expire(timestamp, events);
+
+        let Some(segment) = Segment::from_raw(packet) else {
+            return;
+        };
+        let Transport::TCP(header, TCP::HTTP(http)) = segment.transport else {
+            return;
+        };
+
+        match http {
+            Http::Request(request) => {
+                let key = (segment.src, segment.dst);
+                if let Some((_, evicted)) = self.connections.evict(&key) {
+                    events.extend(evicted.pending.into_iter().map(Event::Http));
+                }
+                let connection =
+                    self.connections
+                        .touch_or_insert_with(timestamp, key, Connection::default);
+                if connection.request_seq == Some(header.sequence_no) {
+                    return;
+                }
+                connection.request_seq = Some(header.sequence_no);
+
+                if connection.pending.len() >= MAX_PENDING
+                    && let Some(transaction) = connection.pending.pop_front()
+                {
+                    events.push(Event::Http(transaction));
+                }
+                connection
+                    .pending
+                    .push_back(Transaction::new(timestamp, &segment, request));
+            }
+            Http::Response(response) => {
+                let key = (segment.dst, segment.src);
+                let Some(connection) = self.connections.touch(timestamp, &key) else {
+                    // the request happened before we started listening
+                    return;
+                };
+                if connection.response_seq == Some(header.sequence_no) {
+                    return;
+                }
+                connection.response_seq = Some(header.sequence_no);
+
+                // informational responses are followed by the actual response
+                if (100..200).contains(&response.code) && response.code != 101 {
+                    return;
+                }
+
+                if let Some(mut transaction) = connection.pending.pop_front() {
+                    transaction.answer(timestamp, response);
+                    events.push(Event::Http(transaction));
+                }
+            }
+        }
+    }
+
+    /// Log all requests that are still waiting for a response
+    pub fn finish(&mut self, events: &mut Vec<Event>) {
+        let mut transactions = self
+            .connections
+            .drain()
+            .flat_map(|(_, connection)| connection.pending)
+            .collect::<Vec<_>>();
+        transactions.sort_by_key(|transaction| transaction.requested);
+        events.extend(transactions.into_iter().map(Event::Http));
+    }
+
+    fn expire(&mut self, timestamp: SystemTime, events: &mut Vec<Event>) {
+        match self.last_scan {
+            Some(last_scan) if elapsed(last_scan, timestamp) < SCAN_INTERVAL => return,
+            _ => self.last_scan = Some(timestamp),
+        }
+
+        // idle connections are kept to recognize retransmissions until they're evicted
+        let mut transactions = Vec::new();
+        for connection in self.connections.values_mut() {
+            while connection
+                .pending
+                .front()
+                .is_some_and(|t| elapsed(t.requested, timestamp) >= RESPONSE_TIMEOUT)
+            {
+                transactions.extend(connection.pending.pop_front());
+            }
+        }
+        transactions.sort_by_key(|transaction| transaction.requested);
+        events.extend(transactions.into_iter().map(Event::Http));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fixtures;
+    use crate::structs::ether::Ether;
+    use crate::structs::ipv4::IPv4;
+
+    const CLIENT: &str = "192.168.1.23:40000";
+    const SERVER: &str = "192.0.2.1:80";
+
+    fn packet(src: &str, dst: &str, seq: u32, http: Http) -> Raw {
+        let mut packet = fixtures::tcp(src, dst, "PA", TCP::HTTP(http));
+        if let Raw::Tun(Ether::IPv4(_, IPv4::TCP(header, ..))) = &mut packet {
+            header.sequence_no = seq;
+        }
+        packet
+    }
+
+    fn request(path: &str, seq: u32) -> Raw {
+        packet(
+            CLIENT,
+            SERVER,
+            seq,
+            Http::Request(Request {
+                method: String::from("GET"),
+                path: path.to_string(),
+                version: 1,
+                headers: Vec::new(),
+                host: Some(String::from("example.com")),
+                agent: None,
+                referer: None,
+                auth: None,
+                cookies: None,
+                body: None,
+                decoded: Default::default(),
+            }),
+        )
+    }
+
+    fn response(code: u16, length: &str, seq: u32) -> Raw {
+        packet(
+            SERVER,
+            CLIENT,
+            seq,
+            Http::Response(Response {
+                code,
+                reason: String::new(),
+                version: 1,
+                headers: vec![(String::from("Content-Length"), length.into())],
+                body: None,
+                decoded: Default::default(),
+            }),
+        )
+    }
+
+    type Row<'a> = (&'a str, Option<u16>, Option<u64>, Option<Duration>);
+
+    fn transactions(events: &[Event]) -> Vec<Row<'_>> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Http(t) => Some((t.path.as_str(), t.status, t.content_length, t.ttfb)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pair_pipelined_requests() {
+        let mut log = HttpLog::default();
+        let mut events = Vec::new();
+        let now = SystemTime::UNIX_EPOCH;
+        let ms = Duration::from_millis;
+
+        log.process(now, &request("/a", 1), &mut events);
+        log.process(now + ms(10), &request("/b", 100), &mut events);
+        log.process(now + ms(20), &response(100, "0", 1), &mut events);
+        log.process(now + ms(30), &response(200, "1234", 50), &mut events);
+        log.process(now + ms(50), &response(404, "x", 300), &mut events);
+        // a response we never saw the request of
+        log.process(now + ms(60), &response(200, "1", 400), &mut events);
+        log.process(now + ms(70), &request("/c", 200), &mut events);
+        log.finish(&mut events);
+
+        assert_eq!(
+            transactions(&events),
+            vec![
+                ("/a", Some(200), Some(1234), Some(ms(30))),
+                ("/b", Some(404), None, Some(ms(40))),
+                ("/c", None, None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignore_retransmissions() {
+        let mut log = HttpLog::default();
+        let mut events = Vec::new();
+        let now = SystemTime::UNIX_EPOCH;
+        let ms = Duration::from_millis;
+
+        log.process(now, &request("/a", 1), &mut events);
+        log.process(now + ms(200), &request("/a", 1), &mut events);
+        log.process(now + ms(210), &request("/b", 100), &mut events);
+        log.process(now + ms(220), &response(200, "10", 1), &mut events);
+        log.process(now + ms(420), &response(200, "10", 1), &mut events);
+        log.process(now + ms(430), &response(404, "0", 200), &mut events);
+        // the request is sent again after it has been answered
+        log.process(now + ms(500), &request("/b", 100), &mut events);
+        log.finish(&mut events);
+
+        assert_eq!(
+            transactions(&events),
+            vec![
+                ("/a", Some(200), Some(10), Some(ms(220))),
+                ("/b", Some(404), Some(0), Some(ms(220))),
+            ]
+        );
+    }
+}
@@ -0,0 +1,455 @@
+use crate::analysis::{Event, Segment, Transport, elapsed, osfp, oui};
+use crate::bounded::BoundedMap;
+use crate::structs::arp::ARP;
+use crate::structs::dhcp::DHCP;
+use crate::structs::dns::{DNS, Record};
+use crate::structs::ether::Ether;
+use crate::structs::http::Http;
+use crate::structs::nbns::NBNS;
+use crate::structs::raw::Raw;
+use crate::structs::tcp::TCP;
+use crate::structs::tls::TLS;
+use crate::structs::udp::UDP;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+const MAX_HOSTS: usize = 4096;
+/// Limit for each list of a host, eg. user agents
+const MAX_VALUES: usize = 32;
+const REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Host {
+    pub mac: Option<[u8; 6]>,
+    pub vendor: Option<&'static str>,
+    pub ips: BTreeSet<IpAddr>,
+    pub hostnames: BTreeSet<String>,
+    /// The dhcp parameter request list (option 55), eg. "1,3,6,15,119,252"
+    pub dhcp_fingerprint: Option<String>,
+    /// Guessed from the tcp syn and syn/ack packets it sent
+    pub os: Option<String>,
+    pub user_agents: BTreeSet<String>,
+    pub snis: BTreeSet<String>,
+    /// Tcp ports that answered with a syn/ack
+    pub services: BTreeSet<u16>,
+    pub first_seen: SystemTime,
+    pub last_seen: SystemTime,
+}
+
+impl Host {
+    fn new(timestamp: SystemTime, mac: Option<[u8; 6]>) -> Host {
+        Host {
+            mac,
+            vendor: mac.as_ref().and_then(oui::vendor),
+            ips: BTreeSet::new(),
+            hostnames: BTreeSet::new(),
+            dhcp_fingerprint: None,
+            os: None,
+            user_agents: BTreeSet::new(),
+            snis: BTreeSet::new(),
+            services: BTreeSet::new(),
+            first_seen: timestamp,
+            last_seen: timestamp,
+        }
+    }
+
+    /// Merge a host that was only known by ip into this one
+    fn merge(&mut self, other: Host) {
+        for ip in other.ips {
+            insert(&mut self.ips, ip);
+        }
+        for hostname in other.hostnames {
+            insert(&mut self.hostnames, hostname);
+        }
+        for agent in other.user_agents {
+            insert(&mut self.user_agents, agent);
+        }
+        for sni in other.snis {
+            insert(&mut self.snis, sni);
+        }
+        for port in other.services {
+            insert(&mut self.services, port);
+        }
+        if self.os.is_none() {
+            self.os = other.os;
+        }
+        self.first_seen = self.first_seen.min(other.first_seen);
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Report {
+    pub hosts: Vec<Host>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Key {
+    Mac([u8; 6]),
+    /// Hosts we don't know the mac of, eg. on a tun interface
+    Ip(IpAddr),
+}
+
+/// Aggregates everything we learn about the hosts of the local network
+#[derive(Default)]
+pub struct Inventory {
+    hosts: BoundedMap<Key, Host, MAX_HOSTS>,
+    by_ip: HashMap<IpAddr, Key>,
+    changed: bool,
+    last_report: Option<SystemTime>,
+    os: Arc<osfp::Database>,
+}
+
+impl Inventory {
+    pub fn new(os: Arc<osfp::Database>) -> Inventory {
+        Inventory {
+            os,
+            ..Default::default()
+        }
+    }
+
+    pub fn process(&mut self, timestamp: SystemTime, packet: &Raw, events: &mut Vec<Event>) {
+        self.learn(timestamp, packet);
+
+        match self.last_report {
+            None => self.last_report = Some(timestamp),
+            Some(last_report) if elapsed(last_report, timestamp) < REPORT_INTERVAL => (),
+            Some(_) => {
+                self.last_report = Some(timestamp);
+                if self.changed {
+                    events.push(Event::Inventory(self.report()));
+                }
+            }
+        }
+    }
+
+    pub fn finish(&mut self, events: &mut Vec<Event>) {
+        events.push(Event::Inventory(self.report()));
+    }
+
+    pub fn report(&mut self) -> Report {
+        self.changed = false;
+
+        let mut hosts = self.hosts.values().cloned().collect::<Vec<_>>();
+        hosts.sort_by(|a, b| (a.ips.first(), a.mac).cmp(&(b.ips.first(), b.mac)));
+        Report { hosts }
+    }
+
+    fn learn(&mut self, timestamp: SystemTime, packet: &Raw) {
+        let mac = match packet {
+            Raw::Ether(frame, _) => Some(frame.source_mac.0),
+            _ => None,
+        };
+
+        match packet.ether() {
+            Some(Ether::Arp(ARP::Request(arp) | ARP::Reply(arp))) => {
+                // arp probes are sent before an address is configured
+                if !arp.src_addr.is_unspecified() {
+                    self.link(timestamp, arp.src_mac.0, IpAddr::V4(arp.src_addr));
+                }
+                return;
+            }
+            Some(Ether::IPv4(..) | Ether::IPv6(..)) => (),
+            _ => return,
+        }
+
+        let Some(segment) = Segment::from_raw(packet) else {
+            return;
+        };
+        let src = segment.src.ip();
+        // the source mac belongs to the router if the sender isn't local
+        let mac = mac.filter(|_| is_local(&src));
+        let db = self.os.clone();
+        let os = packet.ether().and_then(|ether| db.guess(ether));
+        if let Some(host) = self.host(timestamp, src, mac) {
+            host.last_seen = timestamp;
+            if let Some(os) = os
+                && host.os.as_deref() != Some(os)
+            {
+                host.os = Some(os.to_string());
+                self.changed = true;
+            }
+        }
+
+        match segment.transport {
+            Transport::UDP(_, UDP::DHCP(dhcp)) => self.dhcp(timestamp, dhcp),
+            Transport::UDP(_, UDP::NBNS(nbns)) => {
+                let (NBNS::Response(name, addrs)
+                | NBNS::Registration(name, addrs)
+                | NBNS::Refresh(name, addrs)) = nbns
+                else {
+                    return;
+                };
+                if !name.is_host() {
+                    return;
+                }
+                for addr in addrs {
+                    let addr = IpAddr::V4(*addr);
+                    let mac = mac.filter(|_| addr == src);
+                    if let Some(host) = self.host(timestamp, addr, mac) {
+                        self.changed |= insert(&mut host.hostnames, name.name.clone());
+                    }
+                }
+            }
+            Transport::UDP(udp_hdr, UDP::DNS(DNS::Response(response)))
+                if udp_hdr.source_port == 5353 =>
+            {
+                for (name, record) in &response.answers {
+                    let addr = match *record {
+                        Record::A(addr) => IpAddr::V4(addr),
+                        Record::AAAA(addr) => IpAddr::V6(addr),
+                        _ => continue,
+                    };
+                    let Some(name) = name.strip_suffix(".local") else {
+                        continue;
+                    };
+                    let mac = mac.filter(|_| addr == src);
+                    if let Some(host) = self.host(timestamp, addr, mac) {
+                        self.changed |= insert(&mut host.hostnames, name.to_string());
+                    }
+                }
+            }
+            Transport::TCP(_, TCP::HTTP(Http::Request(request))) => {
+                if let Some(agent) = &request.agent
+                    && let Some(host) = self.host(timestamp, src, mac)
+                {
+                    self.changed |= insert(&mut host.user_agents, agent.clone());
+                }
+            }
+            Transport::TCP(_, TCP::TLS(TLS::ClientHello(client_hello))) => {
+                if let Some(hostname) = &client_hello.hostname
+                    && let Some(host) = self.host(timestamp, src, mac)
+                {
+                    self.changed |= insert(&mut host.snis, hostname.clone());
+                }
+            }
+            Transport::UDP(_, UDP::QUIC(quic)) => {
+                if let Some(hostname) = quic
+                    .client_hello
+                    .as_ref()
+                    .and_then(|ch| ch.hostname.as_ref())
+                    && let Some(host) = self.host(timestamp, src, mac)
+                {
+                    self.changed |= insert(&mut host.snis, hostname.clone());
+                }
+            }
+            Transport::TCP(tcp_hdr, _) if tcp_hdr.flag_syn && tcp_hdr.flag_ack => {
+                if let Some(host) = self.host(timestamp, src, mac) {
+                    self.changed |= insert(&mut host.services, tcp_hdr.source_port);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn dhcp(&mut self, timestamp: SystemTime, dhcp: &DHCP) {
+        match dhcp {
+            DHCP::DISCOVER(packet) | DHCP::REQUEST(packet) | DHCP::INFORM(packet) => {
+                let host = self.host_by_mac(timestamp, packet.chaddr);
+                let mut changed = false;
+                if let Some(hostname) = &packet.hostname {
+                    changed |= insert(&mut host.hostnames, hostname.clone());
+                }
+                if let Some(list) = &packet.parameter_request_list {
+                    let fingerprint = list
+                        .iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    if host.dhcp_fingerprint.as_ref() != Some(&fingerprint) {
+                        host.dhcp_fingerprint = Some(fingerprint);
+                        changed = true;
+                    }
+                }
+                self.changed |= changed;
+            }
+            DHCP::ACK(packet) if packet.yiaddr != Ipv4Addr::UNSPECIFIED => {
+                self.link(timestamp, packet.chaddr, IpAddr::V4(packet.yiaddr));
+            }
+            _ => (),
+        }
+    }
+
+    fn host_by_mac(&mut self, timestamp: SystemTime, mac: [u8; 6]) -> &mut Host {
+        let key = Key::Mac(mac);
+        if !self.hosts.contains_key(&key) {
+            self.evict(&key);
+            self.changed = true;
+        }
+        self.hosts
+            .touch_or_insert_with(timestamp, key, || Host::new(timestamp, Some(mac)))
+    }
+
+    /// Record that an ip address belongs to a mac address
+    fn link(&mut self, timestamp: SystemTime, mac: [u8; 6], ip: IpAddr) {
+        let key = Key::Mac(mac);
+        match self.by_ip.get(&ip) {
+            Some(known) if *known == key => return,
+            Some(Key::Ip(_)) => {
+                // we've learned the mac of a host that was only known by its ip
+                let other = self.hosts.remove(&Key::Ip(ip));
+                let host = self.host_by_mac(timestamp, mac);
+                if let Some(other) = other {
+                    host.merge(other);
+                }
+            }
+            Some(previous) => {
+                // the address moved to a different host, eg. after the dhcp lease expired
+                if let Some(host) = self.hosts.get_mut(previous) {
+                    host.ips.remove(&ip);
+                }
+            }
+            None => (),
+        }
+
+        let host = self.host_by_mac(timestamp, mac);
+        insert(&mut host.ips, ip);
+        host.last_seen = timestamp;
+        self.by_ip.insert(ip, key);
+        self.changed = true;
+    }
+
+    /// Lookup the host for an address, only local addresses are added to the inventory
+    fn host(
+        &mut self,
+        timestamp: SystemTime,
+        ip: IpAddr,
+        mac: Option<[u8; 6]>,
+    ) -> Option<&mut Host> {
+        if let Some(mac) = mac {
+            self.link(timestamp, mac, ip);
+        }
+
+        let key = match self.by_ip.get(&ip) {
+            Some(key) => *key,
+            None if is_local(&ip) => {
+                let key = Key::Ip(ip);
+                self.evict(&key);
+                let mut host = Host::new(timestamp, None);
+                host.ips.insert(ip);
+                self.hosts.insert(timestamp, key, host);
+                self.by_ip.insert(ip, key);
+                self.changed = true;
+                key
+            }
+            None => return None,
+        };
+        self.hosts.touch(timestamp, &key)
+    }
+
+    /// The addresses of an evicted host are forgotten as well
+    fn evict(&mut self, key: &Key) {
+        if let Some((_, host)) = self.hosts.evict(key) {
+            for ip in host.ips {
+                self.by_ip.remove(&ip);
+            }
+        }
+    }
+}
+
+/// Insert a value into a bounded set, returns true if the set changed
+fn insert<T: Ord>(set: &mut BTreeSet<T>, value: T) -> bool {
+    if set.len() >= MAX_VALUES {
+        false
+    } else {
+        set.insert(value)
+    }
+}
+
+fn is_local(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_link_local(),
+        IpAddr::V6(ip) => ip.is_unicast_link_local() || ip.is_unique_local(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fixtures;
+    use pktparse::arp::{ArpPacket, HardwareAddressType, Operation, ProtocolAddressType};
+    use pktparse::ethernet::{EtherType, EthernetFrame, MacAddress};
+
+    const MAC: [u8; 6] = [0xb8, 0x27, 0xeb, 0x11, 0x22, 0x33];
+
+    fn syn_ack(src: &str, port: u16) -> Raw {
+        let src = format!("{}:{}", src, port);
+        fixtures::tcp(&src, "192.168.1.23:40000", "SA", TCP::Empty)
+    }
+
+    fn arp_reply(ip: &str, mac: [u8; 6]) -> Raw {
+        Raw::Ether(
+            EthernetFrame {
+                source_mac: MacAddress(mac),
+                dest_mac: MacAddress([0xff; 6]),
+                ethertype: EtherType::ARP,
+            },
+            Ether::Arp(ARP::Reply(ArpPacket {
+                hw_addr_type: HardwareAddressType::Ethernet,
+                proto_addr_type: ProtocolAddressType::IPv4,
+                hw_addr_size: 6,
+                proto_addr_size: 4,
+                operation: Operation::Reply,
+                src_mac: MacAddress(mac),
+                src_addr: ip.parse().unwrap(),
+                dest_mac: MacAddress([0xff; 6]),
+                dest_addr: "192.168.1.23".parse().unwrap(),
+            })),
+        )
+    }
+
+    #[test]
+    fn merge_hosts() {
+        let mut inventory = Inventory::default();
+        let mut events = Vec::new();
+        let now = SystemTime::UNIX_EPOCH;
+
+        inventory.process(now, &syn_ack("192.168.1.50", 22), &mut events);
+        // not part of the local network
+        inventory.process(now, &syn_ack("192.0.2.1", 443), &mut events);
+        inventory.process(now, &arp_reply("192.168.1.50", MAC), &mut events);
+        inventory.process(now, &syn_ack("192.168.1.50", 80), &mut events);
+        assert_eq!(events, vec![]);
+
+        inventory.finish(&mut events);
+        let [Event::Inventory(report)] = &events[..] else {
+            panic!("expected a single report");
+        };
+        assert_eq!(report.hosts.len(), 1);
+
+        let host = &report.hosts[0];
+        assert_eq!(host.mac, Some(MAC));
+        assert_eq!(host.vendor, Some("Raspberry Pi"));
+        assert_eq!(host.ips, BTreeSet::from(["192.168.1.50".parse().unwrap()]));
+        assert_eq!(host.services, BTreeSet::from([22, 80]));
+    }
+
+    #[test]
+    fn relink_and_evict() {
+        const OTHER: [u8; 6] = [0x00, 0x1b, 0x63, 0x44, 0x55, 0x66];
+        let mut inventory = Inventory::default();
+        let mut events = Vec::new();
+        let ip = "192.168.1.50".parse().unwrap();
+        let now = SystemTime::UNIX_EPOCH;
+
+        inventory.process(now, &arp_reply("192.168.1.50", MAC), &mut events);
+        let now = now + Duration::from_secs(1);
+        inventory.process(now, &arp_reply("192.168.1.50", OTHER), &mut events);
+        assert!(inventory.hosts.get(&Key::Mac(MAC)).unwrap().ips.is_empty());
+
+        // the previous host is the oldest and gets evicted first
+        let now = now + Duration::from_secs(1);
+        for i in 0..MAX_HOSTS - 1 {
+            let src = format!("10.0.{}.{}", i / 256, i % 256);
+            inventory.process(now, &syn_ack(&src, 22), &mut events);
+        }
+        assert!(!inventory.hosts.contains_key(&Key::Mac(MAC)));
+        assert_eq!(inventory.by_ip.get(&ip), Some(&Key::Mac(OTHER)));
+        assert_eq!(
+            inventory.hosts.get(&Key::Mac(OTHER)).unwrap().ips,
+            BTreeSet::from([ip])
+        );
+    }
+}
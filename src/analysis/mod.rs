@@ -0,0 +1,318 @@
+use crate::sandbox::output::OutputDir;
+use crate::structs::ether::Ether;
+use crate::structs::raw::Raw;
+use crate::structs::{ipv4, ipv6, tcp, udp};
+use pktparse::tcp::TcpHeader;
+use pktparse::udp::UdpHeader;
+use serde::Serialize;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+pub mod arp;
+pub mod certificates;
+pub mod credentials;
+pub mod dns;
+pub mod fingerprints;
+pub mod flows;
+pub mod http;
+pub mod inventory;
+pub mod names;
+pub mod objects;
+pub mod osfp;
+pub mod oui;
+pub mod rogue;
+
+pub struct Config {
+    pub arp_watch: bool,
+    pub rogue_watch: bool,
+    pub flows: bool,
+    pub http_log: bool,
+    pub dns_log: bool,
+    pub inventory: bool,
+    pub credentials: bool,
+    /// Used to guess the os of the hosts in the inventory
+    pub os_signatures: Arc<osfp::Database>,
+    /// Directory the tls certificates are written to
+    pub certificate_dir: Option<Arc<OutputDir>>,
+    /// Directory the bodies of http responses are written to
+    pub object_dir: Option<Arc<OutputDir>>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Event {
+    Alert(Alert),
+    Flow(flows::Record),
+    FlowSummary(flows::Summary),
+    Http(http::Transaction),
+    Dns(dns::Transaction),
+    Inventory(inventory::Report),
+    Credential(credentials::Credential),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Alert {
+    Arp(arp::Alert),
+    Dns(dns::Alert),
+    Rogue(rogue::Alert),
+}
+
+/// Stateful analysis that runs on every parsed packet, regardless of the output filter
+pub struct Analysis {
+    arp: Option<arp::ArpWatch>,
+    rogue: Option<rogue::RogueWatch>,
+    flows: Option<flows::FlowTable>,
+    http: Option<http::HttpLog>,
+    dns: Option<dns::DnsLog>,
+    inventory: Option<inventory::Inventory>,
+    credentials: Option<credentials::CredentialLog>,
+    certificates: Option<certificates::CertificateDump>,
+    objects: Option<objects::HttpObjects>,
+}
+
+impl Analysis {
+    pub fn new(config: &Config) -> Analysis {
+        Analysis {
+            arp: config.arp_watch.then(arp::ArpWatch::default),
+            rogue: config.rogue_watch.then(rogue::RogueWatch::default),
+            flows: config.flows.then(flows::FlowTable::default),
+            http: config.http_log.then(http::HttpLog::default),
+            dns: config.dns_log.then(dns::DnsLog::default),
+            inventory: config
+                .inventory
+                .then(|| inventory::Inventory::new(config.os_signatures.clone())),
+            credentials: config.credentials.then(credentials::CredentialLog::default),
+            certificates: config
+                .certificate_dir
+                .clone()
+                .map(certificates::CertificateDump::new),
+            objects: config.object_dir.clone().map(objects::HttpObjects::new),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.arp.is_some()
+            || self.rogue.is_some()
+            || self.certificates.is_some()
+            || self.objects.is_some()
+            || self.credentials.is_some()
+            || self.reports_on_exit()
+    }
+
+    /// Whether individual packets should be printed, the reporting modes only print their records
+    #[inline]
+    pub fn prints_packets(&self) -> bool {
+        !self.reports_on_exit() && self.credentials.is_none()
+    }
+
+    /// Whether something is printed once the capture ends, live captures need to stop cleanly
+    #[inline]
+    pub fn reports_on_exit(&self) -> bool {
+        self.flows.is_some()
+            || self.http.is_some()
+            || self.dns.is_some()
+            || self.inventory.is_some()
+    }
+
+    pub fn process(&mut self, timestamp: SystemTime, packet: &Raw) -> Vec<Event> {
+        let mut alerts = Vec::new();
+        if let Some(arp) = &mut self.arp {
+            arp.process(timestamp, packet, &mut alerts);
+        }
+        if let Some(rogue) = &mut self.rogue {
+            rogue.process(timestamp, packet, &mut alerts);
+        }
+        if let Some(certificates) = &mut self.certificates {
+            certificates.process(packet);
+        }
+        if let Some(objects) = &mut self.objects {
+            objects.process(timestamp, packet);
+        }
+
+        let mut events = alerts.into_iter().map(Event::Alert).collect::<Vec<_>>();
+        if let Some(flows) = &mut self.flows {
+            flows.process(timestamp, packet, &mut events);
+        }
+        if let Some(http) = &mut self.http {
+            http.process(timestamp, packet, &mut events);
+        }
+        if let Some(dns) = &mut self.dns {
+            dns.process(timestamp, packet, &mut events);
+        }
+        if let Some(inventory) = &mut self.inventory {
+            inventory.process(timestamp, packet, &mut events);
+        }
+        if let Some(credentials) = &mut self.credentials {
+            credentials.process(timestamp, packet, &mut events);
+        }
+        events
+    }
+
+    /// Called once the packet stream ended
+    pub fn finish(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        if let Some(objects) = &mut self.objects {
+            objects.finish();
+        }
+        if let Some(flows) = &mut self.flows {
+            flows.finish(&mut events);
+        }
+        if let Some(http) = &mut self.http {
+            http.finish(&mut events);
+        }
+        if let Some(dns) = &mut self.dns {
+            dns.finish(&mut events);
+        }
+        if let Some(inventory) = &mut self.inventory {
+            inventory.finish(&mut events);
+        }
+        events
+    }
+}
+
+pub enum Transport<'a> {
+    TCP(&'a TcpHeader, &'a tcp::TCP),
+    UDP(&'a UdpHeader, &'a udp::UDP),
+}
+
+/// A tcp or udp packet with the addresses of both ends
+pub struct Segment<'a> {
+    pub src: SocketAddr,
+    pub dst: SocketAddr,
+    /// Size of the ip packet, including headers
+    pub length: u64,
+    pub transport: Transport<'a>,
+}
+
+impl<'a> Segment<'a> {
+    pub fn from_raw(packet: &'a Raw) -> Option<Segment<'a>> {
+        let (src, dst, length, transport) = match packet.ether()? {
+            Ether::IPv4(ip_hdr, ipv4::IPv4::TCP(tcp_hdr, tcp, _)) => (
+                IpAddr::V4(ip_hdr.source_addr),
+                IpAddr::V4(ip_hdr.dest_addr),
+                u64::from(ip_hdr.length),
+                Transport::TCP(tcp_hdr, tcp),
+            ),
+            Ether::IPv4(ip_hdr, ipv4::IPv4::UDP(udp_hdr, udp)) => (
+                IpAddr::V4(ip_hdr.source_addr),
+                IpAddr::V4(ip_hdr.dest_addr),
+                u64::from(ip_hdr.length),
+                Transport::UDP(udp_hdr, udp),
+            ),
+            Ether::IPv6(ip_hdr, ipv6::IPv6::TCP(tcp_hdr, tcp, _)) => (
+                IpAddr::V6(ip_hdr.source_addr),
+                IpAddr::V6(ip_hdr.dest_addr),
+                u64::from(ip_hdr.length) + 40,
+                Transport::TCP(tcp_hdr, tcp),
+            ),
+            Ether::IPv6(ip_hdr, ipv6::IPv6::UDP(udp_hdr, udp)) => (
+                IpAddr::V6(ip_hdr.source_addr),
+                IpAddr::V6(ip_hdr.dest_addr),
+                u64::from(ip_hdr.length) + 40,
+                Transport::UDP(udp_hdr, udp),
+            ),
+            _ => return None,
+        };
+
+        let (src_port, dst_port) = match transport {
+            Transport::TCP(tcp_hdr, _) => (tcp_hdr.source_port, tcp_hdr.dest_port),
+            Transport::UDP(udp_hdr, _) => (udp_hdr.source_port, udp_hdr.dest_port),
+        };
+
+        Some(Segment {
+            src: SocketAddr::new(src, src_port),
+            dst: SocketAddr::new(dst, dst_port),
+            length,
+            transport,
+        })
+    }
+}
+
+/// Capture timestamps aren't monotonic (merged capture files, clock steps), treat going back in time as no time passed
+#[inline]
+pub fn elapsed(earlier: SystemTime, now: SystemTime) -> Duration {
+    now.duration_since(earlier).unwrap_or_default()
+}
+
+/// Packets for the tests of the analysis modules, addresses are given as `ip:port`
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::structs::ether::Ether;
+    use crate::structs::ipv4::IPv4;
+    use crate::structs::raw::Raw;
+    use crate::structs::tcp::TCP;
+    use crate::structs::udp::UDP;
+    use pktparse::ip::IPProtocol;
+    use pktparse::ipv4::IPv4Header;
+    use pktparse::tcp::TcpHeader;
+    use pktparse::udp::UdpHeader;
+    use std::net::{SocketAddr, SocketAddrV4};
+
+    fn addr(addr: &str) -> SocketAddrV4 {
+        match addr.parse().unwrap() {
+            SocketAddr::V4(addr) => addr,
+            SocketAddr::V6(_) => unreachable!(),
+        }
+    }
+
+    fn ipv4(src: SocketAddrV4, dst: SocketAddrV4, protocol: IPProtocol, ipv4: IPv4) -> Raw {
+        Raw::Tun(Ether::IPv4(
+            IPv4Header {
+                version: 4,
+                ihl: 5,
+                tos: 0,
+                length: 0,
+                id: 0,
+                flags: 0,
+                fragment_offset: 0,
+                ttl: 64,
+                protocol,
+                chksum: 0,
+                source_addr: *src.ip(),
+                dest_addr: *dst.ip(),
+            },
+            ipv4,
+        ))
+    }
+
+    /// The tcp flags are given by their first letter, eg. `SA` for a syn/ack
+    pub fn tcp(src: &str, dst: &str, flags: &str, tcp: TCP) -> Raw {
+        let (src, dst) = (addr(src), addr(dst));
+        let header = TcpHeader {
+            source_port: src.port(),
+            dest_port: dst.port(),
+            sequence_no: 0,
+            ack_no: 0,
+            data_offset: 5,
+            reserved: 0,
+            flag_urg: false,
+            flag_ack: flags.contains('A'),
+            flag_psh: flags.contains('P'),
+            flag_rst: flags.contains('R'),
+            flag_syn: flags.contains('S'),
+            flag_fin: flags.contains('F'),
+            window: 0,
+            checksum: 0,
+            urgent_pointer: 0,
+            options: None,
+        };
+        ipv4(
+            src,
+            dst,
+            IPProtocol::TCP,
+            IPv4::TCP(header, tcp, Vec::new()),
+        )
+    }
+
+    pub fn udp(src: &str, dst: &str, udp: UDP) -> Raw {
+        let (src, dst) = (addr(src), addr(dst));
+        let header = UdpHeader {
+            source_port: src.port(),
+            dest_port: dst.port(),
+            length: 0,
+            checksum: 0,
+        };
+        ipv4(src, dst, IPProtocol::UDP, IPv4::UDP(header, udp))
+    }
+}
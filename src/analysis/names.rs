@@ -0,0 +1,155 @@
+use crate::analysis::{Segment, Transport};
+use crate::bounded::BoundedMap;
+use crate::structs::dns::{DNS, Record};
+use crate::structs::http::Http;
+use crate::structs::raw::Raw;
+use crate::structs::tcp::TCP;
+use crate::structs::tls::TLS;
+use crate::structs::udp::UDP;
+use std::net::IpAddr;
+use std::time::SystemTime;
+
+const MAX_NAMES: usize = 8192;
+
+/// Passive dns cache, remembers which name an ip address was most recently resolved from
+#[derive(Default)]
+pub struct NameCache {
+    names: BoundedMap<IpAddr, String, MAX_NAMES>,
+}
+
+impl NameCache {
+    pub fn learn(&mut self, timestamp: SystemTime, packet: &Raw) {
+        let Some(segment) = Segment::from_raw(packet) else {
+            return;
+        };
+
+        match segment.transport {
+            Transport::UDP(_, UDP::DNS(DNS::Response(response))) => {
+                // annotate with the name that was asked for, not the end of a cname chain
+                let question = response.questions.first().map(|(_, name)| name);
+                for (name, record) in &response.answers {
+                    let addr = match *record {
+                        Record::A(addr) => IpAddr::V4(addr),
+                        Record::AAAA(addr) => IpAddr::V6(addr),
+                        _ => continue,
+                    };
+                    self.insert(timestamp, addr, question.unwrap_or(name));
+                }
+            }
+            Transport::TCP(_, TCP::TLS(TLS::ClientHello(client_hello))) => {
+                if let Some(hostname) = &client_hello.hostname {
+                    self.insert(timestamp, segment.dst.ip(), hostname);
+                }
+            }
+            Transport::UDP(_, UDP::QUIC(quic)) => {
+                if let Some(hostname) = quic
+                    .client_hello
+                    .as_ref()
+                    .and_then(|ch| ch.hostname.as_ref())
+                {
+                    self.insert(timestamp, segment.dst.ip(), hostname);
+                }
+            }
+            Transport::TCP(_, TCP::HTTP(Http::Request(request))) => {
+                if let Some(host) = &request.host {
+                    let host = strip_port(host);
+                    // an ip address in the host header doesn't tell us anything
+                    if host.parse::<IpAddr>().is_err() {
+                        self.insert(timestamp, segment.dst.ip(), host);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    #[inline]
+    pub fn lookup(&self, addr: &IpAddr) -> Option<&str> {
+        self.names.get(addr).map(String::as_str)
+    }
+
+    fn insert(&mut self, timestamp: SystemTime, addr: IpAddr, name: &str) {
+        let name = name.trim_end_matches('.');
+        if name.is_empty() {
+            return;
+        }
+
+        let entry = self
+            .names
+            .touch_or_insert_with(timestamp, addr, String::new);
+        if entry != name {
+            *entry = name.to_string();
+        }
+    }
+}
+
+fn strip_port(host: &str) -> &str {
+    if let Some(v6) = host.strip_prefix('[') {
+        v6.split(']').next().unwrap_or(v6)
+    } else {
+        match host.rsplit_once(':') {
+            Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => host,
+            _ => host,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fixtures;
+    use crate::structs::dns::{QueryType, Response, ResponseCode};
+
+    fn response(question: &str, answers: Vec<(String, Record)>) -> Raw {
+        let response = Response::new(
+            1,
+            ResponseCode::NoError,
+            vec![(QueryType::A, question.to_string())],
+            answers,
+        );
+
+        fixtures::udp(
+            "192.168.1.1:53",
+            "192.168.1.23:40000",
+            UDP::DNS(response.wrap()),
+        )
+    }
+
+    #[test]
+    fn learn_from_dns() {
+        let mut names = NameCache::default();
+        let now = SystemTime::UNIX_EPOCH;
+        let addr = "192.0.2.1".parse().unwrap();
+
+        names.learn(
+            now,
+            &response(
+                "www.example.com.",
+                vec![
+                    (
+                        String::from("www.example.com"),
+                        Record::CNAME(String::from("cdn.example.net")),
+                    ),
+                    (String::from("cdn.example.net"), Record::A(addr)),
+                ],
+            ),
+        );
+        assert_eq!(names.lookup(&IpAddr::V4(addr)), Some("www.example.com"));
+
+        names.learn(
+            now,
+            &response(
+                "example.org",
+                vec![(String::from("example.org"), Record::A(addr))],
+            ),
+        );
+        assert_eq!(names.lookup(&IpAddr::V4(addr)), Some("example.org"));
+    }
+
+    #[test]
+    fn strip_host_port() {
+        assert_eq!(strip_port("example.com:8080"), "example.com");
+        assert_eq!(strip_port("example.com"), "example.com");
+        assert_eq!(strip_port("[::1]:8080"), "::1");
+    }
+}
@@ -0,0 +1,352 @@
+use crate::analysis::{Segment, Transport};
+use crate::bounded::BoundedMap;
+use crate::centrifuge::http::decode;
+use crate::errors::*;
+use crate::sandbox::output::OutputDir;
+use crate::structs::http::{self, Http, Response};
+use crate::structs::raw::Raw;
+use crate::structs::tcp::TCP;
+use bstr::{BString, ByteSlice};
+use data_encoding::HEXLOWER;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+const MAX_CONNECTIONS: usize = 256;
+/// Requests that are still waiting for a response, per connection
+const MAX_PENDING: usize = 32;
+/// Objects are cut off after this size, before and after decoding
+const MAX_OBJECT_LENGTH: usize = 16 * 1024 * 1024;
+/// Segments that arrived ahead of a gap, per object
+const MAX_OUT_OF_ORDER: usize = 64;
+
+/// Written next to every object
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Metadata {
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    /// Missing if the request was not captured
+    pub method: Option<String>,
+    pub url: Option<String>,
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Codings that were removed before saving
+    pub encodings: Vec<String>,
+    pub length: usize,
+    /// The object is incomplete because of missing segments or the size limit
+    pub truncated: bool,
+    pub sha256: String,
+}
+
+struct Download {
+    client: SocketAddr,
+    server: SocketAddr,
+    method: Option<String>,
+    url: Option<String>,
+    status: u16,
+    headers: Vec<(String, BString)>,
+    /// Sequence number of the first segment after the response headers and its offset in the body
+    base: Option<(u32, usize)>,
+    body: Vec<u8>,
+    out_of_order: BTreeMap<usize, Vec<u8>>,
+    overflow: bool,
+}
+
+impl Download {
+    fn new(segment: &Segment, request: Option<Pending>, response: &Response) -> Download {
+        let (method, url) = match request {
+            Some(request) => (Some(request.method), request.url),
+            None => (None, None),
+        };
+        Download {
+            client: segment.dst,
+            server: segment.src,
+            method,
+            url,
+            status: response.code,
+            headers: response.headers.clone(),
+            base: None,
+            body: response
+                .body
+                .as_ref()
+                .map(|x| x.to_vec())
+                .unwrap_or_default(),
+            out_of_order: BTreeMap::new(),
+            overflow: false,
+        }
+    }
+
+    fn append(&mut self, seq: u32, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+
+        let (base_seq, base_offset) = *self.base.get_or_insert((seq, self.body.len()));
+        let relative = seq.wrapping_sub(base_seq);
+        // segments from before the first one we've seen are retransmissions
+        if relative > i32::MAX as u32 {
+            return;
+        }
+        let offset = base_offset + relative as usize;
+
+        if offset > self.body.len() {
+            if self.out_of_order.len() < MAX_OUT_OF_ORDER {
+                self.out_of_order.insert(offset, data.to_vec());
+            }
+            return;
+        }
+        self.extend(offset, data);
+
+        while let Some(entry) = self.out_of_order.first_entry() {
+            if *entry.key() > self.body.len() {
+                break;
+            }
+            let (offset, data) = entry.remove_entry();
+            self.extend(offset, &data);
+        }
+    }
+
+    /// Add the part of the data that we don't have yet
+    fn extend(&mut self, offset: usize, data: &[u8]) {
+        let Some(new) = data.get(self.body.len() - offset..) else {
+            return;
+        };
+        let available = MAX_OBJECT_LENGTH.saturating_sub(self.body.len());
+        if new.len() > available {
+            self.overflow = true;
+        }
+        self.body
+            .extend_from_slice(&new[..new.len().min(available)]);
+    }
+
+    fn is_complete(&self) -> bool {
+        if self.overflow {
+            return true;
+        }
+
+        let chunked = http::header(&self.headers, "transfer-encoding")
+            .is_some_and(|x| x.to_ascii_lowercase().contains_str("chunked"));
+        if chunked {
+            // trailers are rare, otherwise we wait for the connection to close
+            self.body.ends_with(b"\r\n0\r\n\r\n") || self.body == b"0\r\n\r\n"
+        } else if let Some(length) = http::header(&self.headers, "content-length")
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.trim().parse::<usize>().ok())
+        {
+            self.body.len() >= length
+        } else {
+            false
+        }
+    }
+}
+
+struct Pending {
+    method: String,
+    url: Option<String>,
+}
+
+#[derive(Default)]
+struct Connection {
+    requests: VecDeque<Pending>,
+    download: Option<Download>,
+    /// Sequence number of the last response headers, to ignore retransmissions
+    response_seq: Option<u32>,
+}
+
+/// Saves the bodies of http responses into a directory, named after their sha256
+pub struct HttpObjects {
+    dir: Arc<OutputDir>,
+    connections: BoundedMap<(SocketAddr, SocketAddr), Connection, MAX_CONNECTIONS>,
+}
+
+impl HttpObjects {
+    pub fn new(dir: Arc<OutputDir>) -> HttpObjects {
+        HttpObjects {
+            dir,
+            connections: BoundedMap::default(),
+        }
+    }
+
+    pub fn process(&mut self, timestamp: SystemTime, packet: &Raw) {
+        let Some(segment) = Segment::from_raw(packet) else {
+            return;
+        };
+        let Transport::TCP(header, tcp) = segment.transport else {
+            return;
+        };
+
+        match tcp {
+            TCP::HTTP(Http::Request(request)) => {
+                let connection = self.connection(timestamp, (segment.src, segment.dst));
+                if connection.requests.len() >= MAX_PENDING {
+                    connection.requests.pop_front();
+                }
+                connection.requests.push_back(Pending {
+                    method: request.method.clone(),
+                    url: request
+                        .host
+                        .as_ref()
+                        .map(|host| format!("http://{}{}", host, request.path)),
+                });
+            }
+            TCP::HTTP(Http::Response(response)) => {
+                // informational responses are followed by the actual response
+                if (100..200).contains(&response.code) {
+                    return;
+                }
+
+                let connection = self.connection(timestamp, (segment.dst, segment.src));
+                if connection.response_seq == Some(header.sequence_no) {
+                    return;
+                }
+                connection.response_seq = Some(header.sequence_no);
+                if let Some(download) = connection.download.take() {
+                    self.save(download);
+                }
+
+                let connection = self.connection(timestamp, (segment.dst, segment.src));
+                let request = connection.requests.pop_front();
+                let has_body = !matches!(response.code, 204 | 304)
+                    && request.as_ref().is_none_or(|req| req.method != "HEAD");
+                if has_body {
+                    let download = Download::new(&segment, request, response);
+                    if download.is_complete() {
+                        self.save(download);
+                    } else {
+                        connection.download = Some(download);
+                    }
+                }
+            }
+            _ => {
+                let key = (segment.dst, segment.src);
+                let Some(connection) = self.connections.touch(timestamp, &key) else {
+                    return;
+                };
+                let Some(download) = &mut connection.download else {
+                    return;
+                };
+
+                match tcp {
+                    TCP::Text(text) => download.append(header.sequence_no, text.as_bytes()),
+                    TCP::Binary(data) => download.append(header.sequence_no, data),
+                    _ => (),
+                }
+
+                if (download.is_complete() || header.flag_fin || header.flag_rst)
+                    && let Some(download) = connection.download.take()
+                {
+                    self.save(download);
+                }
+            }
+        }
+    }
+
+    /// Save the objects that are still being downloaded
+    pub fn finish(&mut self) {
+        let downloads = self
+            .connections
+            .drain()
+            .filter_map(|(_, connection)| connection.download)
+            .collect::<Vec<_>>();
+        for download in downloads {
+            self.save(download);
+        }
+    }
+
+    fn connection(
+        &mut self,
+        timestamp: SystemTime,
+        key: (SocketAddr, SocketAddr),
+    ) -> &mut Connection {
+        if let Some((_, evicted)) = self.connections.evict(&key)
+            && let Some(download) = evicted.download
+        {
+            self.save(download);
+        }
+        self.connections
+            .touch_or_insert_with(timestamp, key, Connection::default)
+    }
+
+    fn save(&self, download: Download) {
+        let body = decode(&download.body, &download.headers, MAX_OBJECT_LENGTH);
+        if body.data.is_empty() {
+            return;
+        }
+
+        let sha256 = HEXLOWER.encode(&Sha256::digest(&body.data));
+        let metadata = Metadata {
+            client: download.client,
+            server: download.server,
+            method: download.method,
+            url: download.url,
+            status: download.status,
+            content_type: body.content_type,
+            encodings: body.encodings,
+            length: body.data.len(),
+            truncated: body.truncated || download.overflow,
+            sha256,
+        };
+
+        if let Err(err) = self.write(&metadata, &body.data) {
+            warn!("Failed to save http object: {:#}", err);
+        }
+    }
+
+    fn write(&self, metadata: &Metadata, data: &[u8]) -> Result<()> {
+        if self.dir.create(&metadata.sha256, data)? {
+            debug!("Saved http object from {:?}", metadata.url);
+            let json = serde_json::to_vec_pretty(metadata)?;
+            self.dir
+                .create(&format!("{}.json", metadata.sha256), &json)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn download(headers: &[(&str, &str)], body: &[u8]) -> Download {
+        Download {
+            client: "192.168.1.23:40000".parse().unwrap(),
+            server: "192.0.2.1:80".parse().unwrap(),
+            method: None,
+            url: None,
+            status: 200,
+            headers: headers
+                .iter()
+                .map(|(key, value)| (key.to_string(), (*value).into()))
+                .collect(),
+            base: None,
+            body: body.to_vec(),
+            out_of_order: BTreeMap::new(),
+            overflow: false,
+        }
+    }
+
+    #[test]
+    fn reorder_segments() {
+        let mut download = download(&[("Content-Length", "17")], b"hello");
+        download.append(1000, b" wor");
+        download.append(1007, b"there");
+        assert!(!download.is_complete());
+        // retransmission that overlaps with what we have
+        download.append(1002, b"orld");
+        assert_eq!(download.body, b"hello world");
+        download.append(1006, b"\n");
+        assert_eq!(download.body, b"hello world\nthere");
+        assert!(download.is_complete());
+    }
+
+    #[test]
+    fn chunked_end() {
+        let mut download = download(&[("Transfer-Encoding", "chunked")], b"5\r\nhello\r\n");
+        assert!(!download.is_complete());
+        download.append(1, b"0\r\n\r\n");
+        assert!(download.is_complete());
+    }
+}
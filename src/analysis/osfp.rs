@@ -0,0 +1,561 @@
+use crate::errors::*;
+use crate::structs::ether::Ether;
+use crate::structs::tcp::{TCP, TcpOption};
+use crate::structs::{ipv4, ipv6};
+use pktparse::tcp::TcpHeader;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+const SIGNATURES: &str = include_str!("osfp.fp");
+/// The observed ttl may be this many hops below the initial ttl of a signature
+const MAX_DISTANCE: u8 = 35;
+
+// quirks, named like in p0f
+const DF: u32 = 1 << 0;
+const ID_PLUS: u32 = 1 << 1;
+const ID_MINUS: u32 = 1 << 2;
+const ECN: u32 = 1 << 3;
+const ZERO_PLUS: u32 = 1 << 4;
+const FLOW: u32 = 1 << 5;
+const SEQ_MINUS: u32 = 1 << 6;
+const ACK_PLUS: u32 = 1 << 7;
+const ACK_MINUS: u32 = 1 << 8;
+const UPTR_PLUS: u32 = 1 << 9;
+const URGF_PLUS: u32 = 1 << 10;
+const PUSHF_PLUS: u32 = 1 << 11;
+const TS1_MINUS: u32 = 1 << 12;
+const TS2_PLUS: u32 = 1 << 13;
+const OPT_PLUS: u32 = 1 << 14;
+const EXWS: u32 = 1 << 15;
+const BAD: u32 = 1 << 16;
+
+const QUIRKS: &[(&str, u32)] = &[
+    ("df", DF),
+    ("id+", ID_PLUS),
+    ("id-", ID_MINUS),
+    ("ecn", ECN),
+    ("0+", ZERO_PLUS),
+    ("flow", FLOW),
+    ("seq-", SEQ_MINUS),
+    ("ack+", ACK_PLUS),
+    ("ack-", ACK_MINUS),
+    ("uptr+", UPTR_PLUS),
+    ("urgf+", URGF_PLUS),
+    ("pushf+", PUSHF_PLUS),
+    ("ts1-", TS1_MINUS),
+    ("ts2+", TS2_PLUS),
+    ("opt+", OPT_PLUS),
+    ("exws", EXWS),
+    ("bad", BAD),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Layout {
+    /// End of options, followed by this many bytes of padding
+    Eol(usize),
+    Nop,
+    Mss,
+    Ws,
+    Sok,
+    Sack,
+    Ts,
+    Unknown(u8),
+}
+
+impl FromStr for Layout {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Layout> {
+        let layout = match s {
+            "nop" => Layout::Nop,
+            "mss" => Layout::Mss,
+            "ws" => Layout::Ws,
+            "sok" => Layout::Sok,
+            "sack" => Layout::Sack,
+            "ts" => Layout::Ts,
+            _ => {
+                if let Some(padding) = s.strip_prefix("eol+") {
+                    Layout::Eol(padding.parse()?)
+                } else if let Some(kind) = s.strip_prefix('?') {
+                    Layout::Unknown(kind.parse()?)
+                } else {
+                    bail!("Unknown tcp option: {:?}", s)
+                }
+            }
+        };
+        Ok(layout)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum WindowSize {
+    Any,
+    Exact(u16),
+    Mss(u32),
+    Mtu(u32),
+    Modulo(u16),
+}
+
+#[derive(Debug, PartialEq)]
+struct Signature {
+    version: Option<u8>,
+    ttl: Option<u8>,
+    olen: u8,
+    mss: Option<u16>,
+    window: WindowSize,
+    scale: Option<u8>,
+    layout: Vec<Layout>,
+    quirks: u32,
+    payload: Option<bool>,
+}
+
+/// Parse a field that can be a wildcard
+fn wildcard<T: FromStr>(s: &str) -> Result<Option<T>>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    if s == "*" {
+        Ok(None)
+    } else {
+        Ok(Some(s.parse()?))
+    }
+}
+
+impl FromStr for Signature {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Signature> {
+        let fields = s.split(':').collect::<Vec<_>>();
+        let [version, ttl, olen, mss, window, layout, quirks, payload] = fields[..] else {
+            bail!("Expected 8 fields, found {}", fields.len());
+        };
+
+        // p0f writes signatures of packets with a bad or known distance like this
+        let ttl = ttl.trim_end_matches('-');
+        let ttl = ttl.split_once('+').map_or(ttl, |(ttl, _)| ttl);
+
+        let Some((window, scale)) = window.split_once(',') else {
+            bail!("Window size is missing the scale");
+        };
+        let window = if window == "*" {
+            WindowSize::Any
+        } else if let Some(n) = window.strip_prefix("mss*") {
+            WindowSize::Mss(n.parse()?)
+        } else if let Some(n) = window.strip_prefix("mtu*") {
+            WindowSize::Mtu(n.parse()?)
+        } else if let Some(n) = window.strip_prefix('%') {
+            WindowSize::Modulo(n.parse()?)
+        } else {
+            WindowSize::Exact(window.parse()?)
+        };
+
+        let layout = layout
+            .split(',')
+            .filter(|x| !x.is_empty())
+            .map(Layout::from_str)
+            .collect::<Result<_>>()?;
+
+        let mut bits = 0;
+        for quirk in quirks.split(',').filter(|x| !x.is_empty()) {
+            let Some((_, bit)) = QUIRKS.iter().find(|(name, _)| *name == quirk) else {
+                bail!("Unknown quirk: {:?}", quirk);
+            };
+            bits |= bit;
+        }
+
+        let payload = match payload {
+            "0" => Some(false),
+            "+" => Some(true),
+            "*" => None,
+            _ => bail!("Unknown payload class: {:?}", payload),
+        };
+
+        Ok(Signature {
+            version: wildcard(version)?,
+            ttl: wildcard(ttl)?,
+            olen: olen.parse()?,
+            mss: wildcard(mss)?,
+            window,
+            scale: wildcard(scale)?,
+            layout,
+            quirks: bits,
+            payload,
+        })
+    }
+}
+
+impl Signature {
+    /// Returns if the match is fuzzy, p0f allows some quirks to differ because they
+    /// are commonly changed by middleboxes or configuration
+    fn matches(&self, fp: &Fingerprint) -> Option<bool> {
+        if self.version.is_some_and(|version| version != fp.version) {
+            return None;
+        }
+        if let Some(ttl) = self.ttl
+            && (fp.ttl > ttl || ttl - fp.ttl > MAX_DISTANCE)
+        {
+            return None;
+        }
+        if self.olen != fp.olen
+            || self.mss.is_some_and(|mss| Some(mss) != fp.mss)
+            || self.scale.is_some_and(|scale| scale != fp.scale)
+            || self.layout != fp.layout
+            || self.payload == Some(true)
+        {
+            return None;
+        }
+
+        let window = u32::from(fp.window);
+        let mss = fp.mss.map(u32::from);
+        let window_matches = match self.window {
+            WindowSize::Any => true,
+            WindowSize::Exact(n) => n == fp.window,
+            WindowSize::Mss(n) => mss.is_some_and(|mss| mss * n == window),
+            WindowSize::Mtu(n) => {
+                let headers = if fp.version == 4 { 40 } else { 60 };
+                mss.is_some_and(|mss| (mss + headers) * n == window)
+            }
+            WindowSize::Modulo(n) => n != 0 && fp.window.is_multiple_of(n),
+        };
+        if !window_matches {
+            return None;
+        }
+
+        if self.quirks == fp.quirks {
+            return Some(false);
+        }
+        let deleted = self.quirks & !fp.quirks;
+        let added = fp.quirks & !self.quirks;
+        if deleted & !(DF | ID_PLUS) != 0 || added & !(ID_MINUS | ECN) != 0 {
+            None
+        } else {
+            Some(true)
+        }
+    }
+}
+
+/// The properties of a syn or syn/ack packet that are compared with the signatures
+#[derive(Debug, PartialEq)]
+struct Fingerprint {
+    syn_ack: bool,
+    version: u8,
+    ttl: u8,
+    olen: u8,
+    mss: Option<u16>,
+    window: u16,
+    scale: u8,
+    layout: Vec<Layout>,
+    quirks: u32,
+}
+
+impl Fingerprint {
+    fn from_ether(ether: &Ether) -> Option<Fingerprint> {
+        let (version, ttl, olen, quirks, tcp_hdr, options) = match ether {
+            Ether::IPv4(ip_hdr, ipv4::IPv4::TCP(tcp_hdr, TCP::Empty, options))
+                if tcp_hdr.flag_syn =>
+            {
+                let mut quirks = 0;
+                if ip_hdr.flags & 0x2 != 0 {
+                    quirks |= DF;
+                    if ip_hdr.id != 0 {
+                        quirks |= ID_PLUS;
+                    }
+                } else if ip_hdr.id == 0 {
+                    quirks |= ID_MINUS;
+                }
+                if ip_hdr.flags & 0x4 != 0 {
+                    quirks |= ZERO_PLUS;
+                }
+                if ip_hdr.tos & 0x3 != 0 {
+                    quirks |= ECN;
+                }
+                let olen = (ip_hdr.ihl * 4).saturating_sub(20);
+                (4, ip_hdr.ttl, olen, quirks, tcp_hdr, options)
+            }
+            Ether::IPv6(ip_hdr, ipv6::IPv6::TCP(tcp_hdr, TCP::Empty, options))
+                if tcp_hdr.flag_syn =>
+            {
+                let mut quirks = 0;
+                if ip_hdr.flow_label != 0 {
+                    quirks |= FLOW;
+                }
+                if ip_hdr.ecn != 0 {
+                    quirks |= ECN;
+                }
+                (6, ip_hdr.hop_limit, 0, quirks, tcp_hdr, options)
+            }
+            _ => return None,
+        };
+
+        let mut fp = Fingerprint {
+            syn_ack: tcp_hdr.flag_ack,
+            version,
+            ttl,
+            olen,
+            mss: None,
+            window: tcp_hdr.window,
+            scale: 0,
+            layout: Vec::new(),
+            quirks: quirks | tcp_quirks(tcp_hdr),
+        };
+        for option in options {
+            let layout = match option {
+                TcpOption::EndOfOptions(padding) => {
+                    if padding.iter().any(|x| *x != 0) {
+                        fp.quirks |= OPT_PLUS;
+                    }
+                    Layout::Eol(padding.len())
+                }
+                TcpOption::NoOperation => Layout::Nop,
+                TcpOption::MaximumSegmentSize(mss) => {
+                    fp.mss = Some(*mss);
+                    Layout::Mss
+                }
+                TcpOption::WindowScale(scale) => {
+                    fp.scale = *scale;
+                    if *scale > 14 {
+                        fp.quirks |= EXWS;
+                    }
+                    Layout::Ws
+                }
+                TcpOption::SackPermitted => Layout::Sok,
+                TcpOption::Sack(_) => Layout::Sack,
+                TcpOption::Timestamp(value, echo) => {
+                    if *value == 0 {
+                        fp.quirks |= TS1_MINUS;
+                    }
+                    if *echo != 0 && !fp.syn_ack {
+                        fp.quirks |= TS2_PLUS;
+                    }
+                    Layout::Ts
+                }
+                TcpOption::Unknown(kind, _) => Layout::Unknown(*kind),
+                TcpOption::Malformed(_) => {
+                    fp.quirks |= BAD;
+                    continue;
+                }
+            };
+            fp.layout.push(layout);
+        }
+
+        Some(fp)
+    }
+}
+
+fn tcp_quirks(tcp_hdr: &TcpHeader) -> u32 {
+    let mut quirks = 0;
+    // pktparse puts the ns, cwr and ece flags into the lower bits
+    if tcp_hdr.reserved & 0x07 != 0 {
+        quirks |= ECN;
+    }
+    if tcp_hdr.reserved & 0x38 != 0 {
+        quirks |= ZERO_PLUS;
+    }
+    if tcp_hdr.sequence_no == 0 {
+        quirks |= SEQ_MINUS;
+    }
+    if tcp_hdr.flag_ack {
+        if tcp_hdr.ack_no == 0 {
+            quirks |= ACK_MINUS;
+        }
+    } else if tcp_hdr.ack_no != 0 {
+        quirks |= ACK_PLUS;
+    }
+    if tcp_hdr.flag_urg {
+        quirks |= URGF_PLUS;
+    } else if tcp_hdr.urgent_pointer != 0 {
+        quirks |= UPTR_PLUS;
+    }
+    if tcp_hdr.flag_psh {
+        quirks |= PUSHF_PLUS;
+    }
+    quirks
+}
+
+#[derive(Debug)]
+struct Entry {
+    generic: bool,
+    os: String,
+    signature: Signature,
+}
+
+/// Signatures for syn packets (requests) and syn/acks (responses)
+#[derive(Debug)]
+pub struct Database {
+    requests: Vec<Entry>,
+    responses: Vec<Entry>,
+}
+
+impl Default for Database {
+    fn default() -> Database {
+        Database::parse(SIGNATURES).expect("Built-in os signatures are invalid")
+    }
+}
+
+impl Database {
+    pub fn parse(text: &str) -> Result<Database> {
+        let mut db = Database {
+            requests: Vec::new(),
+            responses: Vec::new(),
+        };
+        let mut section = None;
+        let mut label = None;
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+                section = match name {
+                    "tcp:request" => Some(false),
+                    "tcp:response" => Some(true),
+                    _ => None,
+                };
+                label = None;
+                continue;
+            }
+            let Some(syn_ack) = section else {
+                continue;
+            };
+
+            let Some((key, value)) = line.split_once('=') else {
+                bail!("Line {}: Expected key = value", i + 1);
+            };
+            let value = value.trim();
+            match key.trim() {
+                "label" => {
+                    let [kind, _class, name, flavor] = value.splitn(4, ':').collect::<Vec<_>>()[..]
+                    else {
+                        bail!("Line {}: Invalid label: {:?}", i + 1, value);
+                    };
+                    let os = if flavor.is_empty() {
+                        name.to_string()
+                    } else {
+                        format!("{} {}", name, flavor)
+                    };
+                    label = Some((kind == "g", os));
+                }
+                "sig" => {
+                    let Some((generic, os)) = &label else {
+                        bail!("Line {}: Signature without a label", i + 1);
+                    };
+                    let signature: Signature = value
+                        .parse()
+                        .with_context(|| format!("Line {}: Invalid signature", i + 1))?;
+                    let entry = Entry {
+                        generic: *generic,
+                        os: os.clone(),
+                        signature,
+                    };
+                    if syn_ack {
+                        db.responses.push(entry);
+                    } else {
+                        db.requests.push(entry);
+                    }
+                }
+                // eg. the applications a label is known for
+                _ => (),
+            }
+        }
+
+        Ok(db)
+    }
+
+    /// Add the signatures of a file, they take precedence over the existing ones
+    pub fn load(&mut self, path: &Path) -> Result<()> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read os signatures: {:?}", path))?;
+        let mut db = Database::parse(&text)
+            .with_context(|| format!("Failed to parse os signatures: {:?}", path))?;
+        db.requests.append(&mut self.requests);
+        db.responses.append(&mut self.responses);
+        *self = db;
+        Ok(())
+    }
+
+    /// Guess the operating system that sent a syn or syn/ack packet
+    pub fn guess(&self, ether: &Ether) -> Option<&str> {
+        let fp = Fingerprint::from_ether(ether)?;
+        let entries = if fp.syn_ack {
+            &self.responses
+        } else {
+            &self.requests
+        };
+        entries
+            .iter()
+            .filter_map(|entry| {
+                let fuzzy = entry.signature.matches(&fp)?;
+                Some(((fuzzy, entry.generic), entry))
+            })
+            .min_by_key(|(rank, _)| *rank)
+            .map(|(_, entry)| entry.os.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::centrifuge;
+
+    fn syn(ttl: u8, window: u16, options: &[u8]) -> Ether {
+        let mut packet = vec![
+            0x45, 0x00, 0x00, 0x00, 0x12, 0x34, 0x40, 0x00, ttl, 0x06, 0x00, 0x00, 192, 168, 1, 23,
+            93, 184, 216, 34,
+        ];
+        packet.extend([0xc3, 0x50, 0x01, 0xbb, 0x11, 0x22, 0x33, 0x44, 0, 0, 0, 0]);
+        packet.push(((20 + options.len() as u8) / 4) << 4);
+        packet.push(0x02);
+        packet.extend(window.to_be_bytes());
+        packet.extend([0, 0, 0, 0]);
+        packet.extend(options);
+
+        let Ok(ether) = centrifuge::parse_ipv4(&packet) else {
+            panic!("Failed to parse packet");
+        };
+        assert!(matches!(
+            ether,
+            Ether::IPv4(_, ipv4::IPv4::TCP(_, TCP::Empty, ref options)) if !options.is_empty()
+        ));
+        ether
+    }
+
+    #[test]
+    fn guess_syn() {
+        let db = Database::default();
+
+        // mss 1460, sok, ts, nop, ws 7
+        let linux = [
+            2, 4, 0x05, 0xb4, 4, 2, 8, 10, 0, 1, 0, 0, 0, 0, 0, 0, 1, 3, 3, 7,
+        ];
+        let ether = syn(57, 64240, &linux);
+        assert_eq!(db.guess(&ether), Some("Linux 3.11 and newer"));
+        // the window doesn't match a specific signature
+        let ether = syn(57, 1234, &linux);
+        assert_eq!(db.guess(&ether), Some("Linux"));
+        // too many hops away
+        let ether = syn(20, 64240, &linux);
+        assert_eq!(db.guess(&ether), None);
+
+        // mss 1460, nop, ws 8, nop, nop, sok
+        let windows = [2, 4, 0x05, 0xb4, 1, 3, 3, 8, 1, 1, 4, 2];
+        let ether = syn(120, 64240, &windows);
+        assert_eq!(db.guess(&ether), Some("Windows 10 or 11"));
+    }
+
+    #[test]
+    fn load_signature() {
+        let db = Database::parse(
+            "[mtu]\nlabel = Ethernet\nsig = 1500\n\
+             [tcp:request]\nlabel = s:!:Example:scanner\nsig = 4:64:0:1337:1024,0:mss:df,id+:0\n\
+             [tcp:response]\n",
+        )
+        .unwrap();
+        let ether = syn(64, 1024, &[2, 4, 0x05, 0x39]);
+        assert_eq!(db.guess(&ether), Some("Example scanner"));
+
+        assert!(Database::parse("[tcp:request]\nsig = *:64:0:*:*,*::df:0\n").is_err());
+    }
+}
@@ -0,0 +1,124 @@
+//! A small built-in table of vendor prefixes, the full ieee registry is too large to embed
+
+/// Sorted by prefix for binary search
+const VENDORS: &[([u8; 3], &str)] = &[
+    ([0x00, 0x00, 0x0c], "Cisco"),
+    ([0x00, 0x03, 0x93], "Apple"),
+    ([0x00, 0x03, 0xff], "Microsoft"),
+    ([0x00, 0x04, 0x76], "3Com"),
+    ([0x00, 0x05, 0x02], "Apple"),
+    ([0x00, 0x05, 0x5d], "D-Link"),
+    ([0x00, 0x05, 0x69], "VMware"),
+    ([0x00, 0x05, 0x85], "Juniper"),
+    ([0x00, 0x0a, 0x95], "Apple"),
+    ([0x00, 0x0b, 0x86], "Aruba"),
+    ([0x00, 0x0c, 0x29], "VMware"),
+    ([0x00, 0x0c, 0x42], "MikroTik"),
+    ([0x00, 0x0d, 0x3a], "Microsoft"),
+    ([0x00, 0x0d, 0x93], "Apple"),
+    ([0x00, 0x0d, 0xb9], "PC Engines"),
+    ([0x00, 0x0f, 0xb5], "Netgear"),
+    ([0x00, 0x11, 0x32], "Synology"),
+    ([0x00, 0x12, 0x5a], "Microsoft"),
+    ([0x00, 0x14, 0x22], "Dell"),
+    ([0x00, 0x14, 0x51], "Apple"),
+    ([0x00, 0x15, 0x5d], "Microsoft"),
+    ([0x00, 0x16, 0x3e], "Xen"),
+    ([0x00, 0x16, 0xcb], "Apple"),
+    ([0x00, 0x17, 0x88], "Philips Lighting"),
+    ([0x00, 0x17, 0xf2], "Apple"),
+    ([0x00, 0x19, 0xe3], "Apple"),
+    ([0x00, 0x1a, 0x11], "Google"),
+    ([0x00, 0x1b, 0x21], "Intel"),
+    ([0x00, 0x1b, 0x63], "Apple"),
+    ([0x00, 0x1c, 0x42], "Parallels"),
+    ([0x00, 0x1c, 0xb3], "Apple"),
+    ([0x00, 0x1e, 0x52], "Apple"),
+    ([0x00, 0x1e, 0xc2], "Apple"),
+    ([0x00, 0x1f, 0x5b], "Apple"),
+    ([0x00, 0x1f, 0xf3], "Apple"),
+    ([0x00, 0x21, 0xe9], "Apple"),
+    ([0x00, 0x22, 0x41], "Apple"),
+    ([0x00, 0x23, 0x12], "Apple"),
+    ([0x00, 0x23, 0x6c], "Apple"),
+    ([0x00, 0x23, 0xdf], "Apple"),
+    ([0x00, 0x24, 0x36], "Apple"),
+    ([0x00, 0x25, 0x00], "Apple"),
+    ([0x00, 0x25, 0x4b], "Apple"),
+    ([0x00, 0x25, 0xbc], "Apple"),
+    ([0x00, 0x26, 0x08], "Apple"),
+    ([0x00, 0x26, 0x4a], "Apple"),
+    ([0x00, 0x26, 0xb0], "Apple"),
+    ([0x00, 0x26, 0xbb], "Apple"),
+    ([0x00, 0x50, 0x56], "VMware"),
+    ([0x00, 0x50, 0xf2], "Microsoft"),
+    ([0x00, 0xe0, 0x4c], "Realtek"),
+    ([0x00, 0xe0, 0xfc], "Huawei"),
+    ([0x04, 0x18, 0xd6], "Ubiquiti"),
+    ([0x08, 0x00, 0x27], "VirtualBox"),
+    ([0x18, 0xb4, 0x30], "Nest Labs"),
+    ([0x18, 0xfe, 0x34], "Espressif"),
+    ([0x24, 0x0a, 0xc4], "Espressif"),
+    ([0x24, 0xa4, 0x3c], "Ubiquiti"),
+    ([0x28, 0xcd, 0xc1], "Raspberry Pi"),
+    ([0x30, 0xae, 0xa4], "Espressif"),
+    ([0x3c, 0x5a, 0xb4], "Google"),
+    ([0x44, 0x65, 0x0d], "Amazon"),
+    ([0x4c, 0x5e, 0x0c], "MikroTik"),
+    ([0x5c, 0xcf, 0x7f], "Espressif"),
+    ([0x60, 0x01, 0x94], "Espressif"),
+    ([0x74, 0xc2, 0x46], "Amazon"),
+    ([0x78, 0x8a, 0x20], "Ubiquiti"),
+    ([0x80, 0x2a, 0xa8], "Ubiquiti"),
+    ([0x84, 0xf3, 0xeb], "Espressif"),
+    ([0xa4, 0xcf, 0x12], "Espressif"),
+    ([0xb4, 0xfb, 0xe4], "Ubiquiti"),
+    ([0xb8, 0x27, 0xeb], "Raspberry Pi"),
+    ([0xd4, 0xca, 0x6d], "MikroTik"),
+    ([0xd8, 0x3a, 0xdd], "Raspberry Pi"),
+    ([0xdc, 0xa6, 0x32], "Raspberry Pi"),
+    ([0xe4, 0x5f, 0x01], "Raspberry Pi"),
+    ([0xec, 0xfa, 0xbc], "Espressif"),
+    ([0xf0, 0x27, 0x2d], "Amazon"),
+    ([0xf0, 0x9f, 0xc2], "Ubiquiti"),
+    ([0xf4, 0xf5, 0xd8], "Google"),
+    ([0xfc, 0x65, 0xde], "Amazon"),
+];
+
+/// Locally administered addresses are picked by software, eg. randomized wifi macs or qemu
+#[inline]
+pub fn is_locally_administered(mac: &[u8; 6]) -> bool {
+    mac[0] & 0x02 != 0
+}
+
+pub fn vendor(mac: &[u8; 6]) -> Option<&'static str> {
+    if is_locally_administered(mac) {
+        return Some("locally administered");
+    }
+
+    let prefix = [mac[0], mac[1], mac[2]];
+    VENDORS
+        .binary_search_by_key(&prefix, |(prefix, _)| *prefix)
+        .ok()
+        .map(|idx| VENDORS[idx].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorted() {
+        assert!(VENDORS.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn lookup() {
+        assert_eq!(vendor(&[0xb8, 0x27, 0xeb, 1, 2, 3]), Some("Raspberry Pi"));
+        assert_eq!(
+            vendor(&[0x52, 0x54, 0x00, 1, 2, 3]),
+            Some("locally administered")
+        );
+        assert_eq!(vendor(&[0x00, 0x00, 0x01, 1, 2, 3]), None);
+    }
+}
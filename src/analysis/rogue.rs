@@ -0,0 +1,233 @@
+use crate::analysis;
+use crate::bounded::BoundedMap;
+use crate::structs::dhcp::DHCP;
+use crate::structs::ether::Ether;
+use crate::structs::icmpv6::{ICMPv6, Prefix};
+use crate::structs::ipv4::IPv4;
+use crate::structs::ipv6::IPv6;
+use crate::structs::raw::Raw;
+use crate::structs::udp::UDP;
+use serde::Serialize;
+use std::hash::Hash;
+use std::mem;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::SystemTime;
+
+const MAX_ROGUES: usize = 256;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Alert {
+    DhcpServer {
+        server: Ipv4Addr,
+        mac: Option<[u8; 6]>,
+        router: Option<Vec<Ipv4Addr>>,
+        dns: Option<Vec<Ipv4Addr>>,
+    },
+    DhcpOptions {
+        server: Ipv4Addr,
+        router: Option<Vec<Ipv4Addr>>,
+        dns: Option<Vec<Ipv4Addr>>,
+        expected_router: Option<Vec<Ipv4Addr>>,
+        expected_dns: Option<Vec<Ipv4Addr>>,
+    },
+    Router {
+        router: Ipv6Addr,
+        mac: Option<[u8; 6]>,
+        prefixes: Vec<Prefix>,
+        dns: Vec<Ipv6Addr>,
+    },
+    RouterOptions {
+        router: Ipv6Addr,
+        prefixes: Vec<Prefix>,
+        dns: Vec<Ipv6Addr>,
+        expected_prefixes: Vec<Prefix>,
+        expected_dns: Vec<Ipv6Addr>,
+    },
+}
+
+enum Change<T> {
+    NewServer,
+    Options(T),
+}
+
+/// The first server we see is learned as the legitimate one
+struct Learner<K, T> {
+    trusted: Option<(K, T)>,
+    rogues: BoundedMap<K, (), MAX_ROGUES>,
+}
+
+impl<K: Clone + Eq + Hash, T: Clone + PartialEq> Learner<K, T> {
+    fn new() -> Learner<K, T> {
+        Learner {
+            trusted: None,
+            rogues: BoundedMap::default(),
+        }
+    }
+
+    fn observe(&mut self, timestamp: SystemTime, key: K, options: &T) -> Option<Change<T>> {
+        match &mut self.trusted {
+            None => {
+                self.trusted = Some((key, options.clone()));
+                None
+            }
+            Some((trusted, expected)) if *trusted == key => {
+                if expected != options {
+                    Some(Change::Options(mem::replace(expected, options.clone())))
+                } else {
+                    None
+                }
+            }
+            Some(_) => {
+                if self.rogues.touch(timestamp, &key).is_some() {
+                    None
+                } else {
+                    self.rogues.insert(timestamp, key, ());
+                    Some(Change::NewServer)
+                }
+            }
+        }
+    }
+}
+
+type DhcpOptions = (Option<Vec<Ipv4Addr>>, Option<Vec<Ipv4Addr>>);
+type RouterOptions = (Vec<Prefix>, Vec<Ipv6Addr>);
+
+/// Learns the dhcp servers and ipv6 routers of the network and alerts on new ones
+pub struct RogueWatch {
+    dhcp: Learner<Ipv4Addr, DhcpOptions>,
+    routers: Learner<Ipv6Addr, RouterOptions>,
+}
+
+impl Default for RogueWatch {
+    fn default() -> RogueWatch {
+        RogueWatch {
+            dhcp: Learner::new(),
+            routers: Learner::new(),
+        }
+    }
+}
+
+impl RogueWatch {
+    pub fn process(
+        &mut self,
+        timestamp: SystemTime,
+        packet: &Raw,
+        alerts: &mut Vec<analysis::Alert>,
+    ) {
+        let mac = match packet {
+            Raw::Ether(eth_frame, _) => Some(eth_frame.source_mac.0),
+            _ => None,
+        };
+
+        match packet.ether() {
+            Some(Ether::IPv4(
+                ip_hdr,
+                IPv4::UDP(_, UDP::DHCP(DHCP::OFFER(dhcp) | DHCP::ACK(dhcp))),
+            )) => {
+                let server = dhcp.server_identifier.unwrap_or(ip_hdr.source_addr);
+                let options = (dhcp.router.clone(), dhcp.domain_name_server.clone());
+
+                let alert = match self.dhcp.observe(timestamp, server, &options) {
+                    Some(Change::NewServer) => Alert::DhcpServer {
+                        server,
+                        mac,
+                        router: options.0,
+                        dns: options.1,
+                    },
+                    Some(Change::Options((expected_router, expected_dns))) => Alert::DhcpOptions {
+                        server,
+                        router: options.0,
+                        dns: options.1,
+                        expected_router,
+                        expected_dns,
+                    },
+                    None => return,
+                };
+                alerts.push(analysis::Alert::Rogue(alert));
+            }
+            Some(Ether::IPv6(ip_hdr, IPv6::ICMPv6(ICMPv6::RouterAdvertisement(ra)))) => {
+                let router = ip_hdr.source_addr;
+                let options = (ra.prefixes.clone(), ra.dns.clone());
+
+                let alert = match self.routers.observe(timestamp, router, &options) {
+                    Some(Change::NewServer) => Alert::Router {
+                        router,
+                        mac: ra.source_mac.or(mac),
+                        prefixes: options.0,
+                        dns: options.1,
+                    },
+                    Some(Change::Options((expected_prefixes, expected_dns))) => {
+                        Alert::RouterOptions {
+                            router,
+                            prefixes: options.0,
+                            dns: options.1,
+                            expected_prefixes,
+                            expected_dns,
+                        }
+                    }
+                    None => return,
+                };
+                alerts.push(analysis::Alert::Rogue(alert));
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fixtures;
+    use crate::structs::dhcp::Packet;
+
+    fn offer(server: &str, router: &str) -> Raw {
+        let server = server.parse().unwrap();
+        let mut packet = Packet::new(
+            Ipv4Addr::UNSPECIFIED,
+            "192.168.1.23".parse().unwrap(),
+            Ipv4Addr::UNSPECIFIED,
+            [0xaa; 6],
+        );
+        packet.server_identifier = Some(server);
+        packet.router = Some(vec![router.parse().unwrap()]);
+
+        fixtures::udp(
+            &format!("{}:67", server),
+            "255.255.255.255:68",
+            UDP::DHCP(DHCP::OFFER(packet)),
+        )
+    }
+
+    #[test]
+    fn detect_rogue_dhcp() {
+        let mut watch = RogueWatch::default();
+        let mut alerts = Vec::new();
+        let now = SystemTime::UNIX_EPOCH;
+
+        watch.process(now, &offer("192.168.1.1", "192.168.1.1"), &mut alerts);
+        watch.process(now, &offer("192.168.1.1", "192.168.1.1"), &mut alerts);
+        assert_eq!(alerts, vec![]);
+
+        watch.process(now, &offer("192.168.1.66", "192.168.1.66"), &mut alerts);
+        watch.process(now, &offer("192.168.1.66", "192.168.1.66"), &mut alerts);
+        watch.process(now, &offer("192.168.1.1", "192.168.1.254"), &mut alerts);
+        assert_eq!(
+            alerts,
+            vec![
+                analysis::Alert::Rogue(Alert::DhcpServer {
+                    server: "192.168.1.66".parse().unwrap(),
+                    mac: None,
+                    router: Some(vec!["192.168.1.66".parse().unwrap()]),
+                    dns: None,
+                }),
+                analysis::Alert::Rogue(Alert::DhcpOptions {
+                    server: "192.168.1.1".parse().unwrap(),
+                    router: Some(vec!["192.168.1.254".parse().unwrap()]),
+                    dns: None,
+                    expected_router: Some(vec!["192.168.1.1".parse().unwrap()]),
+                    expected_dns: None,
+                }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::time::SystemTime;
+
+/// When an entry was last used, the counter orders entries that were used at the same time
+type Age = (SystemTime, u64);
+
+struct Slot<V> {
+    value: V,
+    age: Age,
+}
+
+/// A map that holds at most `LIMIT` entries so hostile networks can't exhaust our memory,
+/// once it's full the entry that wasn't used for the longest time makes room for a new one
+pub struct BoundedMap<K, V, const LIMIT: usize> {
+    entries: HashMap<K, Slot<V>>,
+    ages: BTreeMap<Age, K>,
+    counter: u64,
+}
+
+impl<K, V, const LIMIT: usize> Default for BoundedMap<K, V, LIMIT> {
+    fn default() -> Self {
+        BoundedMap {
+            entries: HashMap::new(),
+            ages: BTreeMap::new(),
+            counter: 0,
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V, const LIMIT: usize> BoundedMap<K, V, LIMIT> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    #[inline]
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).map(|slot| &slot.value)
+    }
+
+    /// Access an entry without counting it as used
+    #[inline]
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.entries.get_mut(key).map(|slot| &mut slot.value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, slot)| (key, &slot.value))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.values().map(|slot| &slot.value)
+    }
+
+    /// Access every entry without counting them as used
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.entries.values_mut().map(|slot| &mut slot.value)
+    }
+
+    /// Remove the oldest entry if the map is full and `key` would be a new entry
+    pub fn evict(&mut self, key: &K) -> Option<(K, V)> {
+        if self.entries.len() < LIMIT || self.entries.contains_key(key) {
+            return None;
+        }
+        let (_, oldest) = self.ages.pop_first()?;
+        let slot = self.entries.remove(&oldest)?;
+        Some((oldest, slot.value))
+    }
+
+    /// Mark an entry as used
+    pub fn touch(&mut self, timestamp: SystemTime, key: &K) -> Option<&mut V> {
+        let previous = self.entries.get(key)?.age;
+        let age = self.age(timestamp, Some(previous));
+        self.ages.remove(&previous);
+        self.ages.insert(age, key.clone());
+        let slot = self.entries.get_mut(key)?;
+        slot.age = age;
+        Some(&mut slot.value)
+    }
+
+    /// Mark an entry as used, it's created first if it doesn't exist yet
+    pub fn touch_or_insert_with<F: FnOnce() -> V>(
+        &mut self,
+        timestamp: SystemTime,
+        key: K,
+        default: F,
+    ) -> &mut V {
+        self.evict(&key);
+        let previous = self.entries.get(&key).map(|slot| slot.age);
+        let age = self.age(timestamp, previous);
+        if let Some(previous) = previous {
+            self.ages.remove(&previous);
+        }
+        self.ages.insert(age, key.clone());
+        let slot = self.entries.entry(key).or_insert_with(|| Slot {
+            value: default(),
+            age,
+        });
+        slot.age = age;
+        &mut slot.value
+    }
+
+    /// Insert or replace an entry and mark it as used, returns the previous value
+    pub fn insert(&mut self, timestamp: SystemTime, key: K, value: V) -> Option<V> {
+        let previous = self.remove(&key);
+        self.touch_or_insert_with(timestamp, key, || value);
+        previous
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let slot = self.entries.remove(key)?;
+        self.ages.remove(&slot.age);
+        Some(slot.value)
+    }
+
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut keep: F) {
+        let ages = &mut self.ages;
+        self.entries.retain(|key, slot| {
+            let retain = keep(key, &mut slot.value);
+            if !retain {
+                ages.remove(&slot.age);
+            }
+            retain
+        });
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = (K, V)> {
+        self.ages.clear();
+        self.entries.drain().map(|(key, slot)| (key, slot.value))
+    }
+
+    /// Capture timestamps can go back in time (merged capture files, clock steps), an entry never gets older
+    fn age(&mut self, timestamp: SystemTime, previous: Option<Age>) -> Age {
+        self.counter += 1;
+        let time = previous.map_or(timestamp, |(time, _)| time.max(timestamp));
+        (time, self.counter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn bounded_map() {
+        let now = SystemTime::UNIX_EPOCH;
+        let later = now + Duration::from_secs(1);
+        let mut map = BoundedMap::<&str, u32, 2>::default();
+        map.insert(now, "a", 1);
+        map.insert(now, "b", 2);
+        // a timestamp from the past doesn't make an entry older
+        map.touch(later, &"a");
+        map.touch(now, &"a");
+
+        assert_eq!(map.evict(&"a"), None);
+        assert_eq!(map.evict(&"c"), Some(("b", 2)));
+        map.insert(now, "c", 3);
+        map.insert(later, "d", 4);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&"a"), Some(&1));
+        assert_eq!(map.get(&"d"), Some(&4));
+    }
+}
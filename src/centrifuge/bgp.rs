@@ -0,0 +1,369 @@
+use crate::structs::CentrifugeError;
+use crate::structs::bgp::{
+    Capability, Message, Notification, Open, Origin, Prefix, Segment, Update,
+};
+use nom::bytes::complete::take;
+use nom::number::complete::{be_u8, be_u16, be_u32};
+use pktparse::tcp::TcpHeader;
+use std::net::Ipv4Addr;
+
+pub const PORT: u16 = 179;
+const MARKER: [u8; 16] = [0xff; 16];
+const HEADER_LENGTH: usize = 19;
+
+/// Every complete message of a segment, a message that continues in the next segment is skipped
+pub fn extract(tcp_hdr: &TcpHeader, mut remaining: &[u8]) -> Result<Vec<Message>, CentrifugeError> {
+    if tcp_hdr.source_port != PORT && tcp_hdr.dest_port != PORT {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+    if !remaining.starts_with(&MARKER) {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+
+    let mut messages = Vec::new();
+    while remaining.len() >= HEADER_LENGTH && remaining.starts_with(&MARKER) {
+        let length = usize::from(u16::from_be_bytes([remaining[16], remaining[17]]));
+        if length < HEADER_LENGTH {
+            return Err(CentrifugeError::InvalidPacket);
+        }
+        let Some(body) = remaining.get(HEADER_LENGTH..length) else {
+            break;
+        };
+        let message = message(remaining[18], body).map_err(|_| CentrifugeError::InvalidPacket)?;
+        messages.push(message);
+        remaining = &remaining[length..];
+    }
+
+    if messages.is_empty() {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+    Ok(messages)
+}
+
+fn message(kind: u8, body: &[u8]) -> Result<Message, nom::Err<nom::error::Error<&[u8]>>> {
+    let message = match kind {
+        1 => Message::Open(open(body)?.1),
+        2 => Message::Update(update(body)?.1),
+        3 => {
+            let (data, code) = be_u8(body)?;
+            let (data, subcode) = be_u8(data)?;
+            Message::Notification(Notification {
+                code,
+                subcode,
+                reason: shutdown_reason(code, subcode, data),
+                data: data.to_vec(),
+            })
+        }
+        4 if body.is_empty() => Message::Keepalive,
+        4 => return Err(nom_error(body)),
+        5 => {
+            let (remaining, afi) = be_u16(body)?;
+            let (remaining, _reserved) = be_u8(remaining)?;
+            let (_, safi) = be_u8(remaining)?;
+            Message::RouteRefresh { afi, safi }
+        }
+        _ => Message::Unknown(kind, body.to_vec()),
+    };
+    Ok(message)
+}
+
+fn open(input: &[u8]) -> nom::IResult<&[u8], Open> {
+    let (remaining, version) = be_u8(input)?;
+    let (remaining, asn) = be_u16(remaining)?;
+    let (remaining, hold_time) = be_u16(remaining)?;
+    let (remaining, router_id) = be_u32(remaining)?;
+    let (remaining, params_length) = be_u8(remaining)?;
+    let (remaining, mut params) = take(params_length)(remaining)?;
+
+    let mut capabilities = Vec::new();
+    while !params.is_empty() {
+        let (rest, kind) = be_u8(params)?;
+        let (rest, length) = be_u8(rest)?;
+        let (rest, mut param) = take(length)(rest)?;
+        params = rest;
+
+        // only capabilities are still in use, rfc5492
+        if kind != 2 {
+            continue;
+        }
+        while !param.is_empty() {
+            let (rest, code) = be_u8(param)?;
+            let (rest, length) = be_u8(rest)?;
+            let (rest, value) = take(length)(rest)?;
+            param = rest;
+            capabilities.push(capability(code, value)?);
+        }
+    }
+
+    let asn = capabilities
+        .iter()
+        .find_map(|x| match x {
+            Capability::FourOctetAs(asn) => Some(*asn),
+            _ => None,
+        })
+        .unwrap_or(u32::from(asn));
+    Ok((
+        remaining,
+        Open {
+            version,
+            asn,
+            hold_time,
+            router_id: Ipv4Addr::from(router_id),
+            capabilities,
+        },
+    ))
+}
+
+fn capability(code: u8, value: &[u8]) -> Result<Capability, nom::Err<nom::error::Error<&[u8]>>> {
+    let capability = match (code, value.len()) {
+        (1, 4) => {
+            let (remaining, afi) = be_u16(value)?;
+            let (remaining, _reserved) = be_u8(remaining)?;
+            let (_, safi) = be_u8(remaining)?;
+            Capability::Multiprotocol { afi, safi }
+        }
+        (2, 0) => Capability::RouteRefresh,
+        (65, 4) => Capability::FourOctetAs(be_u32(value)?.1),
+        _ => Capability::Unknown(code, value.to_vec()),
+    };
+    Ok(capability)
+}
+
+fn update(input: &[u8]) -> nom::IResult<&[u8], Update> {
+    let (remaining, withdrawn_length) = be_u16(input)?;
+    let (remaining, withdrawn) = take(withdrawn_length)(remaining)?;
+    let (remaining, attributes_length) = be_u16(remaining)?;
+    let (nlri, mut attributes) = take(attributes_length)(remaining)?;
+
+    let mut update = Update {
+        withdrawn: prefixes(withdrawn)?,
+        nlri: prefixes(nlri)?,
+        ..Default::default()
+    };
+
+    while !attributes.is_empty() {
+        let (rest, flags) = be_u8(attributes)?;
+        let (rest, kind) = be_u8(rest)?;
+        let (rest, length) = if flags & 0x10 != 0 {
+            be_u16(rest)?
+        } else {
+            let (rest, length) = be_u8(rest)?;
+            (rest, u16::from(length))
+        };
+        let (rest, value) = take(length)(rest)?;
+        attributes = rest;
+
+        match (kind, value.len()) {
+            (1, 1) => {
+                update.origin = Some(match value[0] {
+                    0 => Origin::IGP,
+                    1 => Origin::EGP,
+                    2 => Origin::Incomplete,
+                    _ => return Err(nom_error(value)),
+                })
+            }
+            (2, _) => update.as_path = as_path(value).ok_or_else(|| nom_error(value))?,
+            (3, 4) => update.next_hop = Some(Ipv4Addr::from(be_u32(value)?.1)),
+            (4, 4) => update.med = Some(be_u32(value)?.1),
+            (5, 4) => update.local_pref = Some(be_u32(value)?.1),
+            (8, _) if value.len() % 4 == 0 => {
+                update.communities = value
+                    .chunks(4)
+                    .map(|x| u32::from_be_bytes([x[0], x[1], x[2], x[3]]))
+                    .collect();
+            }
+            (1 | 3 | 4 | 5 | 8, _) => return Err(nom_error(value)),
+            _ => update.other_attributes.push(kind),
+        }
+    }
+
+    Ok((&[], update))
+}
+
+fn prefixes(mut remaining: &[u8]) -> Result<Vec<Prefix>, nom::Err<nom::error::Error<&[u8]>>> {
+    let mut prefixes = Vec::new();
+    while !remaining.is_empty() {
+        let (rest, length) = be_u8(remaining)?;
+        if length > 32 {
+            return Err(nom_error(remaining));
+        }
+        let (rest, bytes) = take(length.div_ceil(8))(rest)?;
+        remaining = rest;
+
+        let mut network = [0; 4];
+        network[..bytes.len()].copy_from_slice(bytes);
+        prefixes.push(Prefix {
+            network: Ipv4Addr::from(network),
+            length,
+        });
+    }
+    Ok(prefixes)
+}
+
+/// The asn size depends on the capabilities of both peers, a path that doesn't decode cleanly
+/// with 2-byte asns is decoded with 4-byte asns
+fn as_path(value: &[u8]) -> Option<Vec<Segment>> {
+    segments(value, 2).or_else(|| segments(value, 4))
+}
+
+fn segments(mut remaining: &[u8], asn_size: usize) -> Option<Vec<Segment>> {
+    let mut segments = Vec::new();
+    while !remaining.is_empty() {
+        let kind = *remaining.first()?;
+        let count = usize::from(*remaining.get(1)?);
+        let asns = remaining.get(2..2 + count * asn_size)?;
+        remaining = &remaining[2 + count * asn_size..];
+
+        let asns = asns
+            .chunks(asn_size)
+            .map(|x| x.iter().fold(0, |acc, &b| (acc << 8) | u32::from(b)))
+            .collect::<Vec<_>>();
+        // as 0 is reserved and mostly shows up as the upper half of a 4-byte asn, rfc7607
+        if asns.is_empty() || asns.contains(&0) {
+            return None;
+        }
+
+        segments.push(match kind {
+            1 => Segment::Set(asns),
+            2 => Segment::Sequence(asns),
+            3 => Segment::ConfedSequence(asns),
+            4 => Segment::ConfedSet(asns),
+            _ => return None,
+        });
+    }
+    Some(segments)
+}
+
+/// Operators can include a message when shutting down or resetting a session, rfc8203
+fn shutdown_reason(code: u8, subcode: u8, data: &[u8]) -> Option<String> {
+    if code != 6 || !matches!(subcode, 2 | 4) {
+        return None;
+    }
+    let (length, text) = data.split_first()?;
+    let text = text.get(..usize::from(*length))?;
+    if text.is_empty() {
+        return None;
+    }
+    String::from_utf8(text.to_vec()).ok()
+}
+
+#[inline]
+fn nom_error(input: &[u8]) -> nom::Err<nom::error::Error<&[u8]>> {
+    nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_encoding::HEXLOWER;
+
+    fn header(source_port: u16, dest_port: u16) -> TcpHeader {
+        TcpHeader {
+            source_port,
+            dest_port,
+            sequence_no: 0,
+            ack_no: 0,
+            data_offset: 5,
+            reserved: 0,
+            flag_urg: false,
+            flag_ack: true,
+            flag_psh: true,
+            flag_rst: false,
+            flag_syn: false,
+            flag_fin: false,
+            window: 0,
+            checksum: 0,
+            urgent_pointer: 0,
+            options: None,
+        }
+    }
+
+    fn decode(hex: &str) -> Vec<u8> {
+        HEXLOWER.decode(hex.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn open() {
+        let messages = extract(
+            &header(2124, 179),
+            &decode(
+                "ffffffffffffffffffffffffffffffff002d01045ba000b40a000001100206010400010001020641040003d090",
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            messages,
+            [Message::Open(Open {
+                version: 4,
+                asn: 250_000,
+                hold_time: 180,
+                router_id: Ipv4Addr::new(10, 0, 0, 1),
+                capabilities: vec![
+                    Capability::Multiprotocol { afi: 1, safi: 1 },
+                    Capability::FourOctetAs(250_000),
+                ],
+            })]
+        );
+    }
+
+    #[test]
+    fn keepalive_and_update() {
+        let messages = extract(
+            &header(2124, 179),
+            &decode(concat!(
+                "ffffffffffffffffffffffffffffffff001304",
+                "ffffffffffffffffffffffffffffffff006202000000484001010240020a010201f401f40201febb",
+                "400304c0a8000f40050400000064400600c00706febac0a8000ac0080cfebf000103160004015400fa",
+                "800904c0a8000f800a04c0a800fa10ac10",
+                // the next update continues in the following segment
+                "ffffffffffffffffffffffffffffffff006302000000484001010040020a010201f401f40201febb",
+            )),
+        )
+        .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0], Message::Keepalive);
+        let Message::Update(update) = &messages[1] else {
+            panic!("expected update, got {:?}", messages[1]);
+        };
+        assert_eq!(update.origin, Some(Origin::Incomplete));
+        assert_eq!(
+            update.as_path,
+            [Segment::Set(vec![500, 500]), Segment::Sequence(vec![65211])]
+        );
+        assert_eq!(update.next_hop, Some(Ipv4Addr::new(192, 168, 0, 15)));
+        assert_eq!(update.local_pref, Some(100));
+        assert_eq!(update.communities, [0xfebf_0001, 0x0316_0004, 0x0154_00fa]);
+        assert_eq!(update.other_attributes, [6, 7, 9, 10]);
+        assert_eq!(
+            update.nlri,
+            [Prefix {
+                network: Ipv4Addr::new(172, 16, 0, 0),
+                length: 16,
+            }]
+        );
+    }
+
+    #[test]
+    fn four_byte_as_path() {
+        assert_eq!(
+            as_path(&decode("02020003d0900000fde8")),
+            Some(vec![Segment::Sequence(vec![250_000, 65000])])
+        );
+    }
+
+    #[test]
+    fn notification() {
+        let mut data = decode("ffffffffffffffffffffffffffffffff002303060208");
+        data.extend(b"shutdown");
+        data[17] = data.len() as u8;
+        let messages = extract(&header(179, 2124), &data).unwrap();
+        let Message::Notification(notification) = &messages[0] else {
+            panic!("expected notification, got {:?}", messages[0]);
+        };
+        assert_eq!(notification.error(), "Cease");
+        assert_eq!(notification.suberror(), Some("Administrative Shutdown"));
+        assert_eq!(notification.reason.as_deref(), Some("shutdown"));
+
+        assert!(extract(&header(2124, 8080), &data).is_err());
+    }
+}
@@ -35,10 +35,14 @@ pub fn extract(remaining: &[u8]) -> Result<structs::dhcp::DHCP, CentrifugeError>
         match option {
             DhcpOption::RequestedIpAddress(addr) => packet.requested_ip_address = Some(*addr),
             DhcpOption::HostName(hostname) => packet.hostname = Some(hostname.to_string()),
+            DhcpOption::ServerIdentifier(addr) => packet.server_identifier = Some(*addr),
             DhcpOption::Router(router) => packet.router = Some(router.clone()),
             DhcpOption::DomainNameServer(server) => {
                 packet.domain_name_server = Some(server.clone())
             }
+            DhcpOption::ParameterRequestList(list) => {
+                packet.parameter_request_list = Some(list.clone())
+            }
             _ => (),
         }
     }
@@ -2,15 +2,15 @@ use crate::structs::{self, CentrifugeError};
 
 pub fn extract(remaining: &[u8]) -> Result<structs::dns::DNS, CentrifugeError> {
     if let Ok(dns) = dns_parser::Packet::parse(remaining) {
+        let questions = dns
+            .questions
+            .into_iter()
+            .map(|q| (q.qtype.into(), q.qname.to_string()))
+            .collect();
+
         if dns.header.query {
             // dns request
-            let questions = dns
-                .questions
-                .into_iter()
-                .map(|q| (q.qtype.into(), q.qname.to_string()))
-                .collect();
-
-            Ok(structs::dns::Request::new(questions).wrap())
+            Ok(structs::dns::Request::new(dns.header.id, questions).wrap())
         } else {
             // dns response
             let answers = dns
@@ -19,7 +19,13 @@ pub fn extract(remaining: &[u8]) -> Result<structs::dns::DNS, CentrifugeError> {
                 .map(|a| (a.name.to_string(), structs::dns::Record::from(a.data)))
                 .collect();
 
-            Ok(structs::dns::Response::new(answers).wrap())
+            Ok(structs::dns::Response::new(
+                dns.header.id,
+                dns.header.response_code.into(),
+                questions,
+                answers,
+            )
+            .wrap())
         }
     } else {
         Err(structs::CentrifugeError::WrongProtocol)
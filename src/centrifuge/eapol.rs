@@ -0,0 +1,104 @@
+use crate::structs::CentrifugeError;
+use crate::structs::eapol::{EAP, EAPOL, Message, Method};
+use bstr::BString;
+use nom::bytes::complete::take;
+use nom::number::complete::{be_u8, be_u16};
+
+fn method(method_type: u8, data: &[u8]) -> Method {
+    match method_type {
+        1 => Method::Identity(BString::from(data)),
+        2 => Method::Notification(BString::from(data)),
+        3 => Method::Nak(data.to_vec()),
+        4 => Method::MD5,
+        13 => Method::TLS,
+        21 => Method::TTLS,
+        25 => Method::PEAP,
+        26 => Method::MSCHAPv2,
+        43 => Method::FAST,
+        x => Method::Other(x),
+    }
+}
+
+fn eap(remaining: &[u8]) -> nom::IResult<&[u8], EAP> {
+    let (remaining, code) = be_u8(remaining)?;
+    let (remaining, id) = be_u8(remaining)?;
+    let (remaining, length) = be_u16(remaining)?;
+
+    let eap = match code {
+        1 | 2 => {
+            let (remaining, method_type) = be_u8(remaining)?;
+            let (_, data) = take((length as usize).saturating_sub(5))(remaining)?;
+            let msg = Message {
+                id,
+                method: method(method_type, data),
+            };
+            if code == 1 {
+                EAP::Request(msg)
+            } else {
+                EAP::Response(msg)
+            }
+        }
+        3 => EAP::Success(id),
+        4 => EAP::Failure(id),
+        _ => {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                remaining,
+                nom::error::ErrorKind::Switch,
+            )));
+        }
+    };
+
+    Ok((remaining, eap))
+}
+
+fn eapol(remaining: &[u8]) -> nom::IResult<&[u8], EAPOL> {
+    let (remaining, _version) = be_u8(remaining)?;
+    let (remaining, packet_type) = be_u8(remaining)?;
+    let (remaining, length) = be_u16(remaining)?;
+    let (remaining, body) = take(length as usize)(remaining)?;
+
+    let eapol = match packet_type {
+        0 => {
+            let (_, eap) = eap(body)?;
+            EAPOL::EAP(eap)
+        }
+        1 => EAPOL::Start,
+        2 => EAPOL::Logoff,
+        3 => {
+            let (_, descriptor) = be_u8(body)?;
+            EAPOL::Key(descriptor)
+        }
+        x => EAPOL::Unknown(x, body.to_vec()),
+    };
+
+    Ok((remaining, eapol))
+}
+
+pub fn parse(remaining: &[u8]) -> Result<EAPOL, CentrifugeError> {
+    if let Ok((_remaining, eapol)) = eapol(remaining) {
+        Ok(eapol)
+    } else {
+        Err(CentrifugeError::InvalidPacket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_identity_response() {
+        let pkt = &[
+            0x01, 0x00, 0x00, 0x0a, 0x02, 0x01, 0x00, 0x0a, 0x01, 0x61, 0x6c, 0x69, 0x63, 0x65,
+            // padding
+            0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(
+            parse(pkt),
+            Ok(EAPOL::EAP(EAP::Response(Message {
+                id: 1,
+                method: Method::Identity(BString::from("alice")),
+            })))
+        );
+    }
+}
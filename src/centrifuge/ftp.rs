@@ -0,0 +1,343 @@
+use crate::bounded::BoundedMap;
+use crate::structs::CentrifugeError;
+use crate::structs::ftp::{self, Command, Data, DataPort, FTP, Reply};
+use crate::structs::tcp::TCP;
+use bstr::ByteSlice;
+use pktparse::tcp::TcpHeader;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::SystemTime;
+
+pub const CONTROL_PORT: u16 = 21;
+
+const MAX_CONNECTIONS: usize = 1024;
+/// Control lines are short, anything longer is likely something else
+const MAX_LINE_LENGTH: usize = 4096;
+
+pub fn extract(tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<FTP, CentrifugeError> {
+    let text = remaining
+        .to_str()
+        .map_err(|_| CentrifugeError::WrongProtocol)?;
+    if tcp_hdr.dest_port == CONTROL_PORT {
+        command(text).map(FTP::Command)
+    } else if tcp_hdr.source_port == CONTROL_PORT {
+        reply(text).map(FTP::Reply)
+    } else {
+        Err(CentrifugeError::WrongProtocol)
+    }
+}
+
+/// A single command line, clients wait for the reply before sending the next one
+fn command(text: &str) -> Result<Command, CentrifugeError> {
+    let line = text
+        .strip_suffix("\r\n")
+        .or_else(|| text.strip_suffix('\n'))
+        .ok_or(CentrifugeError::WrongProtocol)?;
+    if line.len() > MAX_LINE_LENGTH || line.contains(['\r', '\n']) {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+
+    let (command, argument) = match line.split_once(' ') {
+        Some((command, argument)) => (command, Some(argument.to_string())),
+        None => (line, None),
+    };
+    if !(3..=4).contains(&command.len()) || !command.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+    let command = command.to_ascii_uppercase();
+
+    let data_port = match (command.as_str(), &argument) {
+        ("PORT", Some(argument)) => port_argument(argument),
+        ("EPRT", Some(argument)) => extended_port(argument),
+        _ => None,
+    };
+
+    Ok(Command {
+        command,
+        argument,
+        data_port,
+    })
+}
+
+/// A reply with one or more lines, all lines of a multiline reply start with the same code
+fn reply(text: &str) -> Result<Reply, CentrifugeError> {
+    let text = text
+        .strip_suffix("\r\n")
+        .or_else(|| text.strip_suffix('\n'))
+        .ok_or(CentrifugeError::WrongProtocol)?;
+
+    let mut code = None;
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        if line.len() > MAX_LINE_LENGTH {
+            return Err(CentrifugeError::WrongProtocol);
+        }
+        match reply_line(line) {
+            Some((line_code, text)) if code.is_none_or(|code| code == line_code) => {
+                code = Some(line_code);
+                lines.push(text.to_string());
+            }
+            // continuation lines of a multiline reply don't need a code
+            _ if code.is_some() => lines.push(line.to_string()),
+            _ => return Err(CentrifugeError::WrongProtocol),
+        }
+    }
+    let code = code.ok_or(CentrifugeError::WrongProtocol)?;
+
+    let data_port = match code {
+        227 => lines.first().and_then(|line| passive_port(line)),
+        229 => lines.first().and_then(|line| extended_passive_port(line)),
+        _ => None,
+    };
+
+    Ok(Reply {
+        code,
+        lines,
+        data_port,
+    })
+}
+
+fn reply_line(line: &str) -> Option<(u16, &str)> {
+    let code = line.get(..3)?;
+    if !code.chars().all(|c| c.is_ascii_digit()) || !(b'1'..=b'5').contains(&code.as_bytes()[0]) {
+        return None;
+    }
+    let text = match line.get(3..4)? {
+        " " | "-" => &line[4..],
+        _ => return None,
+    };
+    Some((code.parse().ok()?, text))
+}
+
+/// `h1,h2,h3,h4,p1,p2` as used by `PORT` and `227` replies
+fn port_argument(text: &str) -> Option<DataPort> {
+    let numbers = text
+        .trim()
+        .split(',')
+        .map(|x| x.trim().parse::<u8>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let [h1, h2, h3, h4, p1, p2] = numbers.as_slice() else {
+        return None;
+    };
+    Some(DataPort {
+        ip: Some(IpAddr::V4(Ipv4Addr::new(*h1, *h2, *h3, *h4))),
+        port: u16::from(*p1) << 8 | u16::from(*p2),
+    })
+}
+
+/// The address is usually in parentheses, but rfc959 doesn't require them
+fn passive_port(line: &str) -> Option<DataPort> {
+    let start = line.find(|c: char| c.is_ascii_digit())?;
+    let rest = &line[start..];
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != ',')
+        .unwrap_or(rest.len());
+    port_argument(&rest[..end])
+}
+
+/// `|1|132.235.1.2|6275|` from rfc2428, the first character is the delimiter
+fn extended_port(text: &str) -> Option<DataPort> {
+    let delimiter = text.chars().next()?;
+    let mut fields = text.split(delimiter).skip(1);
+    let protocol = fields.next()?;
+    let ip = fields.next()?;
+    let port = fields.next()?.parse().ok()?;
+    let ip = match protocol {
+        "1" | "2" => Some(ip.parse().ok()?),
+        // replies to EPSV leave out the protocol and address
+        "" if ip.is_empty() => None,
+        _ => return None,
+    };
+    Some(DataPort { ip, port })
+}
+
+/// `Entering Extended Passive Mode (|||6446|)`
+fn extended_passive_port(line: &str) -> Option<DataPort> {
+    let start = line.find('(')?;
+    let end = line[start..].find(')')? + start;
+    extended_port(&line[start + 1..end])
+}
+
+#[derive(Default)]
+struct Expected {
+    command: Option<String>,
+    file: Option<String>,
+}
+
+/// Data connections use ports that are negotiated on the control connection
+#[derive(Default)]
+pub struct Expectations {
+    /// The address that was announced for the next data connection
+    controls: BoundedMap<(SocketAddr, SocketAddr), SocketAddr, MAX_CONNECTIONS>,
+    /// Keyed by the address that accepts the data connection
+    expected: BoundedMap<SocketAddr, Expected, MAX_CONNECTIONS>,
+}
+
+impl Expectations {
+    pub fn track(
+        &mut self,
+        timestamp: SystemTime,
+        src: SocketAddr,
+        dst: SocketAddr,
+        tcp: &mut TCP,
+    ) {
+        match tcp {
+            TCP::FTP(FTP::Command(command)) => {
+                if let Some(data_port) = &command.data_port {
+                    // the address in the command is wrong behind nat, the client is the one listening
+                    let data = SocketAddr::new(src.ip(), data_port.port);
+                    self.expect(timestamp, (src, dst), data);
+                } else if ftp::is_transfer(&command.command) {
+                    let file = match command.command.as_str() {
+                        "LIST" | "NLST" | "MLSD" => None,
+                        _ => command.argument.clone(),
+                    };
+                    if let Some(data) = self.controls.get(&(src, dst))
+                        && let Some(expected) = self.expected.touch(timestamp, data)
+                    {
+                        expected.command = Some(command.command.clone());
+                        expected.file = file;
+                    }
+                }
+            }
+            TCP::FTP(FTP::Reply(reply)) => {
+                if let Some(data_port) = &reply.data_port {
+                    let data = SocketAddr::new(src.ip(), data_port.port);
+                    self.expect(timestamp, (dst, src), data);
+                }
+            }
+            TCP::Text(_) | TCP::Binary(_) => {
+                let key = if self.expected.contains_key(&dst) {
+                    dst
+                } else {
+                    src
+                };
+                let Some(expected) = self.expected.touch(timestamp, &key) else {
+                    return;
+                };
+
+                let data = match std::mem::replace(tcp, TCP::Empty) {
+                    TCP::Text(text) => text.into_bytes(),
+                    TCP::Binary(data) => data,
+                    _ => unreachable!(),
+                };
+                *tcp = TCP::FTP(FTP::Data(Data {
+                    command: expected.command.clone(),
+                    file: expected.file.clone(),
+                    data,
+                }));
+            }
+            _ => (),
+        }
+    }
+
+    fn expect(
+        &mut self,
+        timestamp: SystemTime,
+        control: (SocketAddr, SocketAddr),
+        data: SocketAddr,
+    ) {
+        if let Some(previous) = self.controls.insert(timestamp, control, data)
+            && previous != data
+        {
+            self.expected.remove(&previous);
+        }
+        self.expected.insert(timestamp, data, Expected::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn port_commands() {
+        let command = command("PORT 192,168,1,10,195,80\r\n").unwrap();
+        assert_eq!(
+            command.data_port,
+            Some(DataPort {
+                ip: Some("192.168.1.10".parse().unwrap()),
+                port: 50000,
+            })
+        );
+        let command = super::command("EPRT |2|2001:db8::1|6275|\r\n").unwrap();
+        assert_eq!(
+            command.data_port,
+            Some(DataPort {
+                ip: Some("2001:db8::1".parse().unwrap()),
+                port: 6275,
+            })
+        );
+        assert!(super::command("GET / HTTP/1.1\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn passive_replies() {
+        let reply = reply("227 Entering Passive Mode (192,0,2,1,156,64).\r\n").unwrap();
+        assert_eq!(
+            reply.data_port,
+            Some(DataPort {
+                ip: Some("192.0.2.1".parse().unwrap()),
+                port: 40000,
+            })
+        );
+        let reply = super::reply("229 Entering Extended Passive Mode (|||6446|)\r\n").unwrap();
+        assert_eq!(
+            reply.data_port,
+            Some(DataPort {
+                ip: None,
+                port: 6446,
+            })
+        );
+    }
+
+    #[test]
+    fn multiline_reply() {
+        let reply = reply("230-Welcome\r\n to the archive\r\n230 Login successful.\r\n").unwrap();
+        assert_eq!(reply.code, 230);
+        assert_eq!(
+            reply.lines,
+            ["Welcome", " to the archive", "Login successful."]
+        );
+        assert!(super::reply("SSH-2.0-OpenSSH_9.6\r\n").is_err());
+    }
+
+    #[test]
+    fn data_connection() {
+        let now = SystemTime::now();
+        let client: SocketAddr = "192.168.1.10:50000".parse().unwrap();
+        let server: SocketAddr = "192.0.2.1:21".parse().unwrap();
+        let mut expectations = Expectations::default();
+
+        let mut tcp = TCP::FTP(FTP::Reply(
+            reply("227 Entering Passive Mode (10,0,0,1,156,64)\r\n").unwrap(),
+        ));
+        expectations.track(now, server, client, &mut tcp);
+        let mut tcp = TCP::FTP(FTP::Command(command("RETR notes.txt\r\n").unwrap()));
+        expectations.track(now, client, server, &mut tcp);
+
+        let mut tcp = TCP::Text("hello world\n".to_string());
+        expectations.track(
+            now,
+            "192.0.2.1:40000".parse().unwrap(),
+            "192.168.1.10:50001".parse().unwrap(),
+            &mut tcp,
+        );
+        assert_eq!(
+            tcp,
+            TCP::FTP(FTP::Data(Data {
+                command: Some("RETR".to_string()),
+                file: Some("notes.txt".to_string()),
+                data: b"hello world\n".to_vec(),
+            }))
+        );
+
+        let mut tcp = TCP::Text("hello world\n".to_string());
+        expectations.track(
+            now,
+            "192.0.2.1:40001".parse().unwrap(),
+            "192.168.1.10:50001".parse().unwrap(),
+            &mut tcp,
+        );
+        assert_eq!(tcp, TCP::Text("hello world\n".to_string()));
+    }
+}
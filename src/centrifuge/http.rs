@@ -1,8 +1,13 @@
 use crate::structs::CentrifugeError;
-use crate::structs::http::{Http, Request, Response};
-use bstr::BString;
+use crate::structs::http::{self, Body, Http, Request, Response};
+use bstr::{BString, ByteSlice};
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
 use httparse::Status;
 use std::convert::TryFrom;
+use std::io::Read;
+
+/// Upper limit for bodies decoded for display, protects against decompression bombs
+pub const MAX_BODY_LENGTH: usize = 1024 * 1024;
 
 pub fn extract(remaining: &[u8]) -> Result<Http, CentrifugeError> {
     let mut req_headers = [httparse::EMPTY_HEADER; 256];
@@ -39,3 +44,240 @@ pub fn extract(remaining: &[u8]) -> Result<Http, CentrifugeError> {
         Err(CentrifugeError::WrongProtocol)
     }
 }
+
+/// Remove the transfer and content codings of a body, the decoded data is cut off after `limit` bytes
+pub fn decode(body: &[u8], headers: &[(String, BString)], limit: usize) -> Body {
+    let transfer_encoding = http::header(headers, "transfer-encoding");
+    let content_encoding = http::header(headers, "content-encoding");
+    let content_length = http::header(headers, "content-length");
+    let content_type = http::header(headers, "content-type");
+
+    let mut encodings = Vec::new();
+    let mut truncated = false;
+    let mut data = body.to_vec();
+
+    let transfer_encoding = codings(transfer_encoding);
+    if transfer_encoding.iter().any(|x| x == "chunked") {
+        let (dechunked, complete) = dechunk(&data, limit);
+        data = dechunked;
+        truncated |= !complete;
+        encodings.push("chunked".to_string());
+    } else if let Some(length) = content_length
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.trim().parse::<usize>().ok())
+    {
+        truncated |= data.len() < length;
+        data.truncate(length);
+    }
+
+    // codings are listed in the order they were applied
+    let mut content_encoding = codings(content_encoding);
+    content_encoding.retain(|x| x != "identity");
+    let mut decoded = true;
+    while let Some(coding) = content_encoding.pop() {
+        let Some((inflated, complete)) = decompress(&coding, &data, limit) else {
+            decoded = false;
+            break;
+        };
+        data = inflated;
+        truncated |= !complete;
+        encodings.push(coding);
+    }
+
+    let content_type = content_type
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.trim().to_string());
+    let text = decoded
+        && match &content_type {
+            Some(content_type) => is_text(content_type),
+            None => looks_like_text(&data),
+        };
+
+    Body {
+        encodings,
+        content_type,
+        text,
+        truncated,
+        data: data.into(),
+    }
+}
+
+fn codings(header: Option<&BString>) -> Vec<String> {
+    let Some(header) = header else {
+        return Vec::new();
+    };
+    header
+        .split_str(",")
+        .map(|x| x.trim().to_str_lossy().to_ascii_lowercase())
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+/// Remove the chunked transfer coding, returns false if the body ended before the last chunk
+fn dechunk(mut body: &[u8], limit: usize) -> (Vec<u8>, bool) {
+    let mut out = Vec::new();
+    loop {
+        let Some(idx) = body.find(b"\r\n") else {
+            return (out, false);
+        };
+        // ignore chunk extensions
+        let size = body[..idx].split_str(";").next().unwrap_or_default();
+        let Some(size) = size
+            .trim()
+            .to_str()
+            .ok()
+            .and_then(|x| usize::from_str_radix(x, 16).ok())
+        else {
+            return (out, false);
+        };
+        body = &body[idx + 2..];
+
+        if size == 0 {
+            return (out, true);
+        }
+
+        let available = size.min(body.len());
+        let available = available.min(limit.saturating_sub(out.len()));
+        out.extend_from_slice(&body[..available]);
+        if available < size || body.len() < size + 2 {
+            return (out, false);
+        }
+        body = &body[size + 2..];
+    }
+}
+
+/// Decompress a content coding, returns None for unsupported codings
+fn decompress(coding: &str, data: &[u8], limit: usize) -> Option<(Vec<u8>, bool)> {
+    match coding {
+        "gzip" | "x-gzip" => Some(read_limited(GzDecoder::new(data), limit)),
+        "deflate" => {
+            // some servers send a raw deflate stream without the zlib header
+            let (out, complete) = read_limited(ZlibDecoder::new(data), limit);
+            if complete || !out.is_empty() {
+                Some((out, complete))
+            } else {
+                Some(read_limited(DeflateDecoder::new(data), limit))
+            }
+        }
+        "br" => Some(read_limited(
+            brotli_decompressor::Decompressor::new(data, 4096),
+            limit,
+        )),
+        _ => None,
+    }
+}
+
+/// Read until the end of the stream or the size limit, a stream that is cut off returns what was decoded so far
+fn read_limited<R: Read>(reader: R, limit: usize) -> (Vec<u8>, bool) {
+    let mut out = Vec::new();
+    let complete = reader.take(limit as u64 + 1).read_to_end(&mut out).is_ok();
+    if out.len() > limit {
+        out.truncate(limit);
+        (out, false)
+    } else {
+        (out, complete)
+    }
+}
+
+fn is_text(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime.as_str(),
+            "application/json"
+                | "application/javascript"
+                | "application/ecmascript"
+                | "application/xml"
+                | "application/x-www-form-urlencoded"
+                | "application/graphql"
+        )
+}
+
+fn looks_like_text(data: &[u8]) -> bool {
+    data.to_str().is_ok_and(|x| {
+        x.chars()
+            .all(|c| !c.is_control() || c.is_ascii_whitespace())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    fn response(head: &str, body: &[u8]) -> Response {
+        let mut bytes = head.as_bytes().to_vec();
+        bytes.extend_from_slice(body);
+        match extract(&bytes).unwrap() {
+            Http::Response(resp) => resp,
+            Http::Request(_) => panic!("expected response"),
+        }
+    }
+
+    #[test]
+    fn chunked_gzip() {
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(b"<html>hello world</html>").unwrap();
+        let gz = gz.finish().unwrap();
+
+        let mut body = Vec::new();
+        for chunk in gz.chunks(10) {
+            body.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+            body.extend_from_slice(chunk);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(b"0\r\n\r\n");
+
+        let resp = response(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nTransfer-Encoding: chunked\r\nContent-Encoding: gzip\r\n\r\n",
+            &body,
+        );
+        assert_eq!(
+            resp.decoded(),
+            Some(&Body {
+                encodings: vec!["chunked".to_string(), "gzip".to_string()],
+                content_type: Some("text/html".to_string()),
+                text: true,
+                truncated: false,
+                data: "<html>hello world</html>".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn truncated_chunk() {
+        let resp = response(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n",
+            b"5\r\nhello\r\n10\r\nwor",
+        );
+        let body = resp.decoded().unwrap();
+        assert_eq!(body.data, "hellowor");
+        assert!(body.truncated);
+        assert!(body.text);
+    }
+
+    #[test]
+    fn decompression_bomb() {
+        let mut gz = GzEncoder::new(Vec::new(), Compression::best());
+        gz.write_all(&vec![0; MAX_BODY_LENGTH * 4]).unwrap();
+        let gz = gz.finish().unwrap();
+
+        let resp = response(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Encoding: gzip\r\n\r\n",
+            &gz,
+        );
+        let body = resp.decoded().unwrap();
+        assert_eq!(body.data.len(), MAX_BODY_LENGTH);
+        assert!(body.truncated);
+        assert!(!body.text);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::structs::CentrifugeError;
+use crate::structs::icmpv6::{ICMPv6, Prefix, RouterAdvertisement};
+use nom::bytes::complete::take;
+use nom::number::complete::{be_u8, be_u16, be_u32};
+use std::net::Ipv6Addr;
+
+const OPT_SOURCE_LINK_ADDR: u8 = 1;
+const OPT_PREFIX_INFO: u8 = 3;
+const OPT_MTU: u8 = 5;
+const OPT_RDNSS: u8 = 25;
+
+fn ipv6_addr(remaining: &[u8]) -> nom::IResult<&[u8], Ipv6Addr> {
+    let (remaining, addr) = take(16_usize)(remaining)?;
+    let mut buf = [0; 16];
+    buf.copy_from_slice(addr);
+    Ok((remaining, Ipv6Addr::from(buf)))
+}
+
+fn prefix_info(remaining: &[u8]) -> nom::IResult<&[u8], Prefix> {
+    let (remaining, length) = be_u8(remaining)?;
+    // flags, valid lifetime, preferred lifetime and reserved
+    let (remaining, _) = take(13_usize)(remaining)?;
+    let (remaining, prefix) = ipv6_addr(remaining)?;
+    Ok((remaining, Prefix { prefix, length }))
+}
+
+fn mtu(remaining: &[u8]) -> nom::IResult<&[u8], u32> {
+    let (remaining, _reserved) = be_u16(remaining)?;
+    be_u32(remaining)
+}
+
+fn rdnss(remaining: &[u8]) -> nom::IResult<&[u8], Vec<Ipv6Addr>> {
+    // reserved and lifetime
+    let (mut remaining, _) = take(6_usize)(remaining)?;
+    let mut servers = Vec::new();
+    while let Ok((rest, addr)) = ipv6_addr(remaining) {
+        servers.push(addr);
+        remaining = rest;
+    }
+    Ok((remaining, servers))
+}
+
+/// Malformed options are skipped instead of dropping the whole advertisement
+fn apply_option(ra: &mut RouterAdvertisement, opt_type: u8, data: &[u8]) {
+    match opt_type {
+        OPT_SOURCE_LINK_ADDR if data.len() == 6 => {
+            let mut buf = [0; 6];
+            buf.copy_from_slice(data);
+            ra.source_mac = Some(buf);
+        }
+        OPT_PREFIX_INFO => {
+            if let Ok((_, prefix)) = prefix_info(data) {
+                ra.prefixes.push(prefix);
+            }
+        }
+        OPT_MTU => {
+            if let Ok((_, mtu)) = mtu(data) {
+                ra.mtu = Some(mtu);
+            }
+        }
+        OPT_RDNSS => {
+            if let Ok((_, servers)) = rdnss(data) {
+                ra.dns.extend(servers);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn router_advertisement(remaining: &[u8]) -> nom::IResult<&[u8], RouterAdvertisement> {
+    let (remaining, hop_limit) = be_u8(remaining)?;
+    let (remaining, flags) = be_u8(remaining)?;
+    let (remaining, router_lifetime) = be_u16(remaining)?;
+    let (remaining, _reachable_time) = be_u32(remaining)?;
+    let (mut remaining, _retrans_timer) = be_u32(remaining)?;
+
+    let mut ra = RouterAdvertisement {
+        hop_limit,
+        managed: flags & 0x80 != 0,
+        other: flags & 0x40 != 0,
+        router_lifetime,
+        source_mac: None,
+        prefixes: Vec::new(),
+        mtu: None,
+        dns: Vec::new(),
+    };
+
+    while !remaining.is_empty() {
+        let (rest, opt_type) = be_u8(remaining)?;
+        let (rest, length) = be_u8(rest)?;
+        if length == 0 {
+            break;
+        }
+        // the length is in units of 8 bytes and includes type and length
+        let (rest, data) = take(length as usize * 8 - 2)(rest)?;
+        apply_option(&mut ra, opt_type, data);
+        remaining = rest;
+    }
+
+    Ok((remaining, ra))
+}
+
+fn icmpv6(remaining: &[u8]) -> nom::IResult<&[u8], ICMPv6> {
+    let (remaining, icmp_type) = be_u8(remaining)?;
+    let (remaining, code) = be_u8(remaining)?;
+    let (remaining, _checksum) = be_u16(remaining)?;
+
+    match icmp_type {
+        128 => Ok((remaining, ICMPv6::EchoRequest)),
+        129 => Ok((remaining, ICMPv6::EchoReply)),
+        133 => Ok((remaining, ICMPv6::RouterSolicitation)),
+        134 => {
+            let (remaining, ra) = router_advertisement(remaining)?;
+            Ok((remaining, ICMPv6::RouterAdvertisement(ra)))
+        }
+        135 | 136 => {
+            let (remaining, _flags) = be_u32(remaining)?;
+            let (remaining, target) = ipv6_addr(remaining)?;
+            if icmp_type == 135 {
+                Ok((remaining, ICMPv6::NeighborSolicitation(target)))
+            } else {
+                Ok((remaining, ICMPv6::NeighborAdvertisement(target)))
+            }
+        }
+        _ => Ok((
+            &[],
+            ICMPv6::Other {
+                icmp_type,
+                code,
+                data: remaining.to_vec(),
+            },
+        )),
+    }
+}
+
+pub fn parse(remaining: &[u8]) -> Result<ICMPv6, CentrifugeError> {
+    if let Ok((_remaining, icmp)) = icmpv6(remaining) {
+        Ok(icmp)
+    } else {
+        Err(CentrifugeError::InvalidPacket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_router_advertisement() {
+        let pkt = &[
+            0x86, 0x00, 0x00, 0x00, 0x40, 0x40, 0x07, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x01, 0x01, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x03, 0x04, 0x40, 0xc0,
+            0x00, 0x01, 0x51, 0x80, 0x00, 0x00, 0x38, 0x40, 0x00, 0x00, 0x00, 0x00, 0x20, 0x01,
+            0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x19, 0x03, 0x00, 0x00, 0x00, 0x00, 0x02, 0x58, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x53,
+        ];
+        assert_eq!(
+            parse(pkt),
+            Ok(ICMPv6::RouterAdvertisement(RouterAdvertisement {
+                hop_limit: 64,
+                managed: false,
+                other: true,
+                router_lifetime: 1800,
+                source_mac: Some([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]),
+                prefixes: vec![Prefix {
+                    prefix: "2001:db8::".parse().unwrap(),
+                    length: 64,
+                }],
+                mtu: None,
+                dns: vec!["2001:db8::53".parse().unwrap()],
+            }))
+        );
+    }
+}
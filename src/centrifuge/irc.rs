@@ -0,0 +1,189 @@
+use crate::structs::CentrifugeError;
+use crate::structs::irc::Message;
+use bstr::ByteSlice;
+use pktparse::tcp::TcpHeader;
+
+/// rfc1459 limits messages to 512 bytes, but ircv3 tags can add up to 8191 bytes
+const MAX_LINE_LENGTH: usize = 8704;
+/// rfc1459 allows up to 15 parameters
+const MAX_PARAMS: usize = 15;
+
+pub fn is_port(port: u16) -> bool {
+    matches!(port, 194 | 6665..=6669)
+}
+
+/// Messages on the well known ports, or segments that only contain messages a client would send
+///
+/// Bots often connect to their command and control server on other ports, so messages are
+/// also detected there if they can't be confused with other protocols.
+pub fn extract(tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<Vec<Message>, CentrifugeError> {
+    let text = remaining
+        .to_str()
+        .map_err(|_| CentrifugeError::WrongProtocol)?;
+    let known_port = is_port(tcp_hdr.source_port) || is_port(tcp_hdr.dest_port);
+
+    let mut lines = text.split('\n').collect::<Vec<_>>();
+    // the rest of the message follows in the next segment
+    let partial = lines.pop().unwrap_or_default();
+    if !known_port && !partial.is_empty() {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+
+    let mut messages = Vec::new();
+    for (i, line) in lines.into_iter().enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.is_empty() {
+            continue;
+        }
+        match message(line) {
+            Some(message) if known_port || is_plausible(&message) => messages.push(message),
+            // the segment may start in the middle of a message
+            None if i == 0 && known_port => (),
+            _ => return Err(CentrifugeError::WrongProtocol),
+        }
+    }
+
+    if messages.is_empty() {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+    Ok(messages)
+}
+
+/// A single line, without the line break
+pub fn message(line: &str) -> Option<Message> {
+    if line.len() > MAX_LINE_LENGTH {
+        return None;
+    }
+
+    // ircv3 message tags
+    let line = match line.strip_prefix('@') {
+        Some(rest) => rest.split_once(' ')?.1,
+        None => line,
+    };
+    let (prefix, line) = match line.strip_prefix(':') {
+        Some(rest) => {
+            let (prefix, rest) = rest.split_once(' ')?;
+            if prefix.is_empty() {
+                return None;
+            }
+            (Some(prefix.to_string()), rest)
+        }
+        None => (None, line),
+    };
+
+    let (command, mut rest) = line.split_once(' ').unwrap_or((line, ""));
+    let numeric = command.len() == 3 && command.chars().all(|c| c.is_ascii_digit());
+    let named = command.len() >= 2 && command.chars().all(|c| c.is_ascii_uppercase());
+    if !numeric && !named {
+        return None;
+    }
+
+    let mut params = Vec::new();
+    let mut trailing = None;
+    while !rest.is_empty() {
+        if let Some(text) = rest.strip_prefix(':') {
+            trailing = Some(text.to_string());
+            break;
+        }
+        let (param, next) = rest.split_once(' ').unwrap_or((rest, ""));
+        if !param.is_empty() {
+            params.push(param.to_string());
+        }
+        rest = next;
+    }
+    if params.len() > MAX_PARAMS {
+        return None;
+    }
+
+    Some(Message {
+        prefix,
+        command: command.to_string(),
+        params,
+        trailing,
+    })
+}
+
+/// Messages that are unlikely to be anything but irc
+fn is_plausible(message: &Message) -> bool {
+    let is_channel = |x: &str| x.starts_with(['#', '&', '+', '!']) && x.len() > 1;
+    let params = message.params.len();
+    let trailing = message.trailing.is_some();
+
+    if message.is_numeric() {
+        return message.prefix.is_some() && params > 0;
+    }
+    match message.command.as_str() {
+        "NICK" | "PASS" => params + usize::from(trailing) == 1,
+        "USER" => params >= 3 && (trailing || params >= 4),
+        "JOIN" | "PART" => message.target().is_some_and(is_channel),
+        "PRIVMSG" | "NOTICE" | "TOPIC" => params == 1 && trailing,
+        "PING" | "PONG" => params > 0 || trailing,
+        "QUIT" => trailing || message.prefix.is_some(),
+        "MODE" => params >= 2,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(source_port: u16, dest_port: u16) -> TcpHeader {
+        TcpHeader {
+            source_port,
+            dest_port,
+            sequence_no: 0,
+            ack_no: 0,
+            data_offset: 5,
+            reserved: 0,
+            flag_urg: false,
+            flag_ack: true,
+            flag_psh: true,
+            flag_rst: false,
+            flag_syn: false,
+            flag_fin: false,
+            window: 0,
+            checksum: 0,
+            urgent_pointer: 0,
+            options: None,
+        }
+    }
+
+    #[test]
+    fn privmsg() {
+        let message = message(
+            ":yaloki!n=yaloki@156.185-64-87.adsl-dyn.isp.belgacom.be PRIVMSG #amarok :how do you mean",
+        )
+        .unwrap();
+        assert_eq!(message.nick(), Some("yaloki"));
+        assert_eq!(message.command, "PRIVMSG");
+        assert_eq!(message.params, ["#amarok"]);
+        assert_eq!(message.text(), Some("how do you mean"));
+    }
+
+    #[test]
+    fn partial_lines() {
+        let messages = extract(
+            &header(6667, 2848),
+            b"aul Cifarelli\r\n:sterling.freenode.net 352 vmlemon #rokymotion n=shardz host irc.freenode.net shardz H :0 Samuel\r\n:sterling.freenode",
+        )
+        .unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].command, "352");
+        assert_eq!(messages[0].trailing.as_deref(), Some("0 Samuel"));
+    }
+
+    #[test]
+    fn unusual_port() {
+        let messages = extract(
+            &header(49152, 4444),
+            b"NICK bot123\r\nUSER bot123 0 * :bot\r\nJOIN #c2 key\r\n",
+        )
+        .unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[2].params, ["#c2", "key"]);
+
+        assert!(extract(&header(49152, 6379), b"PING\r\n").is_err());
+        assert!(extract(&header(49152, 8080), b"NICK bot123\r\nHELLO\r\n").is_err());
+    }
+}
@@ -0,0 +1,272 @@
+use data_encoding::HEXLOWER;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::fmt::Display;
+use tls_parser::{TlsExtension, TlsExtensionType, TlsVersion};
+
+const EXT_SNI: u16 = 0x0000;
+const EXT_ALPN: u16 = 0x0010;
+
+/// Clients send random GREASE values (rfc8701), they are ignored by all fingerprints
+#[inline]
+pub fn is_grease(value: u16) -> bool {
+    value & 0x0f0f == 0x0a0a && value >> 8 == value & 0xff
+}
+
+fn extension_types(extensions: &[TlsExtension]) -> Vec<u16> {
+    extensions
+        .iter()
+        .filter(|ext| !matches!(ext, TlsExtension::Grease(..)))
+        .map(|ext| TlsExtensionType::from(ext).0)
+        .filter(|x| !is_grease(*x))
+        .collect()
+}
+
+fn join<T: Display>(values: &[T], separator: &str) -> String {
+    values
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn hex_list(values: &[u16]) -> String {
+    values
+        .iter()
+        .map(|x| format!("{:04x}", x))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The first 12 characters of the sha256, as used by ja4
+fn truncated_hash(text: &str) -> String {
+    if text.is_empty() {
+        return "000000000000".to_string();
+    }
+    let mut hash = HEXLOWER.encode(&Sha256::digest(text.as_bytes()));
+    hash.truncate(12);
+    hash
+}
+
+fn md5(text: &str) -> String {
+    HEXLOWER.encode(&Md5::digest(text.as_bytes()))
+}
+
+fn version_code(version: u16) -> &'static str {
+    match version {
+        0x0304 => "13",
+        0x0303 => "12",
+        0x0302 => "11",
+        0x0301 => "10",
+        0x0300 => "s3",
+        0x0002 => "s2",
+        0xfeff => "d1",
+        0xfefd => "d2",
+        0xfefc => "d3",
+        _ => "00",
+    }
+}
+
+/// First and last character of the first alpn value
+fn alpn_code(alpn: Option<&[u8]>) -> String {
+    match alpn {
+        Some([first, .., last]) | Some([first @ last]) => {
+            if first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric() {
+                format!("{}{}", *first as char, *last as char)
+            } else {
+                let first = HEXLOWER.encode(&[*first]);
+                let last = HEXLOWER.encode(&[*last]);
+                format!("{}{}", &first[..1], &last[1..])
+            }
+        }
+        _ => "00".to_string(),
+    }
+}
+
+fn alpn<'a>(extensions: &[TlsExtension<'a>]) -> Option<&'a [u8]> {
+    extensions.iter().find_map(|ext| match ext {
+        TlsExtension::ALPN(protocols) => protocols.first().copied(),
+        _ => None,
+    })
+}
+
+/// The highest version offered in the supported_versions extension
+fn supported_version(extensions: &[TlsExtension]) -> Option<u16> {
+    extensions.iter().find_map(|ext| match ext {
+        TlsExtension::SupportedVersions(versions) => versions
+            .iter()
+            .map(|v| v.0)
+            .filter(|v| !is_grease(*v))
+            .max(),
+        _ => None,
+    })
+}
+
+pub fn ja3(version: TlsVersion, ciphers: &[u16], extensions: &[TlsExtension]) -> String {
+    let ciphers = ciphers
+        .iter()
+        .copied()
+        .filter(|x| !is_grease(*x))
+        .collect::<Vec<_>>();
+
+    let mut groups = Vec::new();
+    let mut point_formats: &[u8] = &[];
+    for ext in extensions {
+        match ext {
+            TlsExtension::EllipticCurves(list) => {
+                groups = list
+                    .iter()
+                    .map(|x| x.0)
+                    .filter(|x| !is_grease(*x))
+                    .collect();
+            }
+            TlsExtension::EcPointFormats(list) => point_formats = list,
+            _ => (),
+        }
+    }
+
+    md5(&format!(
+        "{},{},{},{},{}",
+        version.0,
+        join(&ciphers, "-"),
+        join(&extension_types(extensions), "-"),
+        join(&groups, "-"),
+        join(point_formats, "-"),
+    ))
+}
+
+pub fn ja3s(version: TlsVersion, cipher: u16, extensions: &[TlsExtension]) -> String {
+    md5(&format!(
+        "{},{},{}",
+        version.0,
+        cipher,
+        join(&extension_types(extensions), "-"),
+    ))
+}
+
+pub fn ja4(
+    quic: bool,
+    version: TlsVersion,
+    ciphers: &[u16],
+    extensions: &[TlsExtension],
+) -> String {
+    let version = supported_version(extensions).unwrap_or(version.0);
+    let sni = extensions
+        .iter()
+        .any(|ext| matches!(ext, TlsExtension::SNI(_)));
+    let signature_algorithms = extensions
+        .iter()
+        .find_map(|ext| match ext {
+            TlsExtension::SignatureAlgorithms(list) => {
+                Some(list.iter().copied().filter(|x| !is_grease(*x)).collect())
+            }
+            _ => None,
+        })
+        .unwrap_or_else(Vec::new);
+
+    let mut ciphers = ciphers
+        .iter()
+        .copied()
+        .filter(|x| !is_grease(*x))
+        .collect::<Vec<_>>();
+    let types = extension_types(extensions);
+    let header = format!(
+        "{}{}{}{:02}{:02}{}",
+        if quic { 'q' } else { 't' },
+        version_code(version),
+        if sni { 'd' } else { 'i' },
+        ciphers.len().min(99),
+        types.len().min(99),
+        alpn_code(alpn(extensions)),
+    );
+
+    ciphers.sort_unstable();
+    let mut sorted = types
+        .into_iter()
+        .filter(|x| *x != EXT_SNI && *x != EXT_ALPN)
+        .collect::<Vec<_>>();
+    sorted.sort_unstable();
+    let mut exts = hex_list(&sorted);
+    if !signature_algorithms.is_empty() {
+        exts.push('_');
+        exts.push_str(&hex_list(&signature_algorithms));
+    }
+
+    format!(
+        "{}_{}_{}",
+        header,
+        truncated_hash(&hex_list(&ciphers)),
+        truncated_hash(&exts)
+    )
+}
+
+pub fn ja4s(quic: bool, version: TlsVersion, cipher: u16, extensions: &[TlsExtension]) -> String {
+    let version = supported_version(extensions).unwrap_or(version.0);
+    let types = extension_types(extensions);
+    format!(
+        "{}{}{:02}{}_{:04x}_{}",
+        if quic { 'q' } else { 't' },
+        version_code(version),
+        types.len().min(99),
+        alpn_code(alpn(extensions)),
+        cipher,
+        truncated_hash(&hex_list(&types)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tls_parser::NamedGroup;
+
+    #[test]
+    fn client_fingerprints() {
+        let ciphers = [0x0a0a, 0x1301, 0x1302, 0xc02b];
+        let extensions = [
+            TlsExtension::Grease(0x1a1a, &[]),
+            TlsExtension::SNI(vec![]),
+            TlsExtension::EllipticCurves(vec![NamedGroup(0x2a2a), NamedGroup(29)]),
+            TlsExtension::EcPointFormats(&[0]),
+            TlsExtension::SignatureAlgorithms(vec![0x0403, 0x0804]),
+            TlsExtension::ALPN(vec![b"h2", b"http/1.1"]),
+            TlsExtension::SupportedVersions(vec![TlsVersion(0x3a3a), TlsVersion(0x0304)]),
+        ];
+
+        assert_eq!(
+            ja3(TlsVersion(0x0303), &ciphers, &extensions),
+            md5("771,4865-4866-49195,0-10-11-13-16-43,29,0")
+        );
+        assert_eq!(
+            ja4(false, TlsVersion(0x0303), &ciphers, &extensions),
+            format!(
+                "t13d0306h2_{}_{}",
+                truncated_hash("1301,1302,c02b"),
+                truncated_hash("000a,000b,000d,002b_0403,0804")
+            )
+        );
+    }
+
+    #[test]
+    fn server_fingerprints() {
+        let extensions = [
+            TlsExtension::SupportedVersions(vec![TlsVersion(0x0304)]),
+            TlsExtension::KeyShare(&[]),
+        ];
+        assert_eq!(
+            ja3s(TlsVersion(0x0303), 0x1301, &extensions),
+            md5("771,4865,43-51")
+        );
+        assert_eq!(
+            ja4s(false, TlsVersion(0x0303), 0x1301, &extensions),
+            format!("t130200_1301_{}", truncated_hash("002b,0033"))
+        );
+    }
+
+    #[test]
+    fn alpn_codes() {
+        assert_eq!(alpn_code(None), "00");
+        assert_eq!(alpn_code(Some(b"h2")), "h2");
+        assert_eq!(alpn_code(Some(b"x")), "xx");
+        assert_eq!(alpn_code(Some(&[0xab, 0x01])), "a1");
+    }
+}
@@ -0,0 +1,58 @@
+use crate::structs::CentrifugeError;
+use crate::structs::lacp::{Info, LACP};
+use nom::bytes::complete::{tag, take};
+use nom::number::complete::{be_u8, be_u16};
+
+const SUBTYPE_LACP: u8 = 0x01;
+
+fn info(remaining: &[u8], tlv_type: u8) -> nom::IResult<&[u8], Info> {
+    let (remaining, _type) = tag(&[tlv_type][..])(remaining)?;
+    let (remaining, _length) = tag(&[20][..])(remaining)?;
+    let (remaining, system_priority) = be_u16(remaining)?;
+    let (remaining, system) = take(6_usize)(remaining)?;
+    let (remaining, key) = be_u16(remaining)?;
+    let (remaining, port_priority) = be_u16(remaining)?;
+    let (remaining, port) = be_u16(remaining)?;
+    let (remaining, state) = be_u8(remaining)?;
+    let (remaining, _reserved) = take(3_usize)(remaining)?;
+
+    let mut buf = [0; 6];
+    buf.copy_from_slice(system);
+
+    Ok((
+        remaining,
+        Info {
+            system_priority,
+            system: buf,
+            key,
+            port_priority,
+            port,
+            state,
+        },
+    ))
+}
+
+fn lacpdu(remaining: &[u8]) -> nom::IResult<&[u8], LACP> {
+    let (remaining, _subtype) = tag(&[SUBTYPE_LACP][..])(remaining)?;
+    let (remaining, version) = be_u8(remaining)?;
+    let (remaining, actor) = info(remaining, 0x01)?;
+    let (remaining, partner) = info(remaining, 0x02)?;
+
+    Ok((
+        remaining,
+        LACP {
+            version,
+            actor,
+            partner,
+        },
+    ))
+}
+
+/// Parse a slow protocols frame (ethertype 0x8809), only LACP is supported
+pub fn parse(remaining: &[u8]) -> Result<LACP, CentrifugeError> {
+    if let Ok((_remaining, lacp)) = lacpdu(remaining) {
+        Ok(lacp)
+    } else {
+        Err(CentrifugeError::InvalidPacket)
+    }
+}
@@ -0,0 +1,130 @@
+use crate::centrifuge::stp;
+use crate::structs::CentrifugeError;
+use crate::structs::llc::{Header, LLC, Snap};
+use nom::bytes::complete::take;
+use nom::number::complete::{be_u8, be_u16};
+
+const SAP_STP: u8 = 0x42;
+const SAP_SNAP: u8 = 0xaa;
+
+const OUI_CISCO: [u8; 3] = [0x00, 0x00, 0x0c];
+const CISCO_PVST: u16 = 0x010b;
+
+fn llc_header(remaining: &[u8]) -> nom::IResult<&[u8], Header> {
+    let (remaining, dsap) = be_u8(remaining)?;
+    let (remaining, ssap) = be_u8(remaining)?;
+    let (remaining, control) = be_u8(remaining)?;
+    Ok((
+        remaining,
+        Header {
+            dsap,
+            ssap,
+            control,
+        },
+    ))
+}
+
+fn snap_header(remaining: &[u8]) -> nom::IResult<&[u8], ([u8; 3], u16)> {
+    let (remaining, oui) = take(3_usize)(remaining)?;
+    let (remaining, protocol) = be_u16(remaining)?;
+    let mut buf = [0; 3];
+    buf.copy_from_slice(oui);
+    Ok((remaining, (buf, protocol)))
+}
+
+/// Parse an 802.3 frame, `length` is the value of the ethertype field
+pub fn parse(length: u16, remaining: &[u8]) -> Result<LLC, CentrifugeError> {
+    // strip ethernet padding
+    let remaining = &remaining[..remaining.len().min(length as usize)];
+
+    let Ok((remaining, header)) = llc_header(remaining) else {
+        return Err(CentrifugeError::InvalidPacket);
+    };
+
+    match header {
+        Header {
+            dsap: SAP_STP,
+            ssap: SAP_STP,
+            ..
+        } => Ok(LLC::STP(stp::parse(remaining)?)),
+        Header {
+            dsap: SAP_SNAP,
+            ssap: SAP_SNAP,
+            ..
+        } => {
+            let Ok((remaining, (oui, protocol))) = snap_header(remaining) else {
+                return Err(CentrifugeError::InvalidPacket);
+            };
+
+            if oui == OUI_CISCO && protocol == CISCO_PVST {
+                Ok(LLC::STP(stp::parse(remaining)?))
+            } else {
+                Ok(LLC::Snap(Snap {
+                    oui,
+                    protocol,
+                    data: remaining.to_vec(),
+                }))
+            }
+        }
+        _ => Ok(LLC::Unknown(header, remaining.to_vec())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::stp::{BPDU, BridgeId, ConfigBpdu};
+
+    #[test]
+    fn parse_rstp_bpdu() {
+        let pkt = &[
+            0x42, 0x42, 0x03, 0x00, 0x00, 0x02, 0x02, 0x3d, 0x80, 0x00, 0x00, 0x1c, 0x0e, 0x87,
+            0x78, 0x00, 0x00, 0x00, 0x00, 0x04, 0x80, 0x00, 0x00, 0x1c, 0x0e, 0x87, 0x85, 0x00,
+            0x80, 0x04, 0x01, 0x00, 0x14, 0x00, 0x02, 0x00, 0x0f, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+        let llc = parse(39, pkt).unwrap();
+        let LLC::STP(BPDU::RST(bpdu)) = llc else {
+            panic!("expected rstp bpdu: {:?}", llc);
+        };
+        assert_eq!(
+            bpdu,
+            ConfigBpdu {
+                version: 2,
+                flags: 0x3d,
+                root: BridgeId {
+                    priority: 32768,
+                    mac: [0x00, 0x1c, 0x0e, 0x87, 0x78, 0x00],
+                },
+                root_path_cost: 4,
+                bridge: BridgeId {
+                    priority: 32768,
+                    mac: [0x00, 0x1c, 0x0e, 0x87, 0x85, 0x00],
+                },
+                port_id: 0x8004,
+                message_age: 256,
+                max_age: 5120,
+                hello_time: 512,
+                forward_delay: 3840,
+            }
+        );
+        assert!(bpdu.topology_change());
+        assert_eq!(bpdu.port_role(), Some("designated"));
+    }
+
+    #[test]
+    fn parse_unknown_sap() {
+        let pkt = &[0xe0, 0xe0, 0x03, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(
+            parse(9, pkt),
+            Ok(LLC::Unknown(
+                Header {
+                    dsap: 0xe0,
+                    ssap: 0xe0,
+                    control: 0x03,
+                },
+                vec![0xff, 0xff, 0x00, 0x00, 0x00, 0x00],
+            ))
+        );
+    }
+}
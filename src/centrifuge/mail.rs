@@ -0,0 +1,417 @@
+use crate::structs::CentrifugeError;
+use crate::structs::mail::{Command, Mail, Message, Protocol, Reply};
+use bstr::ByteSlice;
+use pktparse::tcp::TcpHeader;
+
+/// Control lines are short, anything longer is likely message content
+const MAX_LINE_LENGTH: usize = 4096;
+
+const SMTP_COMMANDS: &[&str] = &[
+    "HELO", "EHLO", "MAIL", "RCPT", "DATA", "BDAT", "RSET", "VRFY", "EXPN", "HELP", "NOOP", "QUIT",
+    "STARTTLS", "AUTH", "ETRN",
+];
+const POP3_COMMANDS: &[&str] = &[
+    "USER", "PASS", "APOP", "AUTH", "STAT", "LIST", "RETR", "DELE", "NOOP", "RSET", "QUIT", "TOP",
+    "UIDL", "CAPA", "STLS",
+];
+const IMAP_COMMANDS: &[&str] = &[
+    "CAPABILITY",
+    "NOOP",
+    "LOGOUT",
+    "STARTTLS",
+    "AUTHENTICATE",
+    "LOGIN",
+    "SELECT",
+    "EXAMINE",
+    "CREATE",
+    "DELETE",
+    "RENAME",
+    "SUBSCRIBE",
+    "UNSUBSCRIBE",
+    "LIST",
+    "LSUB",
+    "STATUS",
+    "APPEND",
+    "CHECK",
+    "CLOSE",
+    "EXPUNGE",
+    "SEARCH",
+    "FETCH",
+    "STORE",
+    "COPY",
+    "MOVE",
+    "UID",
+    "IDLE",
+    "ENABLE",
+    "NAMESPACE",
+    "ID",
+];
+
+/// Headers that only show up in messages, one of them is required to detect a message
+const MESSAGE_HEADERS: &[&str] = &[
+    "from",
+    "to",
+    "cc",
+    "subject",
+    "date",
+    "message-id",
+    "received",
+    "return-path",
+    "mime-version",
+];
+
+pub fn protocol(port: u16) -> Option<Protocol> {
+    match port {
+        25 | 587 => Some(Protocol::SMTP),
+        110 => Some(Protocol::POP3),
+        143 => Some(Protocol::IMAP),
+        _ => None,
+    }
+}
+
+/// Runs after tls, so the client hello that follows a starttls upgrade is still detected
+pub fn extract(tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<Mail, CentrifugeError> {
+    let text = remaining
+        .to_str()
+        .map_err(|_| CentrifugeError::WrongProtocol)?;
+    if !text.ends_with('\n') {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+
+    if let Some(protocol) = protocol(tcp_hdr.dest_port) {
+        if protocol == Protocol::SMTP
+            && let Some(message) = message(text)
+        {
+            return Ok(Mail::Message(message));
+        }
+        let commands = text
+            .lines()
+            .map(|line| command(protocol, line))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Mail::Commands(protocol, commands))
+    } else if let Some(protocol) = protocol(tcp_hdr.source_port) {
+        let replies = match protocol {
+            Protocol::SMTP => smtp_replies(text)?,
+            Protocol::POP3 => vec![pop3_reply(text)?],
+            Protocol::IMAP => imap_replies(text)?,
+        };
+        Ok(Mail::Replies(protocol, replies))
+    } else {
+        Err(CentrifugeError::WrongProtocol)
+    }
+}
+
+fn command(protocol: Protocol, line: &str) -> Result<Command, CentrifugeError> {
+    if line.len() > MAX_LINE_LENGTH || line.is_empty() {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+
+    let (tag, line) = match protocol {
+        // the end of an idle command is the only one without a tag
+        Protocol::IMAP if line.eq_ignore_ascii_case("DONE") => (None, line),
+        Protocol::IMAP => {
+            let (tag, line) = line.split_once(' ').ok_or(CentrifugeError::WrongProtocol)?;
+            if !is_imap_atom(tag) {
+                return Err(CentrifugeError::WrongProtocol);
+            }
+            (Some(tag.to_string()), line)
+        }
+        _ => (None, line),
+    };
+
+    let (command, argument) = match line.split_once(' ') {
+        Some((command, argument)) => (command, Some(argument.to_string())),
+        None => (line, None),
+    };
+    let command = command.to_ascii_uppercase();
+    let known = match protocol {
+        Protocol::SMTP => SMTP_COMMANDS,
+        Protocol::POP3 => POP3_COMMANDS,
+        Protocol::IMAP if tag.is_none() => &["DONE"][..],
+        Protocol::IMAP => IMAP_COMMANDS,
+    };
+    if !known.contains(&command.as_str()) {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+
+    let argument_ref = argument.as_deref().unwrap_or_default();
+    let address = match command.as_str() {
+        "MAIL" => envelope_address(argument_ref, "FROM:"),
+        "RCPT" => envelope_address(argument_ref, "TO:"),
+        _ => None,
+    };
+    let username = match (protocol, command.as_str()) {
+        (Protocol::POP3, "USER") => argument.clone(),
+        (Protocol::POP3, "APOP") => argument_ref.split(' ').next().map(String::from),
+        (Protocol::IMAP, "LOGIN") => imap_strings(argument_ref).into_iter().next(),
+        _ => None,
+    };
+
+    Ok(Command {
+        tag,
+        command,
+        argument,
+        address,
+        username,
+    })
+}
+
+/// `FROM:<alice@example.com> SIZE=1234`, the brackets are empty for bounces
+fn envelope_address(argument: &str, prefix: &str) -> Option<String> {
+    let rest = argument.get(..prefix.len())?;
+    if !rest.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    let rest = argument[prefix.len()..].trim_start();
+    let address = match rest.strip_prefix('<') {
+        Some(rest) => &rest[..rest.find('>')?],
+        None => rest.split(' ').next()?,
+    };
+    Some(address.to_string())
+}
+
+fn is_imap_atom(word: &str) -> bool {
+    !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_graphic() && !"(){%*\"\\]+".contains(c))
+}
+
+/// Split the arguments of an imap command, strings may be quoted
+pub fn imap_strings(data: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut chars = data.chars().peekable();
+    loop {
+        while chars.next_if_eq(&' ').is_some() {}
+        let Some(first) = chars.next() else {
+            break;
+        };
+
+        let mut value = String::new();
+        if first == '"' {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            value.push(first);
+            while let Some(c) = chars.next_if(|c| *c != ' ') {
+                value.push(c);
+            }
+        }
+        out.push(value);
+    }
+    out
+}
+
+/// Multiline replies repeat the code on every line, pipelined replies follow each other
+fn smtp_replies(text: &str) -> Result<Vec<Reply>, CentrifugeError> {
+    let mut replies = Vec::<Reply>::new();
+    let mut last_line = true;
+    for line in text.lines() {
+        let code = line.get(..3).ok_or(CentrifugeError::WrongProtocol)?;
+        let separator = line.get(3..4).unwrap_or(" ");
+        if !code.chars().all(|c| c.is_ascii_digit())
+            || !(b'2'..=b'5').contains(&code.as_bytes()[0])
+            || !matches!(separator, " " | "-")
+            || line.len() > MAX_LINE_LENGTH
+        {
+            return Err(CentrifugeError::WrongProtocol);
+        }
+
+        let text = line.get(4..).unwrap_or_default().to_string();
+        match replies.last_mut() {
+            Some(reply) if !last_line => {
+                if reply.status != code {
+                    return Err(CentrifugeError::WrongProtocol);
+                }
+                reply.lines.push(text);
+            }
+            _ => replies.push(Reply {
+                tag: None,
+                status: code.to_string(),
+                lines: vec![text],
+            }),
+        }
+        last_line = separator == " ";
+    }
+
+    if replies.is_empty() {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+    Ok(replies)
+}
+
+/// Multiline replies like `LIST` or `RETR` continue after the status line
+fn pop3_reply(text: &str) -> Result<Reply, CentrifugeError> {
+    let mut lines = text.lines();
+    let first = lines.next().ok_or(CentrifugeError::WrongProtocol)?;
+    let (status, rest) = first.split_once(' ').unwrap_or((first, ""));
+    if !matches!(status, "+OK" | "-ERR" | "+") {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+
+    Ok(Reply {
+        tag: None,
+        status: status.to_string(),
+        lines: std::iter::once(rest)
+            .filter(|x| !x.is_empty())
+            .chain(lines)
+            .map(String::from)
+            .collect(),
+    })
+}
+
+/// Lines that don't start a response belong to the previous one, like fetched messages
+fn imap_replies(text: &str) -> Result<Vec<Reply>, CentrifugeError> {
+    let mut replies = Vec::<Reply>::new();
+    for line in text.lines() {
+        if let Some(reply) = imap_response(line) {
+            replies.push(reply);
+        } else if let Some(reply) = replies.last_mut() {
+            reply.lines.push(line.to_string());
+        } else {
+            return Err(CentrifugeError::WrongProtocol);
+        }
+    }
+
+    if replies.is_empty() {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+    Ok(replies)
+}
+
+fn imap_response(line: &str) -> Option<Reply> {
+    let (tag, rest) = line.split_once(' ').unwrap_or((line, ""));
+    if tag == "+" {
+        return Some(Reply {
+            tag: None,
+            status: "+".to_string(),
+            lines: vec![rest.to_string()],
+        });
+    }
+
+    let (word, after) = rest.split_once(' ').unwrap_or((rest, ""));
+    // message data starts with the message number, eg. `* 23 EXISTS`
+    let (status, text) = if !word.is_empty() && word.chars().all(|c| c.is_ascii_digit()) {
+        let (status, after) = after.split_once(' ').unwrap_or((after, ""));
+        (status, format!("{} {}", word, after).trim_end().to_string())
+    } else {
+        (word, after.to_string())
+    };
+    let status = status.to_ascii_uppercase();
+
+    let valid = match tag {
+        "*" => !status.is_empty() && status.chars().all(|c| c.is_ascii_alphabetic()),
+        tag => is_imap_atom(tag) && matches!(status.as_str(), "OK" | "NO" | "BAD"),
+    };
+    if !valid {
+        return None;
+    }
+
+    Some(Reply {
+        tag: Some(tag.to_string()),
+        status,
+        lines: vec![text],
+    })
+}
+
+/// The headers at the start of a message, folded lines are joined
+fn message(text: &str) -> Option<Message> {
+    let mut headers = Vec::<(String, String)>::new();
+    for line in text.lines() {
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            let (_, value) = headers.last_mut()?;
+            value.push(' ');
+            value.push_str(line.trim());
+            continue;
+        }
+        let (name, value) = line.split_once(':')?;
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_graphic()) {
+            return None;
+        }
+        headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+    }
+
+    if !headers
+        .iter()
+        .any(|(name, _)| MESSAGE_HEADERS.contains(&name.as_str()))
+    {
+        return None;
+    }
+
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    };
+    Some(Message {
+        from: header("from"),
+        to: header("to"),
+        subject: header("subject"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smtp_envelope() {
+        let commands = "EHLO client.example\r\nMAIL FROM:<alice@example.com> SIZE=123\r\nRCPT TO:<bob@example.org>\r\nDATA\r\n"
+            .lines()
+            .map(|line| command(Protocol::SMTP, line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(commands[0].argument.as_deref(), Some("client.example"));
+        assert_eq!(commands[1].address.as_deref(), Some("alice@example.com"));
+        assert_eq!(commands[2].address.as_deref(), Some("bob@example.org"));
+        assert_eq!(commands[3].command, "DATA");
+        assert!(command(Protocol::SMTP, "hello world").is_err());
+
+        let replies =
+            smtp_replies("250-mx.example.org\r\n250 PIPELINING\r\n250 ok\r\n354 go ahead\r\n")
+                .unwrap();
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[0].lines, ["mx.example.org", "PIPELINING"]);
+        assert_eq!(replies[2].status, "354");
+    }
+
+    #[test]
+    fn message_headers() {
+        let message = message(
+            "From: Alice <alice@example.com>\r\nTo: bob@example.org\r\nSubject: quarterly\r\n numbers\r\n\r\nHi Bob\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            message,
+            Message {
+                from: Some("Alice <alice@example.com>".to_string()),
+                to: Some("bob@example.org".to_string()),
+                subject: Some("quarterly numbers".to_string()),
+            }
+        );
+        assert_eq!(super::message("Host: example.com\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn imap() {
+        let command = command(Protocol::IMAP, "a001 LOGIN \"carol\" \"pa ss\"").unwrap();
+        assert_eq!(command.tag.as_deref(), Some("a001"));
+        assert_eq!(command.username.as_deref(), Some("carol"));
+        assert_eq!(command.line(), "a001 LOGIN \"carol\" \"pa ss\"");
+
+        let replies = imap_replies("* 23 EXISTS\r\na001 OK LOGIN completed\r\n").unwrap();
+        assert_eq!(replies[0].status, "EXISTS");
+        assert_eq!(replies[0].lines, ["23"]);
+        assert_eq!(replies[1].tag.as_deref(), Some("a001"));
+        assert_eq!(replies[1].status, "OK");
+        assert_eq!(replies[1].lines, ["LOGIN completed"]);
+    }
+}
@@ -9,17 +9,36 @@ use crate::structs::raw::Raw;
 
 pub mod arp;
 pub mod cjdns;
+pub mod eapol;
 pub mod icmp;
+pub mod icmpv6;
+pub mod lacp;
+pub mod llc;
 pub mod sll;
+pub mod stp;
 pub mod tcp;
 pub mod udp;
 
+pub mod bgp;
 pub mod dhcp;
 pub mod dns;
 pub mod dropbox;
+pub mod ftp;
 pub mod http;
+pub mod irc;
+pub mod ja;
+pub mod mail;
+pub mod mongodb;
+pub mod mysql;
+pub mod nbns;
+pub mod postgres;
+pub mod quic;
+pub mod redis;
 pub mod ssdp;
+pub mod ssh;
+pub mod telnet;
 pub mod tls;
+pub mod x509;
 
 #[inline]
 pub fn parse(link: &DataLink, data: &[u8]) -> Raw {
@@ -54,7 +73,21 @@ pub fn parse_eth(data: &[u8]) -> Result<Raw, CentrifugeError> {
                 Ok(cjdns_pkt) => Ether::Cjdns(cjdns_pkt),
                 Err(_) => Ether::Unknown(remaining.to_vec()),
             },
-            _ => Ether::Unknown(remaining.to_vec()),
+            EtherType::Other(0x8809) => match lacp::parse(remaining) {
+                Ok(lacp_pkt) => Ether::Lacp(lacp_pkt),
+                Err(_) => Ether::Unknown(remaining.to_vec()),
+            },
+            EtherType::EAPOL => match eapol::parse(remaining) {
+                Ok(eapol_pkt) => Ether::Eapol(eapol_pkt),
+                Err(_) => Ether::Unknown(remaining.to_vec()),
+            },
+            ethertype => match frame_length(ethertype) {
+                Some(length) => match llc::parse(length, remaining) {
+                    Ok(llc_pkt) => Ether::Llc(llc_pkt),
+                    Err(_) => Ether::Unknown(remaining.to_vec()),
+                },
+                None => Ether::Unknown(remaining.to_vec()),
+            },
         };
         Ok(Raw::Ether(eth_frame, inner))
     } else {
@@ -62,6 +95,17 @@ pub fn parse_eth(data: &[u8]) -> Result<Raw, CentrifugeError> {
     }
 }
 
+/// Values below 0x0600 are the payload length of an 802.3 frame instead of an ethertype
+#[inline]
+fn frame_length(ethertype: EtherType) -> Option<u16> {
+    match ethertype {
+        EtherType::LANMIN => Some(0x002e),
+        EtherType::LANMAX => Some(0x05dc),
+        EtherType::Other(length) if length < 0x0600 => Some(length),
+        _ => None,
+    }
+}
+
 #[inline]
 pub fn parse_tun(data: &[u8]) -> Raw {
     Raw::Tun(if let Ok(ipv4) = parse_ipv4(data) {
@@ -86,7 +130,7 @@ pub fn parse_ipv4(data: &[u8]) -> Result<Ether, CentrifugeError> {
     if let Ok((remaining, ip_hdr)) = ipv4::parse_ipv4_header(data) {
         let inner = match ip_hdr.protocol {
             IPProtocol::TCP => match tcp::parse(remaining) {
-                Ok((tcp_hdr, tcp)) => TCP(tcp_hdr, tcp),
+                Ok((tcp_hdr, tcp, options)) => TCP(tcp_hdr, tcp, options),
                 Err(_) => Unknown(remaining.to_vec()),
             },
             IPProtocol::UDP => match udp::parse(remaining) {
@@ -112,13 +156,17 @@ pub fn parse_ipv6(data: &[u8]) -> Result<Ether, CentrifugeError> {
     if let Ok((remaining, ip_hdr)) = ipv6::parse_ipv6_header(data) {
         let inner = match ip_hdr.next_header {
             IPProtocol::TCP => match tcp::parse(remaining) {
-                Ok((tcp_hdr, tcp)) => TCP(tcp_hdr, tcp),
+                Ok((tcp_hdr, tcp, options)) => TCP(tcp_hdr, tcp, options),
                 Err(_) => Unknown(remaining.to_vec()),
             },
             IPProtocol::UDP => match udp::parse(remaining) {
                 Ok((udp_hdr, udp)) => UDP(udp_hdr, udp),
                 Err(_) => Unknown(remaining.to_vec()),
             },
+            IPProtocol::ICMP6 => match icmpv6::parse(remaining) {
+                Ok(icmp) => ICMPv6(icmp),
+                Err(_) => Unknown(remaining.to_vec()),
+            },
             _ => Unknown(remaining.to_vec()),
         };
         Ok(Ether::IPv6(ip_hdr, inner))
@@ -0,0 +1,301 @@
+use crate::structs::CentrifugeError;
+use crate::structs::mongodb::{Command, MongoDB, Op, Reply};
+use bstr::ByteSlice;
+use pktparse::tcp::TcpHeader;
+
+pub const PORT: u16 = 27017;
+
+const OP_REPLY: i32 = 1;
+const OP_QUERY: i32 = 2004;
+const OP_COMPRESSED: i32 = 2012;
+const OP_MSG: i32 = 2013;
+/// The default limit of mongod for a message
+const MAX_MESSAGE_LENGTH: i32 = 48_000_000;
+const HEADER_LENGTH: usize = 16;
+
+/// The first message of a segment, documents that continue in the next segment are read as far as possible
+pub fn extract(tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<MongoDB, CentrifugeError> {
+    let client = tcp_hdr.dest_port == PORT;
+    if !client && tcp_hdr.source_port != PORT {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+
+    let header = remaining
+        .get(..HEADER_LENGTH)
+        .ok_or(CentrifugeError::WrongProtocol)?;
+    let field = |i: usize| i32::from_le_bytes(header[i..i + 4].try_into().unwrap());
+    let (length, request_id, response_to, opcode) = (field(0), field(4), field(8), field(12));
+    if !(HEADER_LENGTH as i32..=MAX_MESSAGE_LENGTH).contains(&length) {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+    let end = (length as usize).min(remaining.len());
+    let body = &remaining[HEADER_LENGTH..end];
+
+    let op = match (opcode, client) {
+        (OP_MSG, _) => {
+            let document = msg_document(body).ok_or(CentrifugeError::InvalidPacket)?;
+            if client {
+                Op::Command(command(document, None).ok_or(CentrifugeError::InvalidPacket)?)
+            } else {
+                Op::Reply(reply(document))
+            }
+        }
+        (OP_QUERY, true) => {
+            let flags_end = body.get(4..).ok_or(CentrifugeError::InvalidPacket)?;
+            let name_end = flags_end
+                .find_byte(0)
+                .ok_or(CentrifugeError::InvalidPacket)?;
+            let name = flags_end[..name_end]
+                .to_str()
+                .map_err(|_| CentrifugeError::InvalidPacket)?;
+            // skip and return counts
+            let document = flags_end
+                .get(name_end + 9..)
+                .ok_or(CentrifugeError::InvalidPacket)?;
+            Op::Command(command(document, Some(name)).ok_or(CentrifugeError::InvalidPacket)?)
+        }
+        (OP_REPLY, false) => {
+            // flags, cursor id, starting from and number returned
+            let document = body.get(20..).ok_or(CentrifugeError::InvalidPacket)?;
+            Op::Reply(reply(document))
+        }
+        (OP_COMPRESSED | 2001..=2007, _) => Op::Other(opcode),
+        _ => return Err(CentrifugeError::WrongProtocol),
+    };
+
+    Ok(MongoDB {
+        request_id,
+        response_to,
+        op,
+    })
+}
+
+/// The body section, document sequences are skipped
+fn msg_document(body: &[u8]) -> Option<&[u8]> {
+    let mut sections = body.get(4..)?;
+    loop {
+        let (&kind, rest) = sections.split_first()?;
+        match kind {
+            0 => return Some(rest),
+            1 => {
+                let size = i32::from_le_bytes(rest.get(..4)?.try_into().ok()?);
+                sections = rest.get(usize::try_from(size).ok()?..)?;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Legacy queries name the collection or `$cmd` for commands
+fn command(document: &[u8], namespace: Option<&str>) -> Option<Command> {
+    let elements = elements(document);
+    let (name, value) = elements.first()?;
+    let string = |key: &str| {
+        elements.iter().find_map(|(name, value)| match value {
+            Bson::String(value) if *name == key => Some(value.clone()),
+            _ => None,
+        })
+    };
+
+    let (mut database, mut collection) = (string("$db"), None);
+    if let Some((db, target)) = namespace.and_then(|x| x.split_once('.')) {
+        database = Some(db.to_string());
+        if target != "$cmd" {
+            collection = Some(target.to_string());
+        }
+    }
+    if let Bson::String(value) = value {
+        collection = Some(value.clone());
+    }
+
+    Some(Command {
+        name: name.to_string(),
+        collection,
+        database,
+    })
+}
+
+fn reply(document: &[u8]) -> Reply {
+    let mut reply = Reply {
+        ok: None,
+        error: None,
+    };
+    for (name, value) in elements(document) {
+        match (name, value) {
+            ("ok", Bson::Number(ok)) => reply.ok = Some(ok),
+            ("errmsg", Bson::String(error)) => reply.error = Some(error),
+            _ => (),
+        }
+    }
+    reply
+}
+
+enum Bson {
+    String(String),
+    Number(f64),
+    Other,
+}
+
+/// The top level fields of a document, a truncated document is read up to the last complete field
+fn elements(document: &[u8]) -> Vec<(&str, Bson)> {
+    let mut elements = Vec::new();
+    let Some(mut remaining) = document.get(4..) else {
+        return elements;
+    };
+    while let Some((&kind, rest)) = remaining.split_first() {
+        if kind == 0 {
+            break;
+        }
+        let Some(name_end) = rest.find_byte(0) else {
+            break;
+        };
+        let Ok(name) = rest[..name_end].to_str() else {
+            break;
+        };
+        let rest = &rest[name_end + 1..];
+        let Some((value, size)) = element(kind, rest) else {
+            break;
+        };
+        elements.push((name, value));
+        remaining = &rest[size..];
+    }
+    elements
+}
+
+/// The value and its size
+fn element(kind: u8, data: &[u8]) -> Option<(Bson, usize)> {
+    let i32_at = |i: usize| -> Option<i32> {
+        Some(i32::from_le_bytes(data.get(i..i + 4)?.try_into().ok()?))
+    };
+    let fixed = |size: usize| (size <= data.len()).then_some((Bson::Other, size));
+
+    match kind {
+        0x01 => {
+            let value = f64::from_le_bytes(data.get(..8)?.try_into().ok()?);
+            Some((Bson::Number(value), 8))
+        }
+        0x02 | 0x0d | 0x0e => {
+            let length = usize::try_from(i32_at(0)?).ok()?;
+            let value = data.get(4..4 + length)?.strip_suffix(b"\0")?;
+            Some((Bson::String(value.to_str_lossy().into_owned()), 4 + length))
+        }
+        // documents, arrays and code with scope
+        0x03 | 0x04 | 0x0f => fixed(usize::try_from(i32_at(0)?).ok()?),
+        0x05 => fixed(4 + 1 + usize::try_from(i32_at(0)?).ok()?),
+        0x06 | 0x0a | 0x7f | 0xff => fixed(0),
+        0x07 => fixed(12),
+        0x08 => Some((Bson::Number(f64::from(*data.first()?)), 1)),
+        0x09 | 0x11 => fixed(8),
+        0x0b => {
+            let pattern = data.find_byte(0)?;
+            let options = data[pattern + 1..].find_byte(0)?;
+            fixed(pattern + options + 2)
+        }
+        0x10 => Some((Bson::Number(f64::from(i32_at(0)?)), 4)),
+        0x12 => {
+            let value = i64::from_le_bytes(data.get(..8)?.try_into().ok()?);
+            Some((Bson::Number(value as f64), 8))
+        }
+        0x13 => fixed(16),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(source_port: u16, dest_port: u16) -> TcpHeader {
+        TcpHeader {
+            source_port,
+            dest_port,
+            sequence_no: 0,
+            ack_no: 0,
+            data_offset: 5,
+            reserved: 0,
+            flag_urg: false,
+            flag_ack: true,
+            flag_psh: true,
+            flag_rst: false,
+            flag_syn: false,
+            flag_fin: false,
+            window: 0,
+            checksum: 0,
+            urgent_pointer: 0,
+            options: None,
+        }
+    }
+
+    fn document(elements: &[(u8, &str, &[u8])]) -> Vec<u8> {
+        let mut document = vec![0; 4];
+        for (kind, name, value) in elements {
+            document.push(*kind);
+            document.extend(name.as_bytes());
+            document.push(0);
+            document.extend(*value);
+        }
+        document.push(0);
+        let length = document.len() as i32;
+        document[..4].copy_from_slice(&length.to_le_bytes());
+        document
+    }
+
+    fn string(value: &str) -> Vec<u8> {
+        let mut data = (value.len() as i32 + 1).to_le_bytes().to_vec();
+        data.extend(value.as_bytes());
+        data.push(0);
+        data
+    }
+
+    fn msg(request_id: i32, response_to: i32, document: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend((HEADER_LENGTH as i32 + 5 + document.len() as i32).to_le_bytes());
+        data.extend(request_id.to_le_bytes());
+        data.extend(response_to.to_le_bytes());
+        data.extend(OP_MSG.to_le_bytes());
+        data.extend([0, 0, 0, 0, 0]);
+        data.extend(document);
+        data
+    }
+
+    #[test]
+    fn find() {
+        let document = document(&[
+            (0x02, "find", &string("users")),
+            (0x03, "filter", &document(&[(0x10, "age", &[30, 0, 0, 0])])),
+            (0x02, "$db", &string("shop")),
+        ]);
+        assert_eq!(
+            extract(&header(49152, 27017), &msg(7, 0, &document)).unwrap(),
+            MongoDB {
+                request_id: 7,
+                response_to: 0,
+                op: Op::Command(Command {
+                    name: "find".to_string(),
+                    collection: Some("users".to_string()),
+                    database: Some("shop".to_string()),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn reply() {
+        let document = document(&[
+            (0x01, "ok", &0f64.to_le_bytes()),
+            (0x02, "errmsg", &string("Authentication failed.")),
+            (0x10, "code", &[18, 0, 0, 0]),
+        ]);
+        let mongodb = extract(&header(27017, 49152), &msg(9, 7, &document)).unwrap();
+        assert_eq!(mongodb.response_to, 7);
+        assert_eq!(
+            mongodb.op,
+            Op::Reply(Reply {
+                ok: Some(0.0),
+                error: Some("Authentication failed.".to_string()),
+            })
+        );
+
+        assert!(extract(&header(49152, 8080), &msg(7, 0, &document)).is_err());
+    }
+}
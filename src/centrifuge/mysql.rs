@@ -0,0 +1,315 @@
+use crate::structs::CentrifugeError;
+use crate::structs::mysql::{Error, Handshake, Login, MySQL};
+use bstr::ByteSlice;
+use nom::bytes::complete::{take, take_till};
+use nom::number::complete::{le_u8, le_u16, le_u24, le_u32};
+use pktparse::tcp::TcpHeader;
+
+pub const PORT: u16 = 3306;
+
+const CLIENT_CONNECT_WITH_DB: u32 = 0x0000_0008;
+const CLIENT_PROTOCOL_41: u32 = 0x0000_0200;
+const CLIENT_SSL: u32 = 0x0000_0800;
+const CLIENT_SECURE_CONNECTION: u32 = 0x0000_8000;
+const CLIENT_PLUGIN_AUTH: u32 = 0x0008_0000;
+const CLIENT_PLUGIN_AUTH_LENENC_CLIENT_DATA: u32 = 0x0020_0000;
+
+const COM_QUIT: u8 = 0x01;
+const COM_INIT_DB: u8 = 0x02;
+const COM_QUERY: u8 = 0x03;
+const COM_STMT_PREPARE: u8 = 0x16;
+
+/// The first packet of a segment, result sets aren't decoded
+pub fn extract(tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<MySQL, CentrifugeError> {
+    let (payload, (length, sequence)) =
+        header(remaining).map_err(|_| CentrifugeError::WrongProtocol)?;
+    let length = length as usize;
+    // only a query is likely to continue in the next segment
+    let complete = payload.len() == length;
+    if length == 0 || payload.len() > length {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+    let &command = payload.first().ok_or(CentrifugeError::WrongProtocol)?;
+
+    let mysql = if tcp_hdr.source_port == PORT {
+        match (sequence, command) {
+            (0, 10) if complete => handshake(payload).map(|x| MySQL::Handshake(x.1)),
+            (_, 0xff) if complete => error(payload).map(|x| MySQL::Error(x.1)),
+            _ => return Err(CentrifugeError::WrongProtocol),
+        }
+    } else if tcp_hdr.dest_port == PORT {
+        match (sequence, command) {
+            (0, COM_QUERY | COM_STMT_PREPARE) => {
+                return text(&payload[1..]).map(MySQL::Query);
+            }
+            (0, COM_INIT_DB) if complete => return text(&payload[1..]).map(MySQL::InitDb),
+            (0, COM_QUIT) if length == 1 => return Ok(MySQL::Quit),
+            (1, _) if complete => login(payload).map(|x| x.1),
+            _ => return Err(CentrifugeError::WrongProtocol),
+        }
+    } else {
+        return Err(CentrifugeError::WrongProtocol);
+    };
+    mysql.map_err(|_| CentrifugeError::InvalidPacket)
+}
+
+fn header(input: &[u8]) -> nom::IResult<&[u8], (u32, u8)> {
+    let (remaining, length) = le_u24(input)?;
+    let (remaining, sequence) = le_u8(remaining)?;
+    Ok((remaining, (length, sequence)))
+}
+
+fn text(data: &[u8]) -> Result<String, CentrifugeError> {
+    let text = data.to_str().map_err(|_| CentrifugeError::WrongProtocol)?;
+    if text.is_empty() || text.contains('\0') {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+    Ok(text.to_string())
+}
+
+/// Null terminated string
+fn cstring(input: &[u8]) -> nom::IResult<&[u8], String> {
+    let (remaining, value) = take_till(|x| x == 0)(input)?;
+    let (remaining, _) = take(1usize)(remaining)?;
+    let value = value.to_str().map_err(|_| nom_error(input))?;
+    Ok((remaining, value.to_string()))
+}
+
+/// Plugins can be sent without the null terminator at the end of a packet
+fn plugin(input: &[u8]) -> Option<String> {
+    let name = input.split(|&x| x == 0).next()?;
+    let name = name.to_str().ok()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn handshake(input: &[u8]) -> nom::IResult<&[u8], Handshake> {
+    let (remaining, _protocol) = le_u8(input)?;
+    let (remaining, server_version) = cstring(remaining)?;
+    let (remaining, connection_id) = le_u32(remaining)?;
+    let (remaining, _auth_data) = take(8usize)(remaining)?;
+    let (remaining, _filler) = le_u8(remaining)?;
+    let (remaining, capabilities_low) = le_u16(remaining)?;
+    if remaining.is_empty() {
+        return Ok((
+            remaining,
+            Handshake {
+                server_version,
+                connection_id,
+                auth_plugin: None,
+            },
+        ));
+    }
+    let (remaining, _charset) = le_u8(remaining)?;
+    let (remaining, _status) = le_u16(remaining)?;
+    let (remaining, capabilities_high) = le_u16(remaining)?;
+    let capabilities = u32::from(capabilities_low) | u32::from(capabilities_high) << 16;
+    let (remaining, auth_data_length) = le_u8(remaining)?;
+    let (remaining, _reserved) = take(10usize)(remaining)?;
+
+    let mut auth_plugin = None;
+    if capabilities & CLIENT_SECURE_CONNECTION != 0 {
+        let length = usize::from(auth_data_length.saturating_sub(8)).max(13);
+        let (remaining, _auth_data) = take(length)(remaining)?;
+        if capabilities & CLIENT_PLUGIN_AUTH != 0 {
+            auth_plugin = plugin(remaining);
+        }
+    }
+
+    Ok((
+        &[],
+        Handshake {
+            server_version,
+            connection_id,
+            auth_plugin,
+        },
+    ))
+}
+
+fn login(input: &[u8]) -> nom::IResult<&[u8], MySQL> {
+    let (remaining, capabilities) = le_u32(input)?;
+    if capabilities & CLIENT_PROTOCOL_41 == 0 {
+        return Err(nom_error(input));
+    }
+    let (remaining, _max_packet_size) = le_u32(remaining)?;
+    let (remaining, _charset) = le_u8(remaining)?;
+    let (remaining, filler) = take(23usize)(remaining)?;
+    if filler.iter().any(|&x| x != 0) {
+        return Err(nom_error(input));
+    }
+    if remaining.is_empty() && capabilities & CLIENT_SSL != 0 {
+        return Ok((remaining, MySQL::SslRequest));
+    }
+
+    let (remaining, username) = cstring(remaining)?;
+    let (remaining, auth_length) = if capabilities & CLIENT_PLUGIN_AUTH_LENENC_CLIENT_DATA != 0 {
+        // the length is encoded as a single byte for anything shorter than 251 bytes
+        let (remaining, length) = le_u8(remaining)?;
+        if length >= 251 {
+            return Err(nom_error(input));
+        }
+        (remaining, length)
+    } else if capabilities & CLIENT_SECURE_CONNECTION != 0 {
+        le_u8(remaining)?
+    } else {
+        let (_, auth) = take_till(|x| x == 0)(remaining)?;
+        let length = u8::try_from(auth.len() + 1).map_err(|_| nom_error(input))?;
+        (remaining, length)
+    };
+    let (mut remaining, _auth) = take(auth_length)(remaining)?;
+
+    let mut database = None;
+    if capabilities & CLIENT_CONNECT_WITH_DB != 0 && !remaining.is_empty() {
+        let (rest, name) = cstring(remaining)?;
+        remaining = rest;
+        database = Some(name).filter(|x| !x.is_empty());
+    }
+    let auth_plugin = if capabilities & CLIENT_PLUGIN_AUTH != 0 {
+        plugin(remaining)
+    } else {
+        None
+    };
+
+    Ok((
+        &[],
+        MySQL::Login(Login {
+            username,
+            database,
+            auth_plugin,
+        }),
+    ))
+}
+
+fn error(input: &[u8]) -> nom::IResult<&[u8], Error> {
+    let (remaining, _header) = le_u8(input)?;
+    let (remaining, code) = le_u16(remaining)?;
+    let (remaining, state) = match remaining.strip_prefix(b"#") {
+        Some(rest) => {
+            let (rest, state) = take(5usize)(rest)?;
+            (rest, state.to_str().ok().map(String::from))
+        }
+        None => (remaining, None),
+    };
+    Ok((
+        &[],
+        Error {
+            code,
+            state,
+            message: remaining.to_str_lossy().into_owned(),
+        },
+    ))
+}
+
+#[inline]
+fn nom_error(input: &[u8]) -> nom::Err<nom::error::Error<&[u8]>> {
+    nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_encoding::HEXLOWER;
+
+    fn header(source_port: u16, dest_port: u16) -> TcpHeader {
+        TcpHeader {
+            source_port,
+            dest_port,
+            sequence_no: 0,
+            ack_no: 0,
+            data_offset: 5,
+            reserved: 0,
+            flag_urg: false,
+            flag_ack: true,
+            flag_psh: true,
+            flag_rst: false,
+            flag_syn: false,
+            flag_fin: false,
+            window: 0,
+            checksum: 0,
+            urgent_pointer: 0,
+            options: None,
+        }
+    }
+
+    fn decode(hex: &str) -> Vec<u8> {
+        HEXLOWER.decode(hex.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn handshake() {
+        let mut data = decode("000000000a");
+        data.extend(b"8.0.36\0");
+        data.extend(decode("0b000000"));
+        data.extend(b"abcdefgh\0");
+        // capabilities, charset, status, auth data length and reserved bytes
+        data.extend(decode("ffffff0200ffdf1500000000000000000000"));
+        data.extend(b"ijklmnopqrst\0caching_sha2_password\0");
+        data[0] = (data.len() - 4) as u8;
+
+        assert_eq!(
+            extract(&header(3306, 49152), &data).unwrap(),
+            MySQL::Handshake(Handshake {
+                server_version: "8.0.36".to_string(),
+                connection_id: 11,
+                auth_plugin: Some("caching_sha2_password".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn login() {
+        let mut data = decode("00000001");
+        data.extend(decode("8da20a00000000012d"));
+        data.extend([0; 23]);
+        data.extend(b"root\0");
+        data.extend([20]);
+        data.extend([0x42; 20]);
+        data.extend(b"shop\0mysql_native_password\0");
+        data[0] = (data.len() - 4) as u8;
+
+        assert_eq!(
+            extract(&header(49152, 3306), &data).unwrap(),
+            MySQL::Login(Login {
+                username: "root".to_string(),
+                database: Some("shop".to_string()),
+                auth_plugin: Some("mysql_native_password".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn query_and_error() {
+        let mut data = decode("0000000003");
+        data.extend(b"SELECT * FROM users WHERE id = 1");
+        data[0] = (data.len() - 4) as u8;
+        assert_eq!(
+            extract(&header(49152, 3306), &data).unwrap(),
+            MySQL::Query("SELECT * FROM users WHERE id = 1".to_string())
+        );
+        // the query continues in the next segment
+        assert_eq!(
+            extract(&header(49152, 3306), &data[..20]).unwrap(),
+            MySQL::Query("SELECT * FROM u".to_string())
+        );
+
+        let mut data = decode("00000002ff1504");
+        data.extend(b"#28000Access denied for user 'root'@'localhost'");
+        data[0] = (data.len() - 4) as u8;
+        assert_eq!(
+            extract(&header(3306, 49152), &data).unwrap(),
+            MySQL::Error(Error {
+                code: 1045,
+                state: Some("28000".to_string()),
+                message: "Access denied for user 'root'@'localhost'".to_string(),
+            })
+        );
+
+        assert!(extract(&header(49152, 8080), &data).is_err());
+    }
+
+    #[test]
+    fn header_only() {
+        let data = decode("05000000");
+        assert!(extract(&header(49152, 3306), &data).is_err());
+        assert!(extract(&header(3306, 49152), &data).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::structs::CentrifugeError;
+use crate::structs::nbns::{NBNS, Name};
+use nom::bytes::complete::take;
+use nom::number::complete::{be_u8, be_u16, be_u32};
+use std::net::Ipv4Addr;
+
+const OPCODE_QUERY: u8 = 0;
+const OPCODE_REGISTRATION: u8 = 5;
+const OPCODE_RELEASE: u8 = 6;
+const OPCODE_REFRESH: u8 = 8;
+// rfc1002 has a typo, implementations use 9 as well
+const OPCODE_REFRESH_ALT: u8 = 9;
+// windows sends multi-homed registrations with this opcode
+const OPCODE_MULTIHOMED_REGISTRATION: u8 = 15;
+
+const TYPE_NB: u16 = 0x0020;
+
+/// Decode the first-level encoding of rfc1001, each byte is split into two letters
+fn decode_name(label: &[u8]) -> Option<Name> {
+    if label.len() != 32 {
+        return None;
+    }
+
+    let mut bytes = [0; 16];
+    for (byte, pair) in bytes.iter_mut().zip(label.chunks(2)) {
+        let high = pair[0].checked_sub(b'A').filter(|x| *x < 16)?;
+        let low = pair[1].checked_sub(b'A').filter(|x| *x < 16)?;
+        *byte = (high << 4) | low;
+    }
+
+    let name = String::from_utf8_lossy(&bytes[..15]).trim_end().to_string();
+    Some(Name {
+        name,
+        suffix: bytes[15],
+    })
+}
+
+fn name(remaining: &[u8]) -> nom::IResult<&[u8], Option<Name>> {
+    let (mut remaining, length) = be_u8(remaining)?;
+    // records usually point back to the question name
+    if length & 0xc0 == 0xc0 {
+        let (remaining, _offset) = be_u8(remaining)?;
+        return Ok((remaining, None));
+    }
+
+    let (rest, label) = take(length)(remaining)?;
+    let name = decode_name(label);
+    remaining = rest;
+
+    // skip the scope id
+    loop {
+        let (rest, length) = be_u8(remaining)?;
+        if length == 0 {
+            return Ok((rest, name));
+        }
+        let (rest, _label) = take(length)(rest)?;
+        remaining = rest;
+    }
+}
+
+fn question(remaining: &[u8]) -> nom::IResult<&[u8], Option<Name>> {
+    let (remaining, name) = name(remaining)?;
+    let (remaining, _qtype) = be_u16(remaining)?;
+    let (remaining, _qclass) = be_u16(remaining)?;
+    Ok((remaining, name))
+}
+
+fn record(remaining: &[u8]) -> nom::IResult<&[u8], (Option<Name>, Vec<Ipv4Addr>)> {
+    let (remaining, name) = name(remaining)?;
+    let (remaining, rrtype) = be_u16(remaining)?;
+    let (remaining, _class) = be_u16(remaining)?;
+    let (remaining, _ttl) = be_u32(remaining)?;
+    let (remaining, rdlength) = be_u16(remaining)?;
+    let (remaining, mut rdata) = take(rdlength)(remaining)?;
+
+    let mut addrs = Vec::new();
+    if rrtype == TYPE_NB {
+        while rdata.len() >= 6 {
+            let (rest, _flags) = be_u16(rdata)?;
+            let (rest, addr) = be_u32(rest)?;
+            addrs.push(Ipv4Addr::from(addr));
+            rdata = rest;
+        }
+    }
+
+    Ok((remaining, (name, addrs)))
+}
+
+fn packet(remaining: &[u8]) -> nom::IResult<&[u8], Option<NBNS>> {
+    let (remaining, _id) = be_u16(remaining)?;
+    let (remaining, flags) = be_u16(remaining)?;
+    let (remaining, qdcount) = be_u16(remaining)?;
+    let (remaining, ancount) = be_u16(remaining)?;
+    let (remaining, _nscount) = be_u16(remaining)?;
+    let (mut remaining, arcount) = be_u16(remaining)?;
+
+    let response = flags & 0x8000 != 0;
+    let opcode = ((flags >> 11) & 0x0f) as u8;
+
+    let mut names = Vec::new();
+    let mut addrs = Vec::new();
+    for _ in 0..qdcount {
+        let (rest, name) = question(remaining)?;
+        names.extend(name);
+        remaining = rest;
+    }
+    // registrations carry the address in the additional section
+    for _ in 0..(ancount + arcount) {
+        let (rest, (name, record_addrs)) = record(remaining)?;
+        names.extend(name);
+        addrs.extend(record_addrs);
+        remaining = rest;
+    }
+
+    let nbns = match (opcode, response) {
+        (OPCODE_QUERY, false) => names.into_iter().next().map(NBNS::Query),
+        (OPCODE_QUERY, true) => names.into_iter().next().map(|n| NBNS::Response(n, addrs)),
+        (OPCODE_REGISTRATION | OPCODE_MULTIHOMED_REGISTRATION, false) => names
+            .into_iter()
+            .next()
+            .map(|n| NBNS::Registration(n, addrs)),
+        (OPCODE_RELEASE, false) => names.into_iter().next().map(|n| NBNS::Release(n, addrs)),
+        (OPCODE_REFRESH | OPCODE_REFRESH_ALT, false) => {
+            names.into_iter().next().map(|n| NBNS::Refresh(n, addrs))
+        }
+        (opcode, response) => Some(NBNS::Other { opcode, response }),
+    };
+
+    Ok((remaining, nbns))
+}
+
+/// Parse a netbios name service packet (udp port 137)
+pub fn extract(remaining: &[u8]) -> Result<NBNS, CentrifugeError> {
+    match packet(remaining) {
+        Ok((_remaining, Some(nbns))) => Ok(nbns),
+        _ => Err(CentrifugeError::InvalidPacket),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_registration() {
+        let mut pkt = vec![
+            0x80, 0x05, 0x29, 0x10, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x20,
+        ];
+        // "WORKSTATION" padded with spaces, suffix 0x00
+        let mut name = *b"WORKSTATION     ";
+        name[15] = 0x00;
+        for byte in name {
+            pkt.push(b'A' + (byte >> 4));
+            pkt.push(b'A' + (byte & 0x0f));
+        }
+        pkt.extend([0x00, 0x00, 0x20, 0x00, 0x01]);
+        // additional record, pointing to the question
+        pkt.extend([0xc0, 0x0c, 0x00, 0x20, 0x00, 0x01, 0x00, 0x04, 0x93, 0xe0]);
+        pkt.extend([0x00, 0x06, 0x00, 0x00, 192, 168, 1, 23]);
+
+        let nbns = extract(&pkt).unwrap();
+        assert_eq!(
+            nbns,
+            NBNS::Registration(
+                Name {
+                    name: String::from("WORKSTATION"),
+                    suffix: 0x00,
+                },
+                vec![Ipv4Addr::new(192, 168, 1, 23)]
+            )
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::structs::CentrifugeError;
+use crate::structs::postgres::{Error, Message, Startup};
+use bstr::ByteSlice;
+use nom::number::complete::be_u32;
+use pktparse::tcp::TcpHeader;
+
+pub const PORT: u16 = 5432;
+
+const PROTOCOL_3: u32 = 0x0003_0000;
+const SSL_REQUEST: u32 = 80877103;
+const GSSENC_REQUEST: u32 = 80877104;
+/// Startup packets are short, the server rejects anything longer than 10000 bytes
+const MAX_STARTUP_LENGTH: usize = 10000;
+
+/// The messages of a segment that are shown, eg. data rows are skipped
+pub fn extract(tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<Vec<Message>, CentrifugeError> {
+    let client = tcp_hdr.dest_port == PORT;
+    if !client && tcp_hdr.source_port != PORT {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+
+    if client && let Some(message) = startup(remaining) {
+        return Ok(vec![message]);
+    }
+
+    let mut messages = Vec::new();
+    let mut remaining = remaining;
+    while !remaining.is_empty() {
+        let [kind, a, b, c, d, ..] = *remaining else {
+            return Err(CentrifugeError::WrongProtocol);
+        };
+        let length = u32::from_be_bytes([a, b, c, d]) as usize;
+        if !kind.is_ascii_alphanumeric() || length < 4 {
+            return Err(CentrifugeError::WrongProtocol);
+        }
+        // the last message may continue in the next segment
+        let end = (1 + length).min(remaining.len());
+        let body = &remaining[5..end];
+        let complete = end == 1 + length;
+        remaining = &remaining[end..];
+
+        let message = if client {
+            match kind {
+                b'Q' => query(body),
+                // the statement of an extended query follows the name
+                b'P' => body
+                    .split_once_str(b"\0")
+                    .and_then(|(_, statement)| query(statement)),
+                b'p' if complete => password(body),
+                b'X' => Some(Message::Terminate),
+                _ => None,
+            }
+        } else {
+            match kind {
+                b'R' => be_u32::<_, nom::error::Error<&[u8]>>(body)
+                    .ok()
+                    .map(|(_, kind)| Message::Authentication(kind)),
+                b'E' => Some(Message::Error(error(body))),
+                _ => None,
+            }
+        };
+        messages.extend(message);
+    }
+
+    if messages.is_empty() {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+    Ok(messages)
+}
+
+/// The first packet of the client doesn't start with a message type
+fn startup(remaining: &[u8]) -> Option<Message> {
+    let (body, length) = be_u32::<_, nom::error::Error<&[u8]>>(remaining).ok()?;
+    let (body, code) = be_u32::<_, nom::error::Error<&[u8]>>(body).ok()?;
+    let length = length as usize;
+    if length != remaining.len() {
+        return None;
+    }
+
+    match code {
+        SSL_REQUEST if length == 8 => Some(Message::SslRequest),
+        GSSENC_REQUEST if length == 8 => Some(Message::GssEncRequest),
+        PROTOCOL_3 if length <= MAX_STARTUP_LENGTH => {
+            let mut startup = Startup {
+                user: None,
+                database: None,
+                application_name: None,
+            };
+            let body = body.strip_suffix(b"\0\0")?;
+            let mut fields = body.split_str(b"\0");
+            while let Some(key) = fields.next() {
+                let value = fields.next()?.to_str().ok()?.to_string();
+                match key {
+                    b"user" => startup.user = Some(value),
+                    b"database" => startup.database = Some(value),
+                    b"application_name" => startup.application_name = Some(value),
+                    _ => (),
+                }
+            }
+            Some(Message::Startup(startup))
+        }
+        _ => None,
+    }
+}
+
+fn query(body: &[u8]) -> Option<Message> {
+    let query = body.split_str(b"\0").next()?;
+    let query = query.to_str().ok()?;
+    if query.is_empty() {
+        return None;
+    }
+    Some(Message::Query(query.to_string()))
+}
+
+/// Sasl messages use the same type but contain binary data
+fn password(body: &[u8]) -> Option<Message> {
+    let password = body.strip_suffix(b"\0")?.to_str().ok()?;
+    if password.chars().any(|c| c.is_control() || c == '\0') {
+        return None;
+    }
+    Some(Message::Password(password.to_string()))
+}
+
+/// Fields start with their type, the list ends with a null byte
+fn error(body: &[u8]) -> Error {
+    let mut error = Error {
+        severity: None,
+        code: None,
+        message: None,
+    };
+    for field in body.split_str(b"\0") {
+        let Some((&kind, value)) = field.split_first() else {
+            break;
+        };
+        let value = Some(value.to_str_lossy().into_owned());
+        match kind {
+            b'S' => error.severity = value,
+            b'C' => error.code = value,
+            b'M' => error.message = value,
+            _ => (),
+        }
+    }
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(source_port: u16, dest_port: u16) -> TcpHeader {
+        TcpHeader {
+            source_port,
+            dest_port,
+            sequence_no: 0,
+            ack_no: 0,
+            data_offset: 5,
+            reserved: 0,
+            flag_urg: false,
+            flag_ack: true,
+            flag_psh: true,
+            flag_rst: false,
+            flag_syn: false,
+            flag_fin: false,
+            window: 0,
+            checksum: 0,
+            urgent_pointer: 0,
+            options: None,
+        }
+    }
+
+    fn message(kind: u8, body: &[u8]) -> Vec<u8> {
+        let mut data = vec![kind];
+        data.extend((body.len() as u32 + 4).to_be_bytes());
+        data.extend(body);
+        data
+    }
+
+    #[test]
+    fn startup() {
+        let body = b"\0\x03\0\0user\0alice\0database\0shop\0application_name\0psql\0\0";
+        let mut data = (body.len() as u32 + 4).to_be_bytes().to_vec();
+        data.extend(body);
+        assert_eq!(
+            extract(&header(49152, 5432), &data).unwrap(),
+            [Message::Startup(Startup {
+                user: Some("alice".to_string()),
+                database: Some("shop".to_string()),
+                application_name: Some("psql".to_string()),
+            })]
+        );
+
+        assert_eq!(
+            extract(&header(49152, 5432), b"\0\0\0\x08\x04\xd2\x16\x2f").unwrap(),
+            [Message::SslRequest]
+        );
+    }
+
+    #[test]
+    fn login() {
+        let mut data = message(b'R', &[0, 0, 0, 3]);
+        assert_eq!(
+            extract(&header(5432, 49152), &data).unwrap(),
+            [Message::Authentication(3)]
+        );
+
+        data = message(b'p', b"hunter2\0");
+        assert_eq!(
+            extract(&header(49152, 5432), &data).unwrap(),
+            [Message::Password("hunter2".to_string())]
+        );
+
+        data = message(
+            b'E',
+            b"SFATAL\0VFATAL\0C28P01\0Mpassword authentication failed for user \"alice\"\0\0",
+        );
+        assert_eq!(
+            extract(&header(5432, 49152), &data).unwrap(),
+            [Message::Error(Error {
+                severity: Some("FATAL".to_string()),
+                code: Some("28P01".to_string()),
+                message: Some("password authentication failed for user \"alice\"".to_string()),
+            })]
+        );
+    }
+
+    #[test]
+    fn queries() {
+        let mut data = message(b'P', b"\0SELECT * FROM orders WHERE id = $1\0\0\0");
+        data.extend(message(b'B', b"\0\0\0\0\0\x01\0\0\0\x0242\0\0"));
+        data.extend(message(b'S', b""));
+        assert_eq!(
+            extract(&header(49152, 5432), &data).unwrap(),
+            [Message::Query(
+                "SELECT * FROM orders WHERE id = $1".to_string()
+            )]
+        );
+
+        // data rows aren't shown
+        let data = message(b'D', b"\0\x01\0\0\0\x0242");
+        assert!(extract(&header(5432, 49152), &data).is_err());
+        assert!(extract(&header(49152, 8080), &message(b'X', b"")).is_err());
+    }
+}
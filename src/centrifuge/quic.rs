@@ -0,0 +1,443 @@
+use crate::bounded::BoundedMap;
+use crate::centrifuge::tls;
+use crate::structs::quic::{CryptoFrame, PacketType, QUIC};
+use crate::structs::raw::Raw;
+use crate::structs::tls::ClientHello;
+use crate::structs::{CentrifugeError, ether, ipv4, ipv6, udp};
+use aes::Aes128;
+use aes::cipher::{BlockCipherEncrypt, KeyInit};
+use aes_gcm::Aes128Gcm;
+use aes_gcm::aead::{Aead, Payload};
+use data_encoding::HEXLOWER;
+use hkdf::Hkdf;
+use nom::bytes::complete::take;
+use nom::number::complete::{be_u8, be_u32};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+use tls_parser::{TlsMessage, TlsMessageHandshake};
+
+const VERSION_1: u32 = 0x0000_0001;
+const VERSION_2: u32 = 0x6b33_43cf;
+
+/// Initial salts from rfc9001 and rfc9369
+const SALT_V1: [u8; 20] = [
+    0x38, 0x76, 0x2c, 0xf7, 0xf5, 0x59, 0x34, 0xb3, 0x4d, 0x17, 0x9a, 0xe6, 0xa4, 0xc8, 0x0c, 0xad,
+    0xcc, 0xbb, 0x7f, 0x0a,
+];
+const SALT_V2: [u8; 20] = [
+    0x0d, 0xed, 0xe3, 0xde, 0xf7, 0x00, 0xa6, 0xdb, 0x81, 0x93, 0x81, 0xbe, 0x6e, 0x26, 0x9d, 0xcb,
+    0xf9, 0xbd, 0x2e, 0xd9,
+];
+
+const MAX_CONNECTIONS: usize = 1024;
+/// A client hello is sent in the first few initial packets, don't buffer more than that
+const MAX_CRYPTO_LENGTH: u64 = 16 * 1024;
+
+/// A long header packet, the packet number and payload are still protected
+struct Packet<'a> {
+    version: u32,
+    packet_type: PacketType,
+    dcid: &'a [u8],
+    scid: &'a [u8],
+    header: &'a [u8],
+    payload: &'a [u8],
+}
+
+pub fn extract(remaining: &[u8]) -> Result<QUIC, CentrifugeError> {
+    let (mut remaining, first) = packet(remaining).map_err(|_| CentrifugeError::WrongProtocol)?;
+    let mut crypto = initial_crypto(&first);
+
+    // initial packets can be coalesced with other packets of the same connection
+    while let Ok((rest, packet)) = packet(remaining) {
+        if packet.dcid != first.dcid {
+            break;
+        }
+        crypto.extend(initial_crypto(&packet));
+        remaining = rest;
+    }
+
+    Ok(QUIC {
+        version: first.version,
+        packet_type: first.packet_type,
+        dcid: HEXLOWER.encode(first.dcid),
+        scid: HEXLOWER.encode(first.scid),
+        client_hello: client_hello(&CryptoStream::from(&crypto)),
+        crypto,
+    })
+}
+
+fn packet(input: &[u8]) -> nom::IResult<&[u8], Packet<'_>> {
+    let (remaining, first) = be_u8(input)?;
+    let (remaining, version) = be_u32(remaining)?;
+    // long header with the fixed bit, it's only unset in version negotiation packets
+    if first & 0x80 == 0 || (first & 0x40 == 0 && version != 0) {
+        return Err(nom_error(input));
+    }
+    let (remaining, dcid_len) = be_u8(remaining)?;
+    let (remaining, dcid) = take(dcid_len)(remaining)?;
+    let (remaining, scid_len) = be_u8(remaining)?;
+    let (remaining, scid) = take(scid_len)(remaining)?;
+
+    let packet_type = match (version, (first >> 4) & 0x03) {
+        (0, _) => PacketType::VersionNegotiation,
+        (VERSION_1, 0) | (VERSION_2, 1) => PacketType::Initial,
+        (VERSION_1, 1) | (VERSION_2, 2) => PacketType::ZeroRtt,
+        (VERSION_1, 2) | (VERSION_2, 3) => PacketType::Handshake,
+        (VERSION_1, 3) | (VERSION_2, 0) => PacketType::Retry,
+        _ => return Err(nom_error(input)),
+    };
+
+    let (remaining, header, payload) = match packet_type {
+        // the rest of the datagram are supported versions or the retry token
+        PacketType::VersionNegotiation | PacketType::Retry => (&[][..], remaining, remaining),
+        _ => {
+            let remaining = if packet_type == PacketType::Initial {
+                let (remaining, token_len) = varint(remaining)?;
+                let (remaining, _token) = take(token_len)(remaining)?;
+                remaining
+            } else {
+                remaining
+            };
+            let (remaining, length) = varint(remaining)?;
+            let header = &input[..input.len() - remaining.len()];
+            let (remaining, payload) = take(length)(remaining)?;
+            (remaining, header, payload)
+        }
+    };
+
+    Ok((
+        remaining,
+        Packet {
+            version,
+            packet_type,
+            dcid,
+            scid,
+            header,
+            payload,
+        },
+    ))
+}
+
+fn varint(input: &[u8]) -> nom::IResult<&[u8], u64> {
+    let (_, first) = be_u8(input)?;
+    let length = 1usize << (first >> 6);
+    let (remaining, bytes) = take(length)(input)?;
+    let value = bytes[1..]
+        .iter()
+        .fold(u64::from(first & 0x3f), |value, byte| {
+            value << 8 | u64::from(*byte)
+        });
+    Ok((remaining, value))
+}
+
+#[inline]
+fn nom_error(input: &[u8]) -> nom::Err<nom::error::Error<&[u8]>> {
+    nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify))
+}
+
+/// The crypto frames of a client's initial packet, anything else can't be decrypted without state
+fn initial_crypto(packet: &Packet) -> Vec<CryptoFrame> {
+    if packet.packet_type != PacketType::Initial {
+        return Vec::new();
+    }
+    decrypt(packet)
+        .map(|payload| crypto_frames(&payload))
+        .unwrap_or_default()
+}
+
+/// HKDF-Expand-Label from tls1.3 with an empty context
+fn expand_label(secret: &[u8], label: &str, out: &mut [u8]) -> Option<()> {
+    let label = format!("tls13 {label}");
+    let mut info = Vec::with_capacity(4 + label.len());
+    info.extend((out.len() as u16).to_be_bytes());
+    info.push(label.len() as u8);
+    info.extend(label.as_bytes());
+    info.push(0);
+    Hkdf::<Sha256>::from_prk(secret)
+        .ok()?
+        .expand(&info, out)
+        .ok()
+}
+
+struct Keys {
+    key: [u8; 16],
+    iv: [u8; 12],
+    hp: [u8; 16],
+}
+
+/// The client's initial keys are derived from the destination connection id it picked
+fn client_keys(version: u32, dcid: &[u8]) -> Option<Keys> {
+    let (salt, prefix) = match version {
+        VERSION_1 => (&SALT_V1, "quic"),
+        VERSION_2 => (&SALT_V2, "quicv2"),
+        _ => return None,
+    };
+    let (initial_secret, _) = Hkdf::<Sha256>::extract(Some(salt), dcid);
+    let mut secret = [0; 32];
+    expand_label(&initial_secret, "client in", &mut secret)?;
+
+    let mut keys = Keys {
+        key: [0; 16],
+        iv: [0; 12],
+        hp: [0; 16],
+    };
+    expand_label(&secret, &format!("{prefix} key"), &mut keys.key)?;
+    expand_label(&secret, &format!("{prefix} iv"), &mut keys.iv)?;
+    expand_label(&secret, &format!("{prefix} hp"), &mut keys.hp)?;
+    Some(keys)
+}
+
+fn decrypt(packet: &Packet) -> Option<Vec<u8>> {
+    let keys = client_keys(packet.version, packet.dcid)?;
+
+    // header protection, the sample starts 4 bytes after the packet number
+    let sample = <[u8; 16]>::try_from(packet.payload.get(4..20)?).ok()?;
+    let mut mask = aes::Block::from(sample);
+    Aes128::new_from_slice(&keys.hp)
+        .ok()?
+        .encrypt_block(&mut mask);
+
+    let mut header = packet.header.to_vec();
+    header[0] ^= mask[0] & 0x0f;
+    let pn_len = usize::from(header[0] & 0x03) + 1;
+    let mut pn = 0u64;
+    for i in 0..pn_len {
+        let byte = packet.payload[i] ^ mask[1 + i];
+        header.push(byte);
+        pn = pn << 8 | u64::from(byte);
+    }
+
+    let mut nonce = keys.iv;
+    for (nonce, pn) in nonce[4..].iter_mut().zip(pn.to_be_bytes()) {
+        *nonce ^= pn;
+    }
+
+    Aes128Gcm::new_from_slice(&keys.key)
+        .ok()?
+        .decrypt(
+            &nonce.into(),
+            Payload {
+                msg: &packet.payload[pn_len..],
+                aad: &header,
+            },
+        )
+        .ok()
+}
+
+/// Initial packets may only contain padding, ping, ack, crypto and connection close frames
+fn crypto_frames(mut remaining: &[u8]) -> Vec<CryptoFrame> {
+    let mut frames = Vec::new();
+    while let Ok((rest, frame_type)) = varint(remaining) {
+        let frame = match frame_type {
+            0x00 | 0x01 => Ok((rest, None)),
+            0x02 | 0x03 => ack(rest, frame_type == 0x03).map(|(rest, _)| (rest, None)),
+            0x06 => crypto(rest).map(|(rest, frame)| (rest, Some(frame))),
+            0x1c => connection_close(rest).map(|(rest, _)| (rest, None)),
+            _ => break,
+        };
+        let Ok((rest, frame)) = frame else {
+            break;
+        };
+        frames.extend(frame);
+        remaining = rest;
+    }
+    frames
+}
+
+fn ack(remaining: &[u8], ecn: bool) -> nom::IResult<&[u8], ()> {
+    let (remaining, _largest) = varint(remaining)?;
+    let (remaining, _delay) = varint(remaining)?;
+    let (remaining, ranges) = varint(remaining)?;
+    let (mut remaining, _first_range) = varint(remaining)?;
+    // each range is a gap and a length
+    let values = ranges.saturating_mul(2) + if ecn { 3 } else { 0 };
+    for _ in 0..values {
+        (remaining, _) = varint(remaining)?;
+    }
+    Ok((remaining, ()))
+}
+
+fn crypto(remaining: &[u8]) -> nom::IResult<&[u8], CryptoFrame> {
+    let (remaining, offset) = varint(remaining)?;
+    let (remaining, length) = varint(remaining)?;
+    let (remaining, data) = take(length)(remaining)?;
+    Ok((
+        remaining,
+        CryptoFrame {
+            offset,
+            data: data.to_vec(),
+        },
+    ))
+}
+
+fn connection_close(remaining: &[u8]) -> nom::IResult<&[u8], ()> {
+    let (remaining, _error) = varint(remaining)?;
+    let (remaining, _frame_type) = varint(remaining)?;
+    let (remaining, length) = varint(remaining)?;
+    let (remaining, _reason) = take(length)(remaining)?;
+    Ok((remaining, ()))
+}
+
+/// The received parts of a crypto stream, overlapping and adjacent frames are merged
+#[derive(Default)]
+struct CryptoStream {
+    ranges: BTreeMap<u64, Vec<u8>>,
+}
+
+impl CryptoStream {
+    fn from(frames: &[CryptoFrame]) -> CryptoStream {
+        let mut stream = CryptoStream::default();
+        for frame in frames {
+            stream.insert(frame);
+        }
+        stream
+    }
+
+    /// Data past the length limit is dropped, so a connection never buffers more than that
+    fn insert(&mut self, frame: &CryptoFrame) {
+        let mut start = frame.offset;
+        let mut end = start.saturating_add(frame.data.len() as u64);
+        if frame.data.is_empty() || end > MAX_CRYPTO_LENGTH {
+            return;
+        }
+
+        let overlapping = self
+            .ranges
+            .range(..=end)
+            .rev()
+            .take_while(|(offset, data)| **offset + data.len() as u64 >= frame.offset)
+            .map(|(offset, _)| *offset)
+            .collect::<Vec<_>>();
+        let mut parts = Vec::new();
+        for offset in overlapping {
+            if let Some(data) = self.ranges.remove(&offset) {
+                start = start.min(offset);
+                end = end.max(offset + data.len() as u64);
+                parts.push((offset, data));
+            }
+        }
+        parts.push((frame.offset, frame.data.clone()));
+
+        let mut data = vec![0; (end - start) as usize];
+        for (offset, part) in parts {
+            let offset = (offset - start) as usize;
+            data[offset..offset + part.len()].copy_from_slice(&part);
+        }
+        self.ranges.insert(start, data);
+    }
+
+    /// The contiguous data from the beginning of the stream
+    fn start(&self) -> &[u8] {
+        self.ranges.get(&0).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+/// Parse the client hello once the start of the crypto stream is complete
+fn client_hello(stream: &CryptoStream) -> Option<ClientHello> {
+    match tls_parser::parse_tls_message_handshake(stream.start()) {
+        Ok((_, TlsMessage::Handshake(TlsMessageHandshake::ClientHello(ch)))) => {
+            tls::client_hello(&ch, true).ok()
+        }
+        _ => None,
+    }
+}
+
+/// The initial packet of a datagram if its client hello is incomplete
+pub fn incomplete(packet: &mut Raw) -> Option<&mut QUIC> {
+    let udp = match packet.ether_mut()? {
+        ether::Ether::IPv4(_, ipv4::IPv4::UDP(_, udp)) => udp,
+        ether::Ether::IPv6(_, ipv6::IPv6::UDP(_, udp)) => udp,
+        _ => return None,
+    };
+    match udp {
+        udp::UDP::QUIC(quic) if quic.client_hello.is_none() && !quic.crypto.is_empty() => {
+            Some(quic)
+        }
+        _ => None,
+    }
+}
+
+/// Client hellos with large key shares are split over multiple initial packets
+#[derive(Default)]
+pub struct Reassembly {
+    streams: BoundedMap<String, CryptoStream, MAX_CONNECTIONS>,
+}
+
+impl Reassembly {
+    pub fn reassemble(&mut self, timestamp: SystemTime, quic: &mut QUIC) {
+        let stream =
+            self.streams
+                .touch_or_insert_with(timestamp, quic.dcid.clone(), CryptoStream::default);
+        for frame in &quic.crypto {
+            stream.insert(frame);
+        }
+
+        if let Some(client_hello) = client_hello(stream) {
+            quic.client_hello = Some(client_hello);
+            self.streams.remove(&quic.dcid);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varints() {
+        assert_eq!(varint(&[0x25]), Ok((&[][..], 37)));
+        assert_eq!(varint(&[0x7b, 0xbd]), Ok((&[][..], 15293)));
+        assert_eq!(
+            varint(&[0x9d, 0x7f, 0x3e, 0x7d, 0x00]),
+            Ok((&[0x00][..], 494878333))
+        );
+    }
+
+    #[test]
+    fn initial_keys() {
+        let dcid = [0x83, 0x94, 0xc8, 0xf0, 0x3e, 0x51, 0x57, 0x08];
+
+        let keys = client_keys(VERSION_1, &dcid).unwrap();
+        assert_eq!(
+            HEXLOWER.encode(&keys.key),
+            "1f369613dd76d5467730efcbe3b1a22d"
+        );
+        assert_eq!(HEXLOWER.encode(&keys.iv), "fa044b2f42a3fd3b46fb255c");
+        assert_eq!(
+            HEXLOWER.encode(&keys.hp),
+            "9f50449e04a0e810283a1e9933adedd2"
+        );
+
+        let keys = client_keys(VERSION_2, &dcid).unwrap();
+        assert_eq!(
+            HEXLOWER.encode(&keys.key),
+            "8b1a0bc121284290a29e0971b5cd045d"
+        );
+        assert_eq!(HEXLOWER.encode(&keys.iv), "91f73e2351d8fa91660e909f");
+        assert_eq!(
+            HEXLOWER.encode(&keys.hp),
+            "45b95e15235d6f45a6b19cbcb0294ba9"
+        );
+    }
+
+    #[test]
+    fn crypto_stream() {
+        let frame = |offset: u64, data: &[u8]| CryptoFrame {
+            offset,
+            data: data.to_vec(),
+        };
+        let mut stream = CryptoStream::default();
+        stream.insert(&frame(6, b"ghi"));
+        stream.insert(&frame(3, b"def"));
+        assert_eq!(stream.start(), b"");
+
+        // overlapping frames don't take up more space
+        for offset in 0..6 {
+            stream.insert(&frame(offset, b"abcdef"[offset as usize..].as_ref()));
+        }
+        stream.insert(&frame(MAX_CRYPTO_LENGTH - 1, b"xx"));
+        assert_eq!(stream.ranges.len(), 1);
+        assert_eq!(stream.start(), b"abcdefghi");
+    }
+}
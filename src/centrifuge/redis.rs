@@ -0,0 +1,209 @@
+use crate::structs::CentrifugeError;
+use crate::structs::redis::{Command, Redis, Value};
+use bstr::ByteSlice;
+use pktparse::tcp::TcpHeader;
+
+pub const PORT: u16 = 6379;
+
+/// Replies of scripts can be nested, anything deeper is unlikely to be resp
+const MAX_DEPTH: usize = 8;
+/// Inline commands are meant to be typed by hand
+const MAX_INLINE_LENGTH: usize = 4096;
+
+/// Every complete command or reply of a segment, a value that continues in the next segment is skipped
+pub fn extract(tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<Redis, CentrifugeError> {
+    if tcp_hdr.dest_port == PORT {
+        let commands = if remaining.starts_with(b"*") {
+            values(remaining)
+                .into_iter()
+                .map(command)
+                .collect::<Option<Vec<_>>>()
+        } else {
+            inline_commands(remaining)
+        };
+        match commands {
+            Some(commands) if !commands.is_empty() => Ok(Redis::Commands(commands)),
+            _ => Err(CentrifugeError::WrongProtocol),
+        }
+    } else if tcp_hdr.source_port == PORT {
+        let replies = values(remaining);
+        if replies.is_empty() {
+            return Err(CentrifugeError::WrongProtocol);
+        }
+        Ok(Redis::Replies(replies))
+    } else {
+        Err(CentrifugeError::WrongProtocol)
+    }
+}
+
+/// Stops at the first value that is incomplete
+fn values(mut remaining: &[u8]) -> Vec<Value> {
+    let mut values = Vec::new();
+    while let Some((rest, value)) = value(remaining, 0) {
+        values.push(value);
+        remaining = rest;
+    }
+    values
+}
+
+fn command(value: Value) -> Option<Command> {
+    let Value::Array(Some(args)) = value else {
+        return None;
+    };
+    let args = args
+        .into_iter()
+        .map(|arg| match arg {
+            Value::Bulk(Some(arg)) => Some(arg),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if args.is_empty() {
+        return None;
+    }
+    Some(Command { args })
+}
+
+/// Commands that are sent without resp framing, eg. with telnet
+fn inline_commands(remaining: &[u8]) -> Option<Vec<Command>> {
+    if remaining.len() > MAX_INLINE_LENGTH || !remaining.ends_with(b"\n") {
+        return None;
+    }
+    let text = remaining.to_str().ok()?;
+    let mut commands = Vec::new();
+    for line in text.lines() {
+        if line.chars().any(|c| c.is_control()) {
+            return None;
+        }
+        let args = line
+            .split_whitespace()
+            .map(|x| x.as_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let name = args.first()?;
+        if !name.iter().all(|x| x.is_ascii_alphabetic()) {
+            return None;
+        }
+        commands.push(Command { args });
+    }
+    Some(commands)
+}
+
+fn line(input: &[u8]) -> Option<(&[u8], &str)> {
+    let end = input.find(b"\r\n")?;
+    let line = input[..end].to_str().ok()?;
+    Some((&input[end + 2..], line))
+}
+
+fn value(input: &[u8], depth: usize) -> Option<(&[u8], Value)> {
+    let (&kind, rest) = input.split_first()?;
+    let (rest, line) = line(rest)?;
+
+    let value = match kind {
+        b'+' | b',' | b'#' | b'(' => Value::Simple(line.to_string()),
+        b'-' => Value::Error(line.to_string()),
+        b':' => Value::Integer(line.parse().ok()?),
+        b'_' => Value::Bulk(None),
+        b'$' => {
+            let length = line.parse::<i64>().ok()?;
+            if length < 0 {
+                return Some((rest, Value::Bulk(None)));
+            }
+            let length = usize::try_from(length).ok()?;
+            let data = rest.get(..length)?;
+            let rest = rest.get(length..)?.strip_prefix(b"\r\n")?;
+            return Some((rest, Value::Bulk(Some(data.to_vec()))));
+        }
+        b'*' | b'%' | b'~' | b'>' => {
+            let count = line.parse::<i64>().ok()?;
+            if count < 0 {
+                return Some((rest, Value::Array(None)));
+            }
+            if depth >= MAX_DEPTH {
+                return None;
+            }
+            // maps are sent as key value pairs
+            let count = usize::try_from(count).ok()? * if kind == b'%' { 2 } else { 1 };
+            let mut rest = rest;
+            let mut values = Vec::new();
+            for _ in 0..count {
+                let (next, value) = value(rest, depth + 1)?;
+                values.push(value);
+                rest = next;
+            }
+            return Some((rest, Value::Array(Some(values))));
+        }
+        _ => return None,
+    };
+    Some((rest, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(source_port: u16, dest_port: u16) -> TcpHeader {
+        TcpHeader {
+            source_port,
+            dest_port,
+            sequence_no: 0,
+            ack_no: 0,
+            data_offset: 5,
+            reserved: 0,
+            flag_urg: false,
+            flag_ack: true,
+            flag_psh: true,
+            flag_rst: false,
+            flag_syn: false,
+            flag_fin: false,
+            window: 0,
+            checksum: 0,
+            urgent_pointer: 0,
+            options: None,
+        }
+    }
+
+    #[test]
+    fn pipelined_commands() {
+        let redis = extract(
+            &header(49152, 6379),
+            b"*2\r\n$4\r\nAUTH\r\n$7\r\nhunter2\r\n*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n",
+        )
+        .unwrap();
+        let Redis::Commands(commands) = redis else {
+            panic!("expected commands, got {:?}", redis);
+        };
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].name(), "AUTH");
+        assert_eq!(commands[1].args[2], b"value");
+    }
+
+    #[test]
+    fn inline_command() {
+        assert_eq!(
+            extract(&header(49152, 6379), b"PING\r\n").unwrap(),
+            Redis::Commands(vec![Command {
+                args: vec![b"PING".to_vec()],
+            }])
+        );
+        assert!(extract(&header(49152, 8080), b"PING\r\n").is_err());
+    }
+
+    #[test]
+    fn replies() {
+        assert_eq!(
+            extract(
+                &header(6379, 49152),
+                b"+OK\r\n$-1\r\n*2\r\n:1\r\n$5\r\nhello\r\n-WRONGPASS invalid\r\n$100\r\nincomplete",
+            )
+            .unwrap(),
+            Redis::Replies(vec![
+                Value::Simple("OK".to_string()),
+                Value::Bulk(None),
+                Value::Array(Some(vec![
+                    Value::Integer(1),
+                    Value::Bulk(Some(b"hello".to_vec())),
+                ])),
+                Value::Error("WRONGPASS invalid".to_string()),
+            ])
+        );
+    }
+}
@@ -90,7 +90,8 @@ mod tests {
                         0, 40, 78, 0, 0, 0, 0, 0, 3, 251, 177, 252, 25, 181, 4, 23, 100, 102, 211,
                         164, 79, 192, 232, 227, 130, 103, 52, 17, 8, 4, 169, 136, 247, 108, 69, 53,
                         165, 67, 201, 73, 66, 79
-                    ],)
+                    ]),
+                    Vec::new(),
                 )
             )
         );
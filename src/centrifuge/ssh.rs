@@ -0,0 +1,329 @@
+use crate::structs::CentrifugeError;
+use crate::structs::ssh::{self, Banner, KexInit, SSH};
+use bstr::ByteSlice;
+use data_encoding::HEXLOWER;
+use md5::{Digest, Md5};
+use nom::bytes::complete::take;
+use nom::number::complete::{be_u8, be_u32};
+use pktparse::tcp::TcpHeader;
+
+pub const PORT: u16 = 22;
+
+const SSH_MSG_KEXINIT: u8 = 20;
+/// rfc4253 requires implementations to support packets of at least this size
+const MAX_PACKET_LENGTH: u32 = 35000;
+/// rfc4253 limits the identification string to 255 characters including the line break
+const MAX_BANNER_LENGTH: usize = 255;
+
+pub fn extract(tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<SSH, CentrifugeError> {
+    let (remaining, banner) = match banner(remaining) {
+        Some((remaining, banner)) => (remaining, Some(banner)),
+        None => (remaining, None),
+    };
+
+    // the kexinit of both sides looks the same, the port tells hassh and hasshServer apart
+    let server = match (tcp_hdr.source_port, tcp_hdr.dest_port) {
+        (PORT, _) => Some(true),
+        (_, PORT) => Some(false),
+        _ => None,
+    };
+
+    let kex_init = if remaining.is_empty() {
+        None
+    } else if let Some(server) = server {
+        match kex_init(remaining, server) {
+            Ok((_, kex_init)) => Some(kex_init),
+            // a banner can be followed by anything, but a kexinit is all we look for without one
+            Err(_) if banner.is_some() => None,
+            Err(_) => return Err(CentrifugeError::WrongProtocol),
+        }
+    } else if banner.is_some() {
+        None
+    } else {
+        return Err(CentrifugeError::WrongProtocol);
+    };
+
+    if banner.is_none() && kex_init.is_none() {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+
+    Ok(SSH { banner, kex_init })
+}
+
+fn banner(remaining: &[u8]) -> Option<(&[u8], Banner)> {
+    if !remaining.starts_with(b"SSH-") {
+        return None;
+    }
+    let end = remaining.find_byte(b'\n')?;
+    if end >= MAX_BANNER_LENGTH {
+        return None;
+    }
+    let line = remaining[..end]
+        .strip_suffix(b"\r")
+        .unwrap_or(&remaining[..end]);
+    let line = line.to_str().ok()?;
+    if line.chars().any(|c| c.is_control()) {
+        return None;
+    }
+
+    let (ident, comments) = match line.split_once(' ') {
+        Some((ident, comments)) => (ident, Some(comments.to_string())),
+        None => (line, None),
+    };
+    let (protocol, software) = ident["SSH-".len()..].split_once('-')?;
+    if !matches!(protocol, "2.0" | "1.99" | "1.5") || software.is_empty() {
+        return None;
+    }
+
+    Some((
+        &remaining[end + 1..],
+        Banner {
+            protocol: protocol.to_string(),
+            software: software.to_string(),
+            comments,
+        },
+    ))
+}
+
+fn kex_init(input: &[u8], server: bool) -> nom::IResult<&[u8], KexInit> {
+    let (remaining, packet_length) = be_u32(input)?;
+    let (remaining, padding_length) = be_u8(remaining)?;
+    if packet_length > MAX_PACKET_LENGTH || u32::from(padding_length) + 1 >= packet_length {
+        return Err(nom_error(input));
+    }
+    let (remaining, message) = be_u8(remaining)?;
+    if message != SSH_MSG_KEXINIT {
+        return Err(nom_error(input));
+    }
+    let (remaining, _cookie) = take(16usize)(remaining)?;
+
+    let (remaining, kex_algorithms) = name_list(remaining)?;
+    let (remaining, host_key_algorithms) = name_list(remaining)?;
+    let (remaining, encryption_client_to_server) = name_list(remaining)?;
+    let (remaining, encryption_server_to_client) = name_list(remaining)?;
+    let (remaining, mac_client_to_server) = name_list(remaining)?;
+    let (remaining, mac_server_to_client) = name_list(remaining)?;
+    let (remaining, compression_client_to_server) = name_list(remaining)?;
+    let (remaining, compression_server_to_client) = name_list(remaining)?;
+    let (remaining, _languages_client_to_server) = name_list(remaining)?;
+    let (remaining, _languages_server_to_client) = name_list(remaining)?;
+    let (remaining, first_kex_follows) = be_u8(remaining)?;
+
+    let hassh = if server {
+        hassh(
+            &kex_algorithms,
+            &encryption_server_to_client,
+            &mac_server_to_client,
+            &compression_server_to_client,
+        )
+    } else {
+        hassh(
+            &kex_algorithms,
+            &encryption_client_to_server,
+            &mac_client_to_server,
+            &compression_client_to_server,
+        )
+    };
+
+    // compression lists contain none in every connection
+    let mut weak = Vec::new();
+    for algorithm in [
+        &kex_algorithms,
+        &host_key_algorithms,
+        &encryption_client_to_server,
+        &encryption_server_to_client,
+        &mac_client_to_server,
+        &mac_server_to_client,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if ssh::is_weak(algorithm) && !weak.contains(algorithm) {
+            weak.push(algorithm.clone());
+        }
+    }
+
+    Ok((
+        remaining,
+        KexInit {
+            server,
+            kex_algorithms,
+            host_key_algorithms,
+            encryption_client_to_server,
+            encryption_server_to_client,
+            mac_client_to_server,
+            mac_server_to_client,
+            compression_client_to_server,
+            compression_server_to_client,
+            first_kex_follows: first_kex_follows != 0,
+            hassh,
+            weak,
+        },
+    ))
+}
+
+/// Comma separated algorithm names, rfc4251 restricts them to printable ascii
+fn name_list(input: &[u8]) -> nom::IResult<&[u8], Vec<String>> {
+    let (remaining, length) = be_u32(input)?;
+    if length > MAX_PACKET_LENGTH {
+        return Err(nom_error(input));
+    }
+    let (remaining, list) = take(length)(remaining)?;
+    if !list.iter().all(|c| c.is_ascii_graphic()) {
+        return Err(nom_error(input));
+    }
+    let list = list
+        .split_str(",")
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_str_lossy().into_owned())
+        .collect();
+    Ok((remaining, list))
+}
+
+/// The md5 of kex, encryption, mac and compression algorithms sent in one direction
+fn hassh(kex: &[String], encryption: &[String], mac: &[String], compression: &[String]) -> String {
+    let text = [kex, encryption, mac, compression]
+        .iter()
+        .map(|x| x.join(","))
+        .collect::<Vec<_>>()
+        .join(";");
+    HEXLOWER.encode(&Md5::digest(text.as_bytes()))
+}
+
+#[inline]
+fn nom_error(input: &[u8]) -> nom::Err<nom::error::Error<&[u8]>> {
+    nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(source_port: u16, dest_port: u16) -> TcpHeader {
+        TcpHeader {
+            source_port,
+            dest_port,
+            sequence_no: 0,
+            ack_no: 0,
+            data_offset: 5,
+            reserved: 0,
+            flag_urg: false,
+            flag_ack: true,
+            flag_psh: true,
+            flag_rst: false,
+            flag_syn: false,
+            flag_fin: false,
+            window: 0,
+            checksum: 0,
+            urgent_pointer: 0,
+            options: None,
+        }
+    }
+
+    fn packet(lists: &[&str]) -> Vec<u8> {
+        let mut payload = vec![SSH_MSG_KEXINIT];
+        payload.extend_from_slice(&[0x42; 16]);
+        for list in lists {
+            payload.extend_from_slice(&(list.len() as u32).to_be_bytes());
+            payload.extend_from_slice(list.as_bytes());
+        }
+        payload.extend_from_slice(&[0, 0, 0, 0, 0]);
+
+        let padding = 8 - (payload.len() + 5) % 8 + 4;
+        let mut packet = ((payload.len() + padding + 1) as u32)
+            .to_be_bytes()
+            .to_vec();
+        packet.push(padding as u8);
+        packet.extend_from_slice(&payload);
+        packet.extend(std::iter::repeat_n(0, padding));
+        packet
+    }
+
+    #[test]
+    fn banner_and_kex_init() {
+        let mut data = b"SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13\r\n".to_vec();
+        data.extend(packet(&[
+            "curve25519-sha256,diffie-hellman-group1-sha1",
+            "ssh-ed25519,ssh-rsa",
+            "aes128-ctr,3des-cbc",
+            "aes128-ctr",
+            "hmac-sha2-256",
+            "hmac-sha2-256,hmac-md5",
+            "none,zlib@openssh.com",
+            "none",
+            "",
+            "",
+        ]));
+
+        let ssh = extract(&header(50000, 22), &data).unwrap();
+        assert_eq!(
+            ssh.banner,
+            Some(Banner {
+                protocol: "2.0".to_string(),
+                software: "OpenSSH_9.6p1".to_string(),
+                comments: Some("Ubuntu-3ubuntu13".to_string()),
+            })
+        );
+        let kex_init = ssh.kex_init.unwrap();
+        assert!(!kex_init.server);
+        assert_eq!(kex_init.host_key_algorithms, ["ssh-ed25519", "ssh-rsa"]);
+        assert_eq!(
+            kex_init.hassh,
+            HEXLOWER.encode(&Md5::digest(
+                b"curve25519-sha256,diffie-hellman-group1-sha1;aes128-ctr,3des-cbc;hmac-sha2-256;none,zlib@openssh.com"
+            ))
+        );
+        assert_eq!(
+            kex_init.weak,
+            [
+                "diffie-hellman-group1-sha1",
+                "ssh-rsa",
+                "3des-cbc",
+                "hmac-md5"
+            ]
+        );
+    }
+
+    #[test]
+    fn server_hassh() {
+        let data = packet(&[
+            "curve25519-sha256",
+            "ssh-ed25519",
+            "aes128-ctr",
+            "chacha20-poly1305@openssh.com",
+            "hmac-sha2-256",
+            "umac-128-etm@openssh.com",
+            "none",
+            "none,zlib@openssh.com",
+            "",
+            "",
+        ]);
+        let kex_init = extract(&header(22, 50000), &data)
+            .unwrap()
+            .kex_init
+            .unwrap();
+        assert!(kex_init.server);
+        assert_eq!(
+            kex_init.hassh,
+            HEXLOWER.encode(&Md5::digest(
+                b"curve25519-sha256;chacha20-poly1305@openssh.com;umac-128-etm@openssh.com;none,zlib@openssh.com"
+            ))
+        );
+        assert!(kex_init.weak.is_empty());
+
+        // the direction is unknown on other ports, only the banner is shown
+        let mut banner = b"SSH-2.0-OpenSSH_9.6p1\r\n".to_vec();
+        banner.extend(&data);
+        let ssh = extract(&header(2222, 50000), &banner).unwrap();
+        assert!(ssh.banner.is_some());
+        assert!(ssh.kex_init.is_none());
+        assert!(extract(&header(2222, 50000), &data).is_err());
+    }
+
+    #[test]
+    fn not_ssh() {
+        assert!(extract(&header(50000, 22), b"SSH-2.0-\r\n").is_err());
+        assert!(extract(&header(50000, 22), b"\x00\x00\x01\x00\x04\x15rest").is_err());
+        assert!(extract(&header(50000, 80), b"GET / HTTP/1.1\r\n\r\n").is_err());
+    }
+}
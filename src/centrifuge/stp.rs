@@ -0,0 +1,71 @@
+use crate::structs::CentrifugeError;
+use crate::structs::stp::{BPDU, BridgeId, ConfigBpdu};
+use nom::bytes::complete::{tag, take};
+use nom::number::complete::{be_u8, be_u16, be_u32};
+
+fn bridge_id(remaining: &[u8]) -> nom::IResult<&[u8], BridgeId> {
+    let (remaining, priority) = be_u16(remaining)?;
+    let (remaining, mac) = take(6_usize)(remaining)?;
+    let mut buf = [0; 6];
+    buf.copy_from_slice(mac);
+    Ok((remaining, BridgeId { priority, mac: buf }))
+}
+
+fn config_bpdu(version: u8, remaining: &[u8]) -> nom::IResult<&[u8], ConfigBpdu> {
+    let (remaining, flags) = be_u8(remaining)?;
+    let (remaining, root) = bridge_id(remaining)?;
+    let (remaining, root_path_cost) = be_u32(remaining)?;
+    let (remaining, bridge) = bridge_id(remaining)?;
+    let (remaining, port_id) = be_u16(remaining)?;
+    let (remaining, message_age) = be_u16(remaining)?;
+    let (remaining, max_age) = be_u16(remaining)?;
+    let (remaining, hello_time) = be_u16(remaining)?;
+    let (remaining, forward_delay) = be_u16(remaining)?;
+
+    Ok((
+        remaining,
+        ConfigBpdu {
+            version,
+            flags,
+            root,
+            root_path_cost,
+            bridge,
+            port_id,
+            message_age,
+            max_age,
+            hello_time,
+            forward_delay,
+        },
+    ))
+}
+
+fn bpdu(remaining: &[u8]) -> nom::IResult<&[u8], BPDU> {
+    let (remaining, _protocol) = tag(&b"\x00\x00"[..])(remaining)?;
+    let (remaining, version) = be_u8(remaining)?;
+    let (remaining, bpdu_type) = be_u8(remaining)?;
+
+    match bpdu_type {
+        0x00 => {
+            let (remaining, bpdu) = config_bpdu(version, remaining)?;
+            Ok((remaining, BPDU::Config(bpdu)))
+        }
+        0x80 => Ok((remaining, BPDU::TopologyChange)),
+        // RSTP and MSTP, the MSTI records that follow are ignored
+        0x02 => {
+            let (remaining, bpdu) = config_bpdu(version, remaining)?;
+            Ok((remaining, BPDU::RST(bpdu)))
+        }
+        _ => Err(nom::Err::Error(nom::error::Error::new(
+            remaining,
+            nom::error::ErrorKind::Switch,
+        ))),
+    }
+}
+
+pub fn parse(remaining: &[u8]) -> Result<BPDU, CentrifugeError> {
+    if let Ok((_remaining, bpdu)) = bpdu(remaining) {
+        Ok(bpdu)
+    } else {
+        Err(CentrifugeError::InvalidPacket)
+    }
+}
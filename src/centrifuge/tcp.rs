@@ -1,27 +1,113 @@
+use std::net::{IpAddr, SocketAddr};
 use std::str::from_utf8;
 
+use nom::bytes::complete::take;
+use nom::number::complete::{be_u8, be_u16, be_u32};
 use pktparse::tcp::{self, TcpHeader};
 
+use crate::centrifuge::bgp;
+use crate::centrifuge::ftp;
 use crate::centrifuge::http;
+use crate::centrifuge::irc;
+use crate::centrifuge::mail;
+use crate::centrifuge::mongodb;
+use crate::centrifuge::mysql;
+use crate::centrifuge::postgres;
+use crate::centrifuge::redis;
+use crate::centrifuge::ssh;
+use crate::centrifuge::telnet;
 use crate::centrifuge::tls;
 
 use crate::structs::CentrifugeError;
-use crate::structs::tcp::TCP;
+use crate::structs::raw::Raw;
+use crate::structs::tcp::{TCP, TcpOption};
+use crate::structs::{ether, ipv4, ipv6};
 
-pub fn parse(remaining: &[u8]) -> Result<(tcp::TcpHeader, TCP), CentrifugeError> {
-    if let Ok((remaining, tcp_hdr)) = tcp::parse_tcp_header(remaining) {
-        let inner = match extract(&tcp_hdr, remaining) {
+pub fn parse(remaining: &[u8]) -> Result<(tcp::TcpHeader, TCP, Vec<TcpOption>), CentrifugeError> {
+    if let Ok((payload, tcp_hdr)) = tcp::parse_tcp_header(remaining) {
+        let options = if tcp_hdr.flag_syn {
+            let header_len = usize::from(tcp_hdr.data_offset) * 4;
+            options(remaining.get(20..header_len).unwrap_or_default())
+        } else {
+            Vec::new()
+        };
+        let inner = match extract(&tcp_hdr, payload) {
             Ok(x) => x,
-            Err(_) => unknown(remaining),
+            Err(_) => unknown(payload),
         };
-        Ok((tcp_hdr, inner))
+        Ok((tcp_hdr, inner, options))
     } else {
         Err(CentrifugeError::InvalidPacket)
     }
 }
 
+pub fn options(mut remaining: &[u8]) -> Vec<TcpOption> {
+    let mut options = Vec::new();
+    while !remaining.is_empty() {
+        match option(remaining) {
+            Ok((rest, option)) => {
+                options.push(option);
+                remaining = rest;
+            }
+            Err(_) => {
+                options.push(TcpOption::Malformed(remaining.to_vec()));
+                break;
+            }
+        }
+    }
+    options
+}
+
+fn option(remaining: &[u8]) -> nom::IResult<&[u8], TcpOption> {
+    let (remaining, kind) = be_u8(remaining)?;
+    match kind {
+        0 => return Ok((&[], TcpOption::EndOfOptions(remaining.to_vec()))),
+        1 => return Ok((remaining, TcpOption::NoOperation)),
+        _ => (),
+    }
+
+    let (remaining, length) = be_u8(remaining)?;
+    let Some(length) = length.checked_sub(2) else {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            remaining,
+            nom::error::ErrorKind::LengthValue,
+        )));
+    };
+    let (remaining, data) = take(length)(remaining)?;
+
+    let option = match (kind, length) {
+        (2, 2) => TcpOption::MaximumSegmentSize(be_u16(data)?.1),
+        (3, 1) => TcpOption::WindowScale(data[0]),
+        (4, 0) => TcpOption::SackPermitted,
+        (5, _) if length % 8 == 0 => {
+            let mut blocks = Vec::new();
+            let mut data = data;
+            while !data.is_empty() {
+                let (rest, left) = be_u32(data)?;
+                let (rest, right) = be_u32(rest)?;
+                blocks.push((left, right));
+                data = rest;
+            }
+            TcpOption::Sack(blocks)
+        }
+        (8, 8) => {
+            let (data, value) = be_u32(data)?;
+            let (_, echo) = be_u32(data)?;
+            TcpOption::Timestamp(value, echo)
+        }
+        (2..=5 | 8, _) => {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                remaining,
+                nom::error::ErrorKind::LengthValue,
+            )));
+        }
+        _ => TcpOption::Unknown(kind, data.to_vec()),
+    };
+    Ok((remaining, option))
+}
+
 #[inline]
-pub fn extract(_tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<TCP, CentrifugeError> {
+pub fn extract(tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<TCP, CentrifugeError> {
     if remaining.is_empty() {
         Ok(TCP::Empty)
     } else if let Ok(client_hello) = tls::extract(remaining) {
@@ -30,11 +116,55 @@ pub fn extract(_tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<TCP, Centrifuge
         Ok(TCP::TLS(server_hello))
     } else if let Ok(http) = http::extract(remaining) {
         Ok(TCP::HTTP(http))
+    } else if let Ok(ssh) = ssh::extract(tcp_hdr, remaining) {
+        Ok(TCP::SSH(ssh))
+    } else if let Ok(ftp) = ftp::extract(tcp_hdr, remaining) {
+        Ok(TCP::FTP(ftp))
+    } else if let Ok(mail) = mail::extract(tcp_hdr, remaining) {
+        Ok(TCP::Mail(mail))
+    } else if let Ok(telnet) = telnet::extract(tcp_hdr, remaining) {
+        Ok(TCP::Telnet(telnet))
+    } else if let Ok(mysql) = mysql::extract(tcp_hdr, remaining) {
+        Ok(TCP::MySQL(mysql))
+    } else if let Ok(messages) = postgres::extract(tcp_hdr, remaining) {
+        Ok(TCP::Postgres(messages))
+    } else if let Ok(redis) = redis::extract(tcp_hdr, remaining) {
+        Ok(TCP::Redis(redis))
+    } else if let Ok(mongodb) = mongodb::extract(tcp_hdr, remaining) {
+        Ok(TCP::MongoDB(mongodb))
+    } else if let Ok(messages) = irc::extract(tcp_hdr, remaining) {
+        Ok(TCP::IRC(messages))
+    } else if let Ok(messages) = bgp::extract(tcp_hdr, remaining) {
+        Ok(TCP::BGP(messages))
     } else {
         Err(CentrifugeError::UnknownProtocol)
     }
 }
 
+/// The connection of a tcp packet and its payload, for the parsers that follow a session
+pub fn connection(packet: &mut Raw) -> Option<(SocketAddr, SocketAddr, &mut TCP)> {
+    let (src, dst, tcp_hdr, tcp) = match packet.ether_mut()? {
+        ether::Ether::IPv4(ip_hdr, ipv4::IPv4::TCP(tcp_hdr, tcp, _)) => (
+            IpAddr::V4(ip_hdr.source_addr),
+            IpAddr::V4(ip_hdr.dest_addr),
+            tcp_hdr,
+            tcp,
+        ),
+        ether::Ether::IPv6(ip_hdr, ipv6::IPv6::TCP(tcp_hdr, tcp, _)) => (
+            IpAddr::V6(ip_hdr.source_addr),
+            IpAddr::V6(ip_hdr.dest_addr),
+            tcp_hdr,
+            tcp,
+        ),
+        _ => return None,
+    };
+    Some((
+        SocketAddr::new(src, tcp_hdr.source_port),
+        SocketAddr::new(dst, tcp_hdr.dest_port),
+        tcp,
+    ))
+}
+
 #[inline]
 pub fn unknown(remaining: &[u8]) -> TCP {
     // if slice contains null bytes, don't try to decode
@@ -0,0 +1,291 @@
+use crate::bounded::BoundedMap;
+use crate::structs::CentrifugeError;
+use crate::structs::tcp::TCP;
+use crate::structs::telnet::{Command, Prompt, Telnet};
+use pktparse::tcp::TcpHeader;
+use std::net::SocketAddr;
+use std::time::SystemTime;
+
+pub const PORT: u16 = 23;
+
+const MAX_SESSIONS: usize = 1024;
+
+/// Interpret as command
+const IAC: u8 = 255;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+const TERMINAL_TYPE: u8 = 24;
+const WINDOW_SIZE: u8 = 31;
+const ENVIRON: u8 = 36;
+const NEW_ENVIRON: u8 = 39;
+
+/// Every segment on the telnet port, commands are removed from the session text
+pub fn extract(tcp_hdr: &TcpHeader, remaining: &[u8]) -> Result<Telnet, CentrifugeError> {
+    if tcp_hdr.source_port != PORT && tcp_hdr.dest_port != PORT {
+        return Err(CentrifugeError::WrongProtocol);
+    }
+
+    let mut commands = Vec::new();
+    let mut text = Vec::new();
+    let mut bytes = remaining.iter().copied();
+    while let Some(byte) = bytes.next() {
+        if byte != IAC {
+            // a carriage return is followed by a null byte if it's not part of a line break
+            if byte != 0 {
+                text.push(byte);
+            }
+            continue;
+        }
+        // a command may continue in the next segment
+        let Some(command) = bytes.next() else {
+            break;
+        };
+        match command {
+            IAC => text.push(IAC),
+            251..=254 => {
+                let Some(option) = bytes.next() else {
+                    break;
+                };
+                commands.push(match command {
+                    251 => Command::Will(option),
+                    252 => Command::Wont(option),
+                    253 => Command::Do(option),
+                    _ => Command::Dont(option),
+                });
+            }
+            SB => {
+                let mut data = Vec::new();
+                while let Some(byte) = bytes.next() {
+                    if byte == IAC {
+                        match bytes.next() {
+                            Some(SE) | None => break,
+                            Some(byte) => data.push(byte),
+                        }
+                    } else {
+                        data.push(byte);
+                    }
+                }
+                if let Some((&option, data)) = data.split_first() {
+                    commands.push(subnegotiation(option, data));
+                }
+            }
+            command => commands.push(Command::Other(command)),
+        }
+    }
+
+    let text = String::from_utf8_lossy(&text).into_owned();
+    let prompt = if tcp_hdr.source_port == PORT {
+        prompt(&text)
+    } else {
+        None
+    };
+    Ok(Telnet {
+        commands,
+        text,
+        prompt,
+        input: None,
+    })
+}
+
+fn subnegotiation(option: u8, data: &[u8]) -> Command {
+    match (option, data) {
+        // `IS`, the request of the server is `SEND`
+        (TERMINAL_TYPE, [0, name @ ..]) => {
+            Command::TerminalType(String::from_utf8_lossy(name).into_owned())
+        }
+        (WINDOW_SIZE, &[w1, w2, h1, h2]) => Command::WindowSize {
+            width: u16::from_be_bytes([w1, w2]),
+            height: u16::from_be_bytes([h1, h2]),
+        },
+        (ENVIRON | NEW_ENVIRON, [0 | 2, variables @ ..]) => {
+            Command::Environment(environment(variables))
+        }
+        _ => Command::Subnegotiation(option, data.to_vec()),
+    }
+}
+
+/// Variables start with `VAR` or `USERVAR` and their value with `VALUE`, rfc1572
+fn environment(data: &[u8]) -> Vec<(String, Option<String>)> {
+    let mut variables = Vec::<(Vec<u8>, Option<Vec<u8>>)>::new();
+    let mut bytes = data.iter().copied();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            0 | 3 => variables.push((Vec::new(), None)),
+            1 => {
+                if let Some(variable) = variables.last_mut() {
+                    variable.1 = Some(Vec::new());
+                }
+            }
+            byte => {
+                // `ESC`
+                let byte = if byte == 2 {
+                    let Some(byte) = bytes.next() else {
+                        break;
+                    };
+                    byte
+                } else {
+                    byte
+                };
+                match variables.last_mut() {
+                    Some((_, Some(value))) => value.push(byte),
+                    Some((name, None)) => name.push(byte),
+                    None => (),
+                }
+            }
+        }
+    }
+    variables
+        .into_iter()
+        .map(|(name, value)| {
+            (
+                String::from_utf8_lossy(&name).into_owned(),
+                value.map(|x| String::from_utf8_lossy(&x).into_owned()),
+            )
+        })
+        .collect()
+}
+
+fn prompt(text: &str) -> Option<Prompt> {
+    let text = text.trim_end().to_ascii_lowercase();
+    if text.ends_with("login:") || text.ends_with("username:") {
+        Some(Prompt::Login)
+    } else if text.ends_with("password:") {
+        Some(Prompt::Password)
+    } else {
+        None
+    }
+}
+
+/// Follows login prompts to mark the keystrokes that answer them
+#[derive(Default)]
+pub struct Sessions {
+    /// Keyed by client and server
+    sessions: BoundedMap<(SocketAddr, SocketAddr), Prompt, MAX_SESSIONS>,
+}
+
+impl Sessions {
+    pub fn track(
+        &mut self,
+        timestamp: SystemTime,
+        src: SocketAddr,
+        dst: SocketAddr,
+        tcp: &mut TCP,
+    ) {
+        let TCP::Telnet(telnet) = tcp else {
+            return;
+        };
+
+        if src.port() == PORT {
+            let Some(prompt) = telnet.prompt else {
+                return;
+            };
+            self.sessions.insert(timestamp, (dst, src), prompt);
+        } else if let Some(prompt) = self.sessions.get(&(src, dst)) {
+            if telnet.text.is_empty() {
+                return;
+            }
+            telnet.input = Some(*prompt);
+            // the answer is complete
+            if telnet.text.contains(['\r', '\n']) {
+                self.sessions.remove(&(src, dst));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(source_port: u16, dest_port: u16) -> TcpHeader {
+        TcpHeader {
+            source_port,
+            dest_port,
+            sequence_no: 0,
+            ack_no: 0,
+            data_offset: 5,
+            reserved: 0,
+            flag_urg: false,
+            flag_ack: true,
+            flag_psh: true,
+            flag_rst: false,
+            flag_syn: false,
+            flag_fin: false,
+            window: 0,
+            checksum: 0,
+            urgent_pointer: 0,
+            options: None,
+        }
+    }
+
+    #[test]
+    fn negotiation() {
+        let telnet = extract(
+            &header(49152, 23),
+            b"\xff\xfb\x18\xff\xfb\x1f\xff\xfa\x1f\x00\x50\x00\x18\xff\xf0\xff\xfa\x18\x00XTERM\xff\xf0\xff\xfa\x27\x00\x00USER\x01root\x03LANG\xff\xf0",
+        )
+        .unwrap();
+        assert_eq!(
+            telnet.commands,
+            [
+                Command::Will(24),
+                Command::Will(31),
+                Command::WindowSize {
+                    width: 80,
+                    height: 24
+                },
+                Command::TerminalType("XTERM".to_string()),
+                Command::Environment(vec![
+                    ("USER".to_string(), Some("root".to_string())),
+                    ("LANG".to_string(), None),
+                ]),
+            ]
+        );
+        assert_eq!(telnet.text, "");
+    }
+
+    #[test]
+    fn text_and_prompt() {
+        let telnet = extract(
+            &header(23, 49152),
+            b"\xff\xfd\x01Debian GNU/Linux 12\r\n\r\nrouter login: ",
+        )
+        .unwrap();
+        assert_eq!(telnet.commands, [Command::Do(1)]);
+        assert_eq!(telnet.text, "Debian GNU/Linux 12\r\n\r\nrouter login: ");
+        assert_eq!(telnet.prompt, Some(Prompt::Login));
+
+        assert!(extract(&header(49152, 2323), b"root\r\n").is_err());
+    }
+
+    #[test]
+    fn track_password() {
+        let client = "192.0.2.1:49152".parse().unwrap();
+        let server = "192.0.2.2:23".parse().unwrap();
+        let now = SystemTime::now();
+        let mut sessions = Sessions::default();
+
+        let mut prompt = TCP::Telnet(extract(&header(23, 49152), b"Password: ").unwrap());
+        sessions.track(now, server, client, &mut prompt);
+
+        let mut inputs = Vec::new();
+        for keystroke in [&b"s"[..], b"3", b"\r\0", b"ls\r\0"] {
+            let mut tcp = TCP::Telnet(extract(&header(49152, 23), keystroke).unwrap());
+            sessions.track(now, client, server, &mut tcp);
+            let TCP::Telnet(telnet) = tcp else {
+                unreachable!();
+            };
+            inputs.push(telnet.input);
+        }
+        assert_eq!(
+            inputs,
+            [
+                Some(Prompt::Password),
+                Some(Prompt::Password),
+                Some(Prompt::Password),
+                None
+            ]
+        );
+    }
+}
@@ -1,39 +1,332 @@
-use crate::structs::tls::{ClientHello, ServerHello, TLS};
+use crate::centrifuge::{ja, x509};
+use crate::structs::tls::{Alert, ClientHello, ServerHello, TLS};
 use crate::structs::{CentrifugeError, tls};
+use data_encoding::BASE64;
+use nom::bytes::complete::take;
+use nom::number::complete::be_u16;
 use std::str;
-use tls_parser::{TlsExtension, TlsMessage, TlsMessageHandshake, parse_tls_extension};
+use tls_parser::{
+    TlsClientHelloContents, TlsExtension, TlsExtensionType, TlsMessage, TlsMessageHandshake,
+    TlsRecordType, TlsServerHelloContents, parse_tls_client_hello_extension,
+    parse_tls_extension_unknown, parse_tls_server_hello_extension,
+};
+
+const TLS13: u16 = 0x0304;
+const EXT_PRE_SHARED_KEY: u16 = 0x0029;
+const EXT_ENCRYPTED_CLIENT_HELLO: u16 = 0xfe0d;
+
+/// A server hello with this random is a hello retry request (rfc8446)
+const HELLO_RETRY_REQUEST: [u8; 32] = [
+    0xcf, 0x21, 0xad, 0x74, 0xe5, 0x9a, 0x61, 0x11, 0xbe, 0x1d, 0x8c, 0x02, 0x1e, 0x65, 0xb8, 0x91,
+    0xc2, 0xa2, 0x11, 0x16, 0x7a, 0xbb, 0x8c, 0x5e, 0x07, 0x9e, 0x09, 0xe2, 0xc8, 0xa8, 0x33, 0x9c,
+];
+
+pub fn extract(mut remaining: &[u8]) -> Result<tls::TLS, CentrifugeError> {
+    let mut server_hello = None;
+    let mut certificates = None;
+    let mut change_cipher_spec = false;
+
+    // a segment can contain multiple records, like the whole handshake of a tls1.2 server
+    let mut parsed = false;
+    while let Ok((rest, tls)) = tls_parser::parse_tls_plaintext(remaining) {
+        parsed = true;
+        remaining = rest;
+
+        if tls.hdr.record_type == TlsRecordType::Alert && tls.hdr.len != 2 {
+            return Ok(TLS::Alert(Alert {
+                level: None,
+                description: None,
+            }));
+        }
 
-pub fn extract(remaining: &[u8]) -> Result<tls::TLS, CentrifugeError> {
-    if let Ok((_remaining, tls)) = tls_parser::parse_tls_plaintext(remaining) {
         for msg in tls.msg {
             match msg {
-                TlsMessage::Handshake(TlsMessageHandshake::ClientHello(ch)) => {
-                    let mut hostname = None;
-
-                    if let Some(mut remaining) = ch.ext {
-                        while let Ok((remaining2, ext)) = parse_tls_extension(remaining) {
-                            remaining = remaining2;
-                            if let TlsExtension::SNI(sni) = ext {
-                                for s in sni {
-                                    let name = str::from_utf8(s.1)
-                                        .map_err(|_| CentrifugeError::ParsingError)?;
-                                    hostname = Some(name.to_owned());
-                                }
-                            }
-                        }
-
-                        return Ok(TLS::ClientHello(ClientHello::new(&ch, hostname)));
-                    }
+                TlsMessage::Handshake(TlsMessageHandshake::ClientHello(ch)) if ch.ext.is_some() => {
+                    return Ok(TLS::ClientHello(client_hello(&ch, false)?));
                 }
                 TlsMessage::Handshake(TlsMessageHandshake::ServerHello(sh)) => {
-                    return Ok(TLS::ServerHello(ServerHello::new(&sh)));
+                    server_hello = Some(self::server_hello(&sh, false));
+                }
+                TlsMessage::Handshake(TlsMessageHandshake::Certificate(chain)) => {
+                    certificates = Some(
+                        chain
+                            .cert_chain
+                            .iter()
+                            .filter_map(|cert| x509::certificate(cert.data).ok())
+                            .collect(),
+                    );
+                }
+                TlsMessage::Alert(alert) => {
+                    return Ok(TLS::Alert(Alert {
+                        level: tls::alert_level(alert.severity.0),
+                        description: Some(tls::alert_description(alert.code.0)),
+                    }));
                 }
+                TlsMessage::ChangeCipherSpec => change_cipher_spec = true,
                 _ => (),
             }
         }
 
-        Err(CentrifugeError::ParsingError)
+        // the following records are encrypted and would only be parsed into garbage
+        if change_cipher_spec {
+            break;
+        }
+    }
+
+    match (server_hello, certificates) {
+        (Some(mut server_hello), certificates) => {
+            server_hello.certificates = certificates.unwrap_or_default();
+            // tls1.3 servers also send one for middlebox compatibility
+            if change_cipher_spec
+                && server_hello.resumed.is_none()
+                && server_hello.version != tls::version_name(TLS13)
+            {
+                server_hello.resumed = Some("session");
+            }
+            Ok(TLS::ServerHello(server_hello))
+        }
+        (None, Some(certificates)) => Ok(TLS::Certificate(certificates)),
+        (None, None) if change_cipher_spec => Ok(TLS::ChangeCipherSpec),
+        (None, None) if parsed => Err(CentrifugeError::ParsingError),
+        (None, None) => Err(CentrifugeError::WrongProtocol),
+    }
+}
+
+/// Parse all extensions, keeping the ones we fail to decode as unknown so fingerprints stay complete
+fn extensions<'a, F, E>(mut remaining: &'a [u8], parse: F) -> Vec<TlsExtension<'a>>
+where
+    F: Fn(&'a [u8]) -> Result<(&'a [u8], TlsExtension<'a>), E>,
+{
+    let mut extensions = Vec::new();
+    while let Some((rest, ext)) = parse(remaining)
+        .ok()
+        .or_else(|| parse_tls_extension_unknown(remaining).ok())
+    {
+        extensions.push(ext);
+        remaining = rest;
+    }
+    extensions
+}
+
+pub fn client_hello(
+    ch: &TlsClientHelloContents,
+    quic: bool,
+) -> Result<ClientHello, CentrifugeError> {
+    let extensions = extensions(ch.ext.unwrap_or_default(), parse_tls_client_hello_extension);
+    let ciphers = ch.ciphers.iter().map(|x| x.0).collect::<Vec<_>>();
+
+    let mut hello = ClientHello {
+        version: None,
+        session_id: ch.session_id.map(|id| BASE64.encode(id)),
+        hostname: None,
+        alpn: Vec::new(),
+        supported_versions: Vec::new(),
+        groups: Vec::new(),
+        key_shares: Vec::new(),
+        signature_algorithms: Vec::new(),
+        ech: false,
+        grease: ciphers.iter().any(|x| ja::is_grease(*x)),
+        resumption: None,
+        ja3: ja::ja3(ch.version, &ciphers, &extensions),
+        ja4: ja::ja4(quic, ch.version, &ciphers, &extensions),
+    };
+    let mut version = ch.version.0;
+    let mut ticket = false;
+
+    for ext in &extensions {
+        match ext {
+            TlsExtension::SNI(sni) => {
+                for s in sni {
+                    let name = str::from_utf8(s.1).map_err(|_| CentrifugeError::ParsingError)?;
+                    hello.hostname = Some(name.to_owned());
+                }
+            }
+            TlsExtension::ALPN(protocols) => {
+                hello.alpn = protocols
+                    .iter()
+                    .map(|x| String::from_utf8_lossy(x).into_owned())
+                    .collect();
+            }
+            TlsExtension::SupportedVersions(versions) => {
+                let versions = versions.iter().map(|x| x.0);
+                if let Some(max) = versions.clone().filter(|x| !ja::is_grease(*x)).max() {
+                    version = max;
+                }
+                hello.supported_versions = names(versions, &mut hello.grease, tls::version_name);
+            }
+            TlsExtension::EllipticCurves(groups) => {
+                let groups = groups.iter().map(|x| x.0);
+                hello.groups = names(groups, &mut hello.grease, |x| Some(tls::group_name(x)));
+            }
+            TlsExtension::KeyShare(data) => {
+                let groups = key_share_groups(data).map(|(_, x)| x).unwrap_or_default();
+                hello.key_shares = names(groups.into_iter(), &mut hello.grease, |x| {
+                    Some(tls::group_name(x))
+                });
+            }
+            TlsExtension::SignatureAlgorithms(schemes) => {
+                hello.signature_algorithms =
+                    names(schemes.iter().copied(), &mut hello.grease, |x| {
+                        Some(tls::signature_scheme_name(x))
+                    });
+            }
+            TlsExtension::SessionTicket(data) => ticket = !data.is_empty(),
+            TlsExtension::Grease(..) => hello.grease = true,
+            TlsExtension::Unknown(ext_type, _) if ext_type.0 == EXT_ENCRYPTED_CLIENT_HELLO => {
+                hello.ech = true
+            }
+            _ => (),
+        }
+    }
+    hello.version = tls::version_name(version);
+
+    hello.resumption = if has_extension(&extensions, EXT_PRE_SHARED_KEY) {
+        Some("psk")
+    } else if ticket {
+        Some("ticket")
+    } else if version < TLS13 && ch.session_id.is_some_and(|id| !id.is_empty()) {
+        // tls1.3 clients send a random session id for middlebox compatibility
+        Some("session_id")
     } else {
-        Err(CentrifugeError::WrongProtocol)
+        None
+    };
+
+    Ok(hello)
+}
+
+pub fn server_hello(sh: &TlsServerHelloContents, quic: bool) -> ServerHello {
+    let extensions = extensions(sh.ext.unwrap_or_default(), parse_tls_server_hello_extension);
+
+    let mut version = sh.version.0;
+    let mut alpn = None;
+    let mut key_share = None;
+    for ext in &extensions {
+        match ext {
+            TlsExtension::SupportedVersions(versions) => {
+                if let Some(selected) = versions.first() {
+                    version = selected.0;
+                }
+            }
+            TlsExtension::ALPN(protocols) => {
+                alpn = protocols
+                    .first()
+                    .map(|x| String::from_utf8_lossy(x).into_owned());
+            }
+            // the selected key share starts with its group
+            TlsExtension::KeyShare(data) => {
+                key_share = be_u16::<_, nom::error::Error<_>>(*data)
+                    .ok()
+                    .map(|(_, group)| tls::group_name(group));
+            }
+            _ => (),
+        }
+    }
+
+    ServerHello {
+        version: tls::version_name(version),
+        session_id: sh.session_id.map(|id| BASE64.encode(id)),
+        cipher: sh.cipher.get_ciphersuite().map(|cs| cs.name),
+        alpn,
+        key_share,
+        hello_retry: sh.random == HELLO_RETRY_REQUEST,
+        resumed: has_extension(&extensions, EXT_PRE_SHARED_KEY).then_some("psk"),
+        ja3s: ja::ja3s(sh.version, sh.cipher.0, &extensions),
+        ja4s: ja::ja4s(quic, sh.version, sh.cipher.0, &extensions),
+        certificates: Vec::new(),
+    }
+}
+
+#[inline]
+fn has_extension(extensions: &[TlsExtension], ext_type: u16) -> bool {
+    extensions
+        .iter()
+        .any(|ext| TlsExtensionType::from(ext).0 == ext_type)
+}
+
+/// Name the values of a list, grease values are only recorded as being present
+fn names<T>(
+    values: impl Iterator<Item = u16>,
+    grease: &mut bool,
+    name: impl Fn(u16) -> Option<T>,
+) -> Vec<T> {
+    let mut names = Vec::new();
+    for value in values {
+        if ja::is_grease(value) {
+            *grease = true;
+        } else if let Some(name) = name(value) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// The groups of the key shares offered by a client
+fn key_share_groups(remaining: &[u8]) -> nom::IResult<&[u8], Vec<u16>> {
+    let (remaining, length) = be_u16(remaining)?;
+    let (remaining, mut entries) = take(length)(remaining)?;
+
+    let mut groups = Vec::new();
+    while !entries.is_empty() {
+        let (rest, group) = be_u16(entries)?;
+        let (rest, length) = be_u16(rest)?;
+        let (rest, _key) = take(length)(rest)?;
+        groups.push(group);
+        entries = rest;
+    }
+    Ok((remaining, groups))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_shares() {
+        let data = [
+            0x00, 0x0a, // list length
+            0x3a, 0x3a, 0x00, 0x01, 0x00, // grease
+            0x11, 0xec, 0x00, 0x01, 0xff, // X25519MLKEM768
+        ];
+        let (_, groups) = key_share_groups(&data).unwrap();
+        assert_eq!(groups, vec![0x3a3a, 0x11ec]);
+
+        let mut grease = false;
+        let names = names(groups.into_iter(), &mut grease, |x| {
+            Some(tls::group_name(x))
+        });
+        assert_eq!(names, vec!["X25519MLKEM768"]);
+        assert!(grease);
+    }
+
+    #[test]
+    fn alerts() {
+        let tls = extract(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28]).unwrap();
+        assert_eq!(
+            tls,
+            TLS::Alert(Alert {
+                level: Some("fatal"),
+                description: Some("handshake_failure".to_string()),
+            })
+        );
+
+        let tls = extract(&[0x15, 0x03, 0x03, 0x00, 0x04, 0x8b, 0x1f, 0x03, 0xa2]).unwrap();
+        assert_eq!(
+            tls,
+            TLS::Alert(Alert {
+                level: None,
+                description: None,
+            })
+        );
+    }
+
+    #[test]
+    fn change_cipher_spec() {
+        // followed by an encrypted finished message
+        let tls = extract(&[
+            0x14, 0x03, 0x03, 0x00, 0x01, 0x01, 0x16, 0x03, 0x03, 0x00, 0x04, 0x01, 0x00, 0x00,
+            0x00,
+        ])
+        .unwrap();
+        assert_eq!(tls, TLS::ChangeCipherSpec);
     }
 }
@@ -5,6 +5,8 @@ use pktparse::udp::{self, UdpHeader};
 use crate::centrifuge::dhcp;
 use crate::centrifuge::dns;
 use crate::centrifuge::dropbox;
+use crate::centrifuge::nbns;
+use crate::centrifuge::quic;
 use crate::centrifuge::ssdp;
 
 use crate::structs::CentrifugeError;
@@ -29,6 +31,13 @@ pub fn extract(udp_hdr: UdpHeader, remaining: &[u8]) -> Result<UDP, CentrifugeEr
     } else if udp_hdr.dest_port == 53 || udp_hdr.source_port == 53 {
         let dns = dns::extract(remaining)?;
         Ok(UDP::DNS(dns))
+    } else if udp_hdr.dest_port == 5353 && udp_hdr.source_port == 5353 {
+        // multicast dns
+        let dns = dns::extract(remaining)?;
+        Ok(UDP::DNS(dns))
+    } else if udp_hdr.dest_port == 137 || udp_hdr.source_port == 137 {
+        let nbns = nbns::extract(remaining)?;
+        Ok(UDP::NBNS(nbns))
     } else if (udp_hdr.dest_port == 67 && udp_hdr.source_port == 68)
         || (udp_hdr.dest_port == 68 && udp_hdr.source_port == 67)
     {
@@ -37,6 +46,8 @@ pub fn extract(udp_hdr: UdpHeader, remaining: &[u8]) -> Result<UDP, CentrifugeEr
     } else if udp_hdr.source_port == 17500 && udp_hdr.dest_port == 17500 {
         let dropbox = dropbox::extract(remaining)?;
         Ok(UDP::Dropbox(dropbox))
+    } else if let Ok(quic) = quic::extract(remaining) {
+        Ok(UDP::QUIC(quic))
     } else {
         Err(CentrifugeError::UnknownProtocol)
     }
@@ -0,0 +1,135 @@
+use crate::structs::CentrifugeError;
+use crate::structs::tls::Certificate;
+use data_encoding::{BASE64, HEXLOWER};
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use x509_parser::certificate::X509Certificate;
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::FromDer;
+use x509_parser::public_key::PublicKey;
+use x509_parser::time::ASN1Time;
+use x509_parser::x509::SubjectPublicKeyInfo;
+
+const SELF_SIGNED: &[u8] =
+    b"MIIBfzCCATGgAwIBAgIBATAFBgMrZXAwLTEZMBcGA1UEAwwQaW50cmFuZXQuZXhhbXBsZTEQMA4GA1UECgwHRXhh\
+    bXBsZTAeFw0yMDAxMDEwMDAwMDBaFw0yMDAxMDIwMDAwMDBaMC0xGTAXBgNVBAMMEGludHJhbmV0LmV4YW1wbGUx\
+    EDAOBgNVBAoMB0V4YW1wbGUwKjAFBgMrZXADIQD/IVNaYxmApOZqpDhmZSeCs+UiefjIBfoedoorPpKAHaN2MHQw\
+    HQYDVR0OBBYEFNdWbm3J6lxQ14XskCRUNVAtGLUCMB8GA1UdIwQYMBaAFNdWbm3J6lxQ14XskCRUNVAtGLUCMA8G\
+    A1UdEwEB/wQFMAMBAf8wIQYDVR0RBBowGIIQaW50cmFuZXQuZXhhbXBsZYcECgAABTAFBgMrZXADQQBKALFP1lz+\
+    wny4jj3XMxHoL9JAyihch+AfYcHtXxhMly3+o3VXbUa6BHHzds52DVqjodFf3ViWNnYmSloma5UK";
+
+/// x509-parser fills its oid tables on first use, they are hashmaps that need getrandom for their
+/// keys, parse a certificate before the sandbox forbids it
+pub fn init() {
+    if let Ok(der) = BASE64.decode(SELF_SIGNED) {
+        let _ = certificate(&der);
+    }
+}
+
+pub fn certificate(der: &[u8]) -> Result<Certificate, CentrifugeError> {
+    let (_, cert) = X509Certificate::from_der(der).map_err(|_| CentrifugeError::ParsingError)?;
+
+    let alt_names = match cert.subject_alternative_name() {
+        Ok(Some(san)) => san
+            .value
+            .general_names
+            .iter()
+            .filter_map(general_name)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let validity = cert.validity();
+    Ok(Certificate {
+        subject: cert.subject().to_string(),
+        issuer: cert.issuer().to_string(),
+        alt_names,
+        not_before: time(&validity.not_before),
+        not_after: time(&validity.not_after),
+        expired: false,
+        expires: validity.not_after.timestamp(),
+        key: key(cert.public_key()),
+        // without verifying the signature this could also be a ca that issued itself
+        self_signed: cert.subject().as_raw() == cert.issuer().as_raw(),
+        sha256: HEXLOWER.encode(&Sha256::digest(der)),
+        der: der.to_vec(),
+    })
+}
+
+fn general_name(name: &GeneralName) -> Option<String> {
+    match name {
+        GeneralName::DNSName(name) | GeneralName::RFC822Name(name) | GeneralName::URI(name) => {
+            Some(name.to_string())
+        }
+        GeneralName::IPAddress(addr) => {
+            let addr = match *addr {
+                [a, b, c, d] => IpAddr::V4(Ipv4Addr::new(*a, *b, *c, *d)),
+                addr => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(addr).ok()?)),
+            };
+            Some(addr.to_string())
+        }
+        _ => None,
+    }
+}
+
+fn time(time: &ASN1Time) -> String {
+    let time = time.to_datetime();
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        time.year(),
+        u8::from(time.month()),
+        time.day(),
+        time.hour(),
+        time.minute(),
+        time.second()
+    )
+}
+
+fn key(spki: &SubjectPublicKeyInfo) -> String {
+    match spki.parsed() {
+        Ok(PublicKey::RSA(rsa)) => {
+            let modulus = rsa.modulus;
+            let zeros = modulus.iter().take_while(|x| **x == 0).count();
+            let bits = match modulus.get(zeros) {
+                Some(first) => (modulus.len() - zeros) * 8 - first.leading_zeros() as usize,
+                None => 0,
+            };
+            format!("rsa{}", bits)
+        }
+        Ok(PublicKey::EC(ec)) => format!("ec{}", ec.key_size()),
+        Ok(PublicKey::DSA(y)) => format!("dsa{}", y.len() * 8),
+        _ => match spki.algorithm.algorithm.to_id_string().as_str() {
+            "1.3.101.112" => "ed25519".to_string(),
+            "1.3.101.113" => "ed448".to_string(),
+            oid => oid.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_signed() {
+        let der = BASE64.decode(SELF_SIGNED).unwrap();
+        let cert = certificate(&der).unwrap();
+        assert_eq!(
+            cert,
+            Certificate {
+                subject: "CN=intranet.example, O=Example".to_string(),
+                issuer: "CN=intranet.example, O=Example".to_string(),
+                alt_names: vec!["intranet.example".to_string(), "10.0.0.5".to_string()],
+                not_before: "2020-01-01 00:00:00".to_string(),
+                not_after: "2020-01-02 00:00:00".to_string(),
+                expired: false,
+                expires: 1577923200,
+                key: "ed25519".to_string(),
+                self_signed: true,
+                sha256: "0464abda599f855604550dab12bff56c571d9dc25af3e7c1aa51f0bbb5e7d28b"
+                    .to_string(),
+                der: der.clone(),
+            }
+        );
+    }
+}
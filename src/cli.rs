@@ -1,5 +1,6 @@
 use clap::ArgAction;
 use clap_complete::Shell;
+use std::path::PathBuf;
 
 #[derive(Debug, clap::Parser)]
 #[allow(clippy::struct_excessive_bools)]
@@ -21,6 +22,55 @@ pub struct Args {
 The default only shows few packets, this flag can be specified multiple times. (maximum: 4)"
     )]
     pub verbose: u8,
+    /// Track ip to mac mappings and alert on arp spoofing and ip conflicts
+    #[arg(long = "arp-watch")]
+    pub arp_watch: bool,
+    /// Learn the dhcp servers and ipv6 routers of the network and alert on rogue ones
+    #[arg(long = "rogue-watch")]
+    pub rogue_watch: bool,
+    /// Track connections and print a record when they end instead of individual packets
+    #[arg(long = "flows")]
+    pub flows: bool,
+    /// Pair http requests with their responses and print them like an access log
+    #[arg(long = "http-log")]
+    pub http_log: bool,
+    /// Match dns responses with their queries, print response times and alert on spoofed answers
+    #[arg(long = "dns-log")]
+    pub dns_log: bool,
+    /// Aggregate what we learn about the hosts of the local network and print a report
+    /// periodically and at exit
+    #[arg(long = "inventory")]
+    pub inventory: bool,
+    /// Print usernames, passwords and tokens sent in cleartext over http, ftp, pop3,
+    /// imap, smtp, telnet, irc, postgres and redis, each finding is only printed once
+    #[arg(long = "credentials")]
+    pub credentials: bool,
+    /// Annotate ip addresses with the hostname they were resolved from, based on observed
+    /// dns answers, tls sni and http host headers
+    #[arg(long = "passive-dns")]
+    pub passive_dns: bool,
+    /// Load additional tcp signatures for os fingerprinting, in the format of p0f.fp
+    #[arg(long = "os-signatures")]
+    pub os_signatures: Option<PathBuf>,
+    /// Show client names for ja3/ja4 fingerprints, each line of the file contains a
+    /// fingerprint followed by a name
+    #[arg(long = "tls-fingerprints")]
+    pub tls_fingerprints: Option<PathBuf>,
+    /// Save the tls certificates of servers into this directory, it needs to exist and
+    /// be writable by the sandbox user
+    #[arg(long = "dump-certificates")]
+    pub dump_certificates: Option<PathBuf>,
+    /// Save the files transferred over cleartext http into this directory, each with a
+    /// json file describing where it came from
+    #[arg(long = "dump-http-objects")]
+    pub dump_http_objects: Option<PathBuf>,
+    /// Mask passwords, tokens, cookies, tls session ids and dhcp hostnames in the output,
+    /// equal values are replaced with the same pseudonym
+    #[arg(long = "redact")]
+    pub redact: bool,
+    /// Also replace mac and ip addresses with pseudonyms, implies --redact
+    #[arg(long = "redact-addresses")]
+    pub redact_addresses: bool,
     /// Open a pcap file instead of a device
     #[arg(short = 'r', long = "read")]
     pub read: bool,
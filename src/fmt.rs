@@ -5,23 +5,52 @@ use bstr::ByteSlice;
 use pktparse::icmp::{IcmpCode, IcmpData, IcmpHeader};
 use sha2::{Digest, Sha512};
 use std::cmp;
-use std::fmt::Debug;
-
+use std::fmt::{Debug, Display};
+use std::net::{IpAddr, SocketAddr};
+use std::time::SystemTime;
+
+use crate::analysis::fingerprints::Fingerprints;
+use crate::analysis::names::NameCache;
+use crate::analysis::osfp;
+use crate::analysis::{self, Alert, Event, flows};
+use crate::redact::Redact;
 use crate::structs::NoiseLevel;
 use crate::structs::arp;
+use crate::structs::bgp;
 use crate::structs::cjdns;
+use crate::structs::dns;
+use crate::structs::eapol;
 use crate::structs::ether::Ether;
+use crate::structs::ftp;
 use crate::structs::http;
 use crate::structs::icmp;
+use crate::structs::icmpv6;
 use crate::structs::ip::IPHeader;
 use crate::structs::ipv4;
 use crate::structs::ipv6;
+use crate::structs::irc;
+use crate::structs::lacp;
+use crate::structs::llc;
+use crate::structs::mail;
+use crate::structs::mongodb;
+use crate::structs::mysql;
+use crate::structs::nbns;
+use crate::structs::postgres;
+use crate::structs::quic;
 use crate::structs::raw::Raw;
+use crate::structs::redis;
+use crate::structs::ssh;
+use crate::structs::stp;
 use crate::structs::tcp;
+use crate::structs::telnet;
 use crate::structs::tls;
 use crate::structs::udp;
 
 const GREY: Color = Color::Fixed(245);
+/// Queries and values of database commands are cut off after this many characters
+const MAX_QUERY_LENGTH: usize = 100;
+/// Redis commands and replies are cut off after this many arguments or elements
+const MAX_REDIS_VALUES: usize = 8;
 
 pub struct Config {
     fmt: Format,
@@ -29,9 +58,17 @@ pub struct Config {
 }
 
 impl Config {
-    pub fn new(layout: Layout, verbosity: u8, colors: bool) -> Config {
+    pub fn new(
+        layout: Layout,
+        verbosity: u8,
+        colors: bool,
+        names: bool,
+        os: Arc<osfp::Database>,
+        fingerprints: Fingerprints,
+        redact: Option<Redact>,
+    ) -> Config {
         Config {
-            fmt: Format::new(layout, colors),
+            fmt: Format::new(layout, colors, names, os, fingerprints, redact),
             filter: Arc::new(Filter::new(verbosity)),
         }
     }
@@ -54,15 +91,43 @@ pub enum Layout {
 pub struct Format {
     layout: Layout,
     colors: bool,
+    names: Option<NameCache>,
+    os: Arc<osfp::Database>,
+    fingerprints: Fingerprints,
+    redact: Option<Redact>,
 }
 
 impl Format {
-    pub fn new(layout: Layout, colors: bool) -> Format {
-        Format { layout, colors }
+    pub fn new(
+        layout: Layout,
+        colors: bool,
+        names: bool,
+        os: Arc<osfp::Database>,
+        fingerprints: Fingerprints,
+        redact: Option<Redact>,
+    ) -> Format {
+        Format {
+            layout,
+            colors,
+            names: names.then(NameCache::default),
+            os,
+            fingerprints,
+            redact,
+        }
+    }
+
+    #[inline]
+    pub fn learn(&mut self, timestamp: SystemTime, packet: &Raw) {
+        if let Some(names) = &mut self.names {
+            names.learn(timestamp, packet);
+        }
     }
 
     #[inline]
-    pub fn print(&self, packet: Raw) {
+    pub fn print(&self, mut packet: Raw) {
+        if let Some(redact) = &self.redact {
+            redact.packet(&mut packet);
+        }
         match self.layout {
             Layout::Compact => self.print_compact(packet),
             Layout::Debugging => self.print_debugging(packet),
@@ -70,6 +135,73 @@ impl Format {
         }
     }
 
+    #[inline]
+    pub fn print_event(&self, mut event: Event) {
+        if let Some(redact) = &self.redact {
+            redact.event(&mut event);
+        }
+        match self.layout {
+            Layout::Compact => {
+                let out = match event {
+                    Event::Alert(ref alert) => {
+                        self.highlight(Color::Red, format_compact_alert(alert))
+                    }
+                    Event::Flow(ref record) => {
+                        self.colorify(Color::Cyan, format_compact_flow(record))
+                    }
+                    Event::FlowSummary(ref summary) => {
+                        self.highlight(Color::Cyan, format_compact_flow_summary(summary))
+                    }
+                    Event::Http(ref transaction) => {
+                        self.colorify(Color::Red, format_compact_http_transaction(transaction))
+                    }
+                    Event::Dns(ref transaction) => {
+                        self.colorify(Color::Yellow, format_compact_dns_transaction(transaction))
+                    }
+                    Event::Inventory(ref report) => format_compact_inventory(report),
+                    Event::Credential(ref credential) => {
+                        self.highlight(Color::Red, format_compact_credential(credential))
+                    }
+                };
+                println!("{}", out);
+            }
+            Layout::Debugging => {
+                let out = match event {
+                    Event::Alert(ref alert) => {
+                        self.highlight(Color::Red, format!("alert: {:?}", alert))
+                    }
+                    Event::Flow(ref record) => {
+                        self.colorify(Color::Cyan, format!("flow: {:?}", record))
+                    }
+                    Event::FlowSummary(ref summary) => {
+                        self.highlight(Color::Cyan, format!("flow summary: {:?}", summary))
+                    }
+                    Event::Http(ref transaction) => {
+                        self.colorify(Color::Red, format!("http: {:?}", transaction))
+                    }
+                    Event::Dns(ref transaction) => {
+                        self.colorify(Color::Yellow, format!("dns: {:?}", transaction))
+                    }
+                    Event::Inventory(ref report) => format!("inventory: {:?}", report),
+                    Event::Credential(ref credential) => {
+                        self.highlight(Color::Red, format!("credential: {:?}", credential))
+                    }
+                };
+                println!("{}", out);
+            }
+            Layout::Json => println!("{}", serde_json::to_string(&event).unwrap()),
+        }
+    }
+
+    #[inline]
+    fn highlight(&self, color: Color, out: String) -> String {
+        if self.colors {
+            color.bold().paint(out).to_string()
+        } else {
+            out
+        }
+    }
+
     #[inline]
     fn colorify(&self, color: Color, out: String) -> String {
         if self.colors {
@@ -79,6 +211,16 @@ impl Format {
         }
     }
 
+    /// Append the name this address was resolved from, if we've seen it
+    #[inline]
+    fn display_socket<A: Display>(&self, addr: A, ip: IpAddr, port: u16) -> String {
+        match self.names.as_ref().and_then(|names| names.lookup(&ip)) {
+            // the name comes from the network and may contain terminal escape sequences
+            Some(name) => format!("{}:{} ({})", addr, port, escape_controls(name)),
+            None => format!("{}:{}", addr, port),
+        }
+    }
+
     #[inline]
     fn print_compact(&self, packet: Raw) {
         let mut out = String::new();
@@ -115,13 +257,21 @@ impl Format {
 
     #[inline]
     fn format_compact_eth(&self, out: &mut String, eth: Ether) -> Option<Color> {
-        match eth {
+        let os = self.os.guess(&eth);
+        let color = match eth {
             Ether::Arp(arp_pkt) => Some(self.format_compact_arp(out, &arp_pkt)),
             Ether::IPv4(ip_hdr, ipv4) => self.format_compact_ipv4(out, &ip_hdr, ipv4),
             Ether::IPv6(ip_hdr, ipv6) => self.format_compact_ipv6(out, &ip_hdr, ipv6),
             Ether::Cjdns(cjdns_pkt) => Some(self.format_compact_cjdns(out, &cjdns_pkt)),
+            Ether::Llc(llc_pkt) => self.format_compact_llc(out, &llc_pkt),
+            Ether::Lacp(lacp_pkt) => Some(self.format_compact_lacp(out, &lacp_pkt)),
+            Ether::Eapol(eapol_pkt) => Some(self.format_compact_eapol(out, &eapol_pkt)),
             Ether::Unknown(data) => self.format_compact_unknown_data(out, &data),
+        };
+        if let Some(os) = os {
+            out.push_str(&format!("(os: {})", os));
         }
+        color
     }
 
     #[inline]
@@ -186,6 +336,137 @@ impl Format {
         Color::Purple
     }
 
+    #[inline]
+    fn format_compact_llc(&self, out: &mut String, llc_pkt: &llc::LLC) -> Option<Color> {
+        use crate::structs::llc::LLC;
+        match llc_pkt {
+            LLC::STP(bpdu) => Some(self.format_compact_stp(out, bpdu)),
+            LLC::Snap(snap) => {
+                out.push_str(&format!(
+                    "[llc/snap] oui={:02x}:{:02x}:{:02x}, protocol=0x{:04x} {:?}",
+                    snap.oui[0],
+                    snap.oui[1],
+                    snap.oui[2],
+                    snap.protocol,
+                    snap.data.as_bstr()
+                ));
+                None
+            }
+            LLC::Unknown(header, data) => {
+                out.push_str(&format!(
+                    "[llc] dsap=0x{:02x}, ssap=0x{:02x} {:?}",
+                    header.dsap,
+                    header.ssap,
+                    data.as_bstr()
+                ));
+                None
+            }
+        }
+    }
+
+    #[inline]
+    fn format_compact_stp(&self, out: &mut String, bpdu: &stp::BPDU) -> Color {
+        use crate::structs::stp::BPDU;
+        let (kind, bpdu, rst) = match bpdu {
+            BPDU::Config(bpdu) => ("stp/config", bpdu, false),
+            BPDU::RST(bpdu) => ("stp/rst   ", bpdu, true),
+            BPDU::TopologyChange => {
+                out.push_str("[stp/tcn   ] topology change notification");
+                return Color::Yellow;
+            }
+        };
+
+        out.push_str(&format!(
+            "[{}] root {}, cost {}, bridge {}, port 0x{:04x}",
+            kind,
+            display_bridge_id(&bpdu.root),
+            bpdu.root_path_cost,
+            display_bridge_id(&bpdu.bridge),
+            bpdu.port_id,
+        ));
+
+        let mut extra = Vec::new();
+        if let Some(role) = bpdu.port_role().filter(|_| rst) {
+            extra.push(format!("role: {}", role));
+        }
+        extra.push(format!(
+            "hello: {}s, max age: {}s, forward delay: {}s",
+            bpdu.hello_time / 256,
+            bpdu.max_age / 256,
+            bpdu.forward_delay / 256
+        ));
+        if bpdu.topology_change() {
+            extra.push("topology change".to_string());
+        }
+        out.push_str(&format!(" ({})", extra.join(", ")));
+
+        if bpdu.topology_change() {
+            Color::Yellow
+        } else {
+            Color::Blue
+        }
+    }
+
+    #[inline]
+    fn format_compact_lacp(&self, out: &mut String, lacp: &lacp::LACP) -> Color {
+        let display_info = |info: &lacp::Info| {
+            format!(
+                "{}/{} key {}, port {}/{} [{}]",
+                info.system_priority,
+                display_macadr_buf(info.system),
+                info.key,
+                info.port_priority,
+                info.port,
+                info.state_flags().join(", ")
+            )
+        };
+
+        out.push_str(&format!(
+            "[lacp] actor {} -> partner {}",
+            display_info(&lacp.actor),
+            display_info(&lacp.partner)
+        ));
+        Color::Blue
+    }
+
+    #[inline]
+    fn format_compact_eapol(&self, out: &mut String, eapol: &eapol::EAPOL) -> Color {
+        use crate::structs::eapol::{EAP, EAPOL, Method};
+        match eapol {
+            EAPOL::EAP(EAP::Request(msg)) | EAPOL::EAP(EAP::Response(msg)) => {
+                let kind = match eapol {
+                    EAPOL::EAP(EAP::Request(_)) => "request",
+                    _ => "response",
+                };
+                out.push_str(&format!("[eapol] EAP {} #{}, ", kind, msg.id));
+                match &msg.method {
+                    Method::Identity(identity) => {
+                        out.push_str(&format!("identity: {:?}", identity));
+                        return Color::Red;
+                    }
+                    Method::Notification(text) => {
+                        out.push_str(&format!("notification: {:?}", text))
+                    }
+                    Method::Nak(desired) => out.push_str(&format!("nak, desired: {:?}", desired)),
+                    method => out.push_str(&format!("method: {:?}", method)),
+                }
+            }
+            EAPOL::EAP(EAP::Success(id)) => out.push_str(&format!("[eapol] EAP success #{}", id)),
+            EAPOL::EAP(EAP::Failure(id)) => out.push_str(&format!("[eapol] EAP failure #{}", id)),
+            EAPOL::Start => out.push_str("[eapol] start"),
+            EAPOL::Logoff => out.push_str("[eapol] logoff"),
+            EAPOL::Key(descriptor) => {
+                out.push_str(&format!("[eapol] key (descriptor: {})", descriptor))
+            }
+            EAPOL::Unknown(packet_type, data) => out.push_str(&format!(
+                "[eapol] type={} {:?}",
+                packet_type,
+                data.as_bstr()
+            )),
+        }
+        Color::Purple
+    }
+
     #[inline]
     fn format_compact_ipv4<IP: IPHeader>(
         &self,
@@ -194,7 +475,7 @@ impl Format {
         next: ipv4::IPv4,
     ) -> Option<Color> {
         match next {
-            ipv4::IPv4::TCP(tcp_hdr, tcp) => {
+            ipv4::IPv4::TCP(tcp_hdr, tcp, _) => {
                 Some(self.format_compact_ip_tcp(out, ip_hdr, &tcp_hdr, tcp))
             }
             ipv4::IPv4::UDP(udp_hdr, udp) => {
@@ -215,12 +496,13 @@ impl Format {
         next: ipv6::IPv6,
     ) -> Option<Color> {
         match next {
-            ipv6::IPv6::TCP(tcp_hdr, tcp) => {
+            ipv6::IPv6::TCP(tcp_hdr, tcp, _) => {
                 Some(self.format_compact_ip_tcp(out, ip_hdr, &tcp_hdr, tcp))
             }
             ipv6::IPv6::UDP(udp_hdr, udp) => {
                 Some(self.format_compact_ip_udp(out, ip_hdr, udp_hdr, udp))
             }
+            ipv6::IPv6::ICMPv6(icmp) => Some(self.format_compact_ip_icmpv6(out, ip_hdr, icmp)),
             ipv6::IPv6::Unknown(data) => self.format_compact_ip_unknown(out, ip_hdr, &data),
         }
     }
@@ -266,8 +548,12 @@ impl Format {
         out.push_str(&format!(
             "[tcp/{:2}] {:22} -> {:22} ",
             flags,
-            format!("{}:{}", ip_hdr.source_addr(), tcp_hdr.source_port),
-            format!("{}:{}", ip_hdr.dest_addr(), tcp_hdr.dest_port)
+            self.display_socket(
+                ip_hdr.source_addr(),
+                ip_hdr.source_ip(),
+                tcp_hdr.source_port
+            ),
+            self.display_socket(ip_hdr.dest_addr(), ip_hdr.dest_ip(), tcp_hdr.dest_port)
         ));
 
         use crate::structs::tcp::TCP::*;
@@ -289,9 +575,9 @@ impl Format {
                     out.push_str(&align(offset, &format!("{key:?}: {value:?}")));
                 }
 
-                if let Some(body) = http.body {
+                if let Some(body) = http.decoded() {
                     out.push('\n');
-                    out.push_str(&align(offset, &format!("{body:?}")));
+                    out.push_str(&align(offset, &display_body(body)));
                 }
 
                 Color::Red
@@ -309,22 +595,16 @@ impl Format {
                     out.push_str(&align(offset, &format!("{key:?}: {value:?}")));
                 }
 
-                if let Some(body) = http.body {
+                if let Some(body) = http.decoded() {
                     out.push('\n');
-                    out.push_str(&align(offset, &format!("{body:?}")));
+                    out.push_str(&align(offset, &display_body(body)));
                 }
 
                 Color::Red
             }
             TLS(tls::TLS::ClientHello(client_hello)) => {
-                let extra = display_kv_list(&[
-                    ("version", client_hello.version),
-                    ("session", client_hello.session_id.as_deref()),
-                    ("hostname", client_hello.hostname.as_deref()),
-                ]);
-
                 out.push_str("[tls] ClientHello");
-                out.push_str(&extra);
+                out.push_str(&self.display_client_hello(&client_hello));
                 Color::Green
             }
             TLS(tls::TLS::ServerHello(server_hello)) => {
@@ -332,11 +612,333 @@ impl Format {
                     ("version", server_hello.version),
                     ("session", server_hello.session_id.as_deref()),
                     ("cipher", server_hello.cipher),
+                    ("alpn", server_hello.alpn.as_deref()),
+                    ("key_share", server_hello.key_share.as_deref()),
+                    ("resumed", server_hello.resumed),
+                    ("ja3s", Some(&server_hello.ja3s)),
+                    ("ja4s", Some(&server_hello.ja4s)),
+                    (
+                        "server",
+                        self.fingerprints
+                            .lookup(&server_hello.ja4s)
+                            .or_else(|| self.fingerprints.lookup(&server_hello.ja3s)),
+                    ),
                 ]);
 
-                out.push_str("[tls] ServerHello");
+                let offset = out.len();
+                if server_hello.hello_retry {
+                    out.push_str("[tls] HelloRetryRequest");
+                } else {
+                    out.push_str("[tls] ServerHello");
+                }
                 out.push_str(&extra);
-                Color::Green
+                display_certificates(out, offset, &server_hello.certificates)
+            }
+            TLS(tls::TLS::Alert(alert)) => {
+                out.push_str("[tls] Alert");
+                if alert.description.is_some() {
+                    out.push_str(&display_kv_list(&[
+                        ("level", alert.level),
+                        ("description", alert.description.as_deref()),
+                    ]));
+                } else {
+                    out.push_str(" (encrypted)");
+                }
+
+                if alert.level == Some("warning") {
+                    Color::Yellow
+                } else {
+                    Color::Red
+                }
+            }
+            TLS(tls::TLS::ChangeCipherSpec) => {
+                out.push_str("[tls] ChangeCipherSpec");
+                GREY
+            }
+            TLS(tls::TLS::Certificate(certificates)) => {
+                let offset = out.len();
+                out.push_str("[tls] Certificate");
+                display_certificates(out, offset, &certificates)
+            }
+            SSH(ssh) => {
+                let offset = out.len();
+                if let Some(banner) = &ssh.banner {
+                    out.push_str(&format!(
+                        "[ssh] SSH-{} {:?}",
+                        banner.protocol, banner.software
+                    ));
+                    out.push_str(&display_kv_list(&[(
+                        "comments",
+                        banner.comments.as_deref(),
+                    )]));
+                }
+                match &ssh.kex_init {
+                    Some(kex_init) => {
+                        if ssh.banner.is_some() {
+                            out.push('\n');
+                            out.push_str(&" ".repeat(offset));
+                        }
+                        display_kex_init(out, offset, kex_init)
+                    }
+                    None => Color::Green,
+                }
+            }
+            FTP(ftp::FTP::Command(command)) => {
+                out.push_str(&format!("[ftp] {}", command.command));
+                if let Some(argument) = &command.argument {
+                    out.push_str(&format!(" {:?}", argument));
+                }
+                out.push_str(&display_data_port(&command.data_port));
+                Color::Red
+            }
+            FTP(ftp::FTP::Reply(reply)) => {
+                let offset = out.len();
+                out.push_str(&format!("[ftp] {}", reply.code));
+                for (i, line) in reply.lines.iter().enumerate() {
+                    if i == 0 {
+                        out.push_str(&format!(" {:?}", line));
+                    } else {
+                        out.push_str(&align(offset + 10, &format!("{:?}", line)));
+                    }
+                }
+                out.push_str(&display_data_port(&reply.data_port));
+                Color::Blue
+            }
+            FTP(ftp::FTP::Data(data)) => {
+                out.push_str("[ftp-data]");
+                out.push_str(&display_kv_list(&[
+                    ("command", data.command.as_deref()),
+                    ("file", data.file.as_deref()),
+                ]));
+                out.push_str(&format!(" {:?}", data.data.as_bstr()));
+                Color::Yellow
+            }
+            Mail(mail::Mail::Commands(protocol, commands)) => {
+                let offset = out.len();
+                for (i, command) in commands.iter().enumerate() {
+                    let mut line = format!("[{}] ", protocol.name());
+                    if let Some(tag) = &command.tag {
+                        line.push_str(&format!("{} ", tag));
+                    }
+                    line.push_str(&command.command);
+                    if let Some(argument) = &command.argument {
+                        line.push_str(&format!(" {:?}", argument));
+                    }
+                    line.push_str(&display_kv_list(&[
+                        ("address", command.address.as_deref()),
+                        ("username", command.username.as_deref()),
+                    ]));
+                    if i == 0 {
+                        out.push_str(&line);
+                    } else {
+                        out.push_str(&align(offset, &line));
+                    }
+                }
+                Color::Red
+            }
+            Mail(mail::Mail::Replies(protocol, replies)) => {
+                let offset = out.len();
+                for (i, reply) in replies.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(&align(offset, ""));
+                    }
+                    let start = out.len();
+                    out.push_str(&format!("[{}] ", protocol.name()));
+                    if let Some(tag) = &reply.tag {
+                        out.push_str(&format!("{} ", tag));
+                    }
+                    out.push_str(&reply.status);
+                    let indent = offset + out.len() - start + 1;
+                    for (i, line) in reply.lines.iter().enumerate() {
+                        if i == 0 {
+                            out.push_str(&format!(" {:?}", line));
+                        } else {
+                            out.push_str(&align(indent, &format!("{:?}", line)));
+                        }
+                    }
+                }
+                Color::Blue
+            }
+            Mail(mail::Mail::Message(message)) => {
+                out.push_str("[smtp] message");
+                out.push_str(&display_kv_list(&[
+                    ("from", message.from.as_deref()),
+                    ("to", message.to.as_deref()),
+                    ("subject", message.subject.as_deref()),
+                ]));
+                Color::Red
+            }
+            Telnet(telnet) => {
+                out.push_str("[telnet]");
+                if !telnet.text.is_empty() {
+                    out.push_str(&format!(" {:?}", telnet.text));
+                }
+                out.push_str(&display_telnet(&telnet));
+                if telnet.prompt.is_some() || telnet.input.is_some() {
+                    Color::Yellow
+                } else if telnet.text.is_empty() {
+                    GREY
+                } else {
+                    Color::Red
+                }
+            }
+            MySQL(mysql) => {
+                match &mysql {
+                    mysql::MySQL::Handshake(handshake) => {
+                        out.push_str(&format!("[mysql] handshake {:?}", handshake.server_version));
+                        out.push_str(&display_kv_list(&[
+                            ("connection_id", Some(&handshake.connection_id.to_string())),
+                            ("auth_plugin", handshake.auth_plugin.as_deref()),
+                        ]));
+                    }
+                    mysql::MySQL::Login(login) => {
+                        out.push_str(&format!("[mysql] login {:?}", login.username));
+                        out.push_str(&display_kv_list(&[
+                            ("database", login.database.as_deref()),
+                            ("auth_plugin", login.auth_plugin.as_deref()),
+                        ]));
+                    }
+                    mysql::MySQL::SslRequest => out.push_str("[mysql] SSLRequest"),
+                    mysql::MySQL::Query(query) => {
+                        out.push_str(&format!("[mysql] query {:?}", truncate(query)))
+                    }
+                    mysql::MySQL::InitDb(database) => {
+                        out.push_str(&format!("[mysql] use {:?}", database))
+                    }
+                    mysql::MySQL::Quit => out.push_str("[mysql] quit"),
+                    mysql::MySQL::Error(error) => {
+                        out.push_str(&format!("[mysql] error {} {:?}", error.code, error.message));
+                        out.push_str(&display_kv_list(&[("state", error.state.as_deref())]));
+                    }
+                }
+                match mysql {
+                    mysql::MySQL::Handshake(_) => Color::Blue,
+                    mysql::MySQL::Error(_) => Color::Yellow,
+                    _ => Color::Red,
+                }
+            }
+            Postgres(messages) => {
+                let offset = out.len();
+                for (i, message) in messages.iter().enumerate() {
+                    let line = format!("[postgres] {}", display_postgres(message));
+                    if i == 0 {
+                        out.push_str(&line);
+                    } else {
+                        out.push_str(&align(offset, &line));
+                    }
+                }
+                if messages
+                    .iter()
+                    .any(|x| matches!(x, postgres::Message::Error(_)))
+                {
+                    Color::Yellow
+                } else if messages
+                    .iter()
+                    .any(|x| matches!(x, postgres::Message::Authentication(_)))
+                {
+                    Color::Blue
+                } else {
+                    Color::Red
+                }
+            }
+            Redis(redis::Redis::Commands(commands)) => {
+                let offset = out.len();
+                for (i, command) in commands.iter().enumerate() {
+                    let mut line = format!("[redis] {}", command.name());
+                    for arg in command.args.iter().skip(1).take(MAX_REDIS_VALUES) {
+                        line.push_str(&format!(" {:?}", truncate_bytes(arg).as_bstr()));
+                    }
+                    if command.args.len() > MAX_REDIS_VALUES + 1 {
+                        line.push_str(" ...");
+                    }
+                    if i == 0 {
+                        out.push_str(&line);
+                    } else {
+                        out.push_str(&align(offset, &line));
+                    }
+                }
+                Color::Red
+            }
+            Redis(redis::Redis::Replies(replies)) => {
+                let offset = out.len();
+                for (i, reply) in replies.iter().enumerate() {
+                    let line = format!("[redis] {}", display_resp(reply));
+                    if i == 0 {
+                        out.push_str(&line);
+                    } else {
+                        out.push_str(&align(offset, &line));
+                    }
+                }
+                if replies.iter().any(|x| matches!(x, redis::Value::Error(_))) {
+                    Color::Yellow
+                } else {
+                    Color::Blue
+                }
+            }
+            MongoDB(mongodb) => match mongodb.op {
+                mongodb::Op::Command(command) => {
+                    out.push_str(&format!("[mongodb] {}", command.name));
+                    out.push_str(&display_kv_list(&[
+                        ("collection", command.collection.as_deref()),
+                        ("database", command.database.as_deref()),
+                    ]));
+                    Color::Red
+                }
+                mongodb::Op::Reply(reply) => {
+                    let ok = reply.ok.map(|x| x.to_string());
+                    out.push_str("[mongodb] reply");
+                    out.push_str(&display_kv_list(&[
+                        ("ok", ok.as_deref()),
+                        ("error", reply.error.as_deref()),
+                    ]));
+                    if reply.error.is_some() {
+                        Color::Yellow
+                    } else {
+                        Color::Blue
+                    }
+                }
+                mongodb::Op::Other(opcode) => {
+                    out.push_str(&format!("[mongodb] opcode {}", opcode));
+                    GREY
+                }
+            },
+            IRC(messages) => {
+                let offset = out.len();
+                for (i, message) in messages.iter().enumerate() {
+                    let line = format!("[irc] {}", display_irc(message));
+                    if i == 0 {
+                        out.push_str(&line);
+                    } else {
+                        out.push_str(&align(offset, &line));
+                    }
+                }
+                if messages.iter().any(|x| x.is_numeric()) {
+                    GREY
+                } else {
+                    Color::Blue
+                }
+            }
+            BGP(messages) => {
+                let offset = out.len();
+                for (i, message) in messages.iter().enumerate() {
+                    for (j, line) in display_bgp(message).into_iter().enumerate() {
+                        if i == 0 && j == 0 {
+                            out.push_str(&line);
+                        } else {
+                            out.push_str(&align(offset, &line));
+                        }
+                    }
+                }
+                if messages
+                    .iter()
+                    .any(|x| matches!(x, bgp::Message::Notification(_)))
+                {
+                    Color::Red
+                } else if messages.iter().all(|x| *x == bgp::Message::Keepalive) {
+                    GREY
+                } else {
+                    Color::Blue
+                }
             }
             Text(text) => {
                 out.push_str(&format!("[text] {:?}", text));
@@ -350,6 +952,30 @@ impl Format {
         }
     }
 
+    fn display_client_hello(&self, client_hello: &tls::ClientHello) -> String {
+        let client = self
+            .fingerprints
+            .lookup(&client_hello.ja4)
+            .or_else(|| self.fingerprints.lookup(&client_hello.ja3));
+        let alpn = client_hello.alpn.join(",");
+        let key_shares = client_hello.key_shares.join(",");
+        display_kv_list(&[
+            ("version", client_hello.version),
+            ("session", client_hello.session_id.as_deref()),
+            ("hostname", client_hello.hostname.as_deref()),
+            ("alpn", Some(alpn.as_str()).filter(|x| !x.is_empty())),
+            (
+                "key_share",
+                Some(key_shares.as_str()).filter(|x| !x.is_empty()),
+            ),
+            ("ech", client_hello.ech.then_some("yes")),
+            ("resumption", client_hello.resumption),
+            ("ja3", Some(&client_hello.ja3)),
+            ("ja4", Some(&client_hello.ja4)),
+            ("client", client),
+        ])
+    }
+
     #[inline]
     fn format_compact_ip_udp<IP: IPHeader>(
         &self,
@@ -360,8 +986,12 @@ impl Format {
     ) -> Color {
         out.push_str(&format!(
             "[udp   ] {:22} -> {:22} ",
-            format!("{}:{}", ip_hdr.source_addr(), udp_hdr.source_port),
-            format!("{}:{}", ip_hdr.dest_addr(), udp_hdr.dest_port)
+            self.display_socket(
+                ip_hdr.source_addr(),
+                ip_hdr.source_ip(),
+                udp_hdr.source_port
+            ),
+            self.display_socket(ip_hdr.dest_addr(), ip_hdr.dest_ip(), udp_hdr.dest_port)
         ));
 
         use crate::structs::udp::UDP::*;
@@ -400,6 +1030,7 @@ impl Format {
                         out.push_str(
                             &DhcpKvListWriter::new()
                                 .append("hostname", &ack.hostname)
+                                .append("server", &ack.server_identifier)
                                 .append("router", &ack.router)
                                 .append("dns", &ack.domain_name_server)
                                 .finalize(),
@@ -414,6 +1045,7 @@ impl Format {
                         out.push_str(
                             &DhcpKvListWriter::new()
                                 .append("hostname", &offer.hostname)
+                                .append("server", &offer.server_identifier)
                                 .append("router", &offer.router)
                                 .append("dns", &offer.domain_name_server)
                                 .finalize(),
@@ -459,6 +1091,34 @@ impl Format {
 
                 Color::Yellow
             }
+            NBNS(nbns) => {
+                use crate::structs::nbns::NBNS::*;
+                out.push_str(&match nbns {
+                    Query(name) => format!("[nbns] query, {}", display_nbns_name(&name)),
+                    Response(name, addrs) => {
+                        format!("[nbns] resp, {} => {:?}", display_nbns_name(&name), addrs)
+                    }
+                    Registration(name, addrs) => format!(
+                        "[nbns] registration, {} => {:?}",
+                        display_nbns_name(&name),
+                        addrs
+                    ),
+                    Release(name, addrs) => format!(
+                        "[nbns] release, {} => {:?}",
+                        display_nbns_name(&name),
+                        addrs
+                    ),
+                    Refresh(name, addrs) => format!(
+                        "[nbns] refresh, {} => {:?}",
+                        display_nbns_name(&name),
+                        addrs
+                    ),
+                    Other { opcode, response } => {
+                        format!("[nbns] opcode: {}, response: {}", opcode, response)
+                    }
+                });
+                Color::Yellow
+            }
             SSDP(ssdp) => {
                 use crate::structs::ssdp::SSDP::*;
                 out.push_str(&match ssdp {
@@ -484,6 +1144,21 @@ impl Format {
                 ));
                 Color::Purple
             }
+            QUIC(quic) => {
+                if let Some(client_hello) = &quic.client_hello {
+                    out.push_str("[quic] ClientHello");
+                    out.push_str(&self.display_client_hello(client_hello));
+                    Color::Green
+                } else {
+                    out.push_str(&format!("[quic] {:?}", quic.packet_type));
+                    out.push_str(&display_kv_list(&[
+                        ("version", quic::version_name(quic.version)),
+                        ("dcid", Some(&quic.dcid)),
+                        ("scid", Some(quic.scid.as_str()).filter(|x| !x.is_empty())),
+                    ]));
+                    GREY
+                }
+            }
             Text(text) => {
                 out.push_str(&format!("[text] {:?}", text));
                 Color::Red
@@ -546,6 +1221,83 @@ impl Format {
         Color::Blue
     }
 
+    fn format_compact_ip_icmpv6<IP: IPHeader>(
+        &self,
+        out: &mut String,
+        ip_hdr: &IP,
+        icmp: icmpv6::ICMPv6,
+    ) -> Color {
+        use crate::structs::icmpv6::ICMPv6::*;
+        let code = match icmp {
+            EchoRequest => "icmp6/ping",
+            EchoReply => "icmp6/pong",
+            RouterSolicitation => "icmp6/rs",
+            RouterAdvertisement(_) => "icmp6/ra",
+            NeighborSolicitation(_) => "icmp6/ns",
+            NeighborAdvertisement(_) => "icmp6/na",
+            Other { .. } => "icmp6",
+        };
+        out.push_str(&format!(
+            "[{:10}] {:18} -> {:22} ",
+            code,
+            ip_hdr.source_addr(),
+            ip_hdr.dest_addr()
+        ));
+
+        match icmp {
+            RouterAdvertisement(ra) => {
+                let mut flags = Vec::new();
+                if ra.managed {
+                    flags.push("managed");
+                }
+                if ra.other {
+                    flags.push("other");
+                }
+
+                out.push_str(&format!("router lifetime: {}s", ra.router_lifetime));
+                out.push_str(
+                    &DhcpKvListWriter::new()
+                        .append(
+                            "prefixes",
+                            &Some(display_prefixes(&ra.prefixes))
+                                .filter(|_| !ra.prefixes.is_empty()),
+                        )
+                        .append("dns", &Some(&ra.dns).filter(|dns| !dns.is_empty()))
+                        .append("mtu", &ra.mtu)
+                        .append(
+                            "mac",
+                            &ra.source_mac.map(|mac| Unquoted(display_macadr_buf(mac))),
+                        )
+                        .append("flags", &Some(flags).filter(|flags| !flags.is_empty()))
+                        .finalize(),
+                );
+                Color::Yellow
+            }
+            NeighborSolicitation(target) => {
+                out.push_str(&format!("who has {}?", target));
+                Color::Blue
+            }
+            NeighborAdvertisement(target) => {
+                out.push_str(&format!("{} is here", target));
+                Color::Blue
+            }
+            Other {
+                icmp_type,
+                code,
+                data,
+            } => {
+                out.push_str(&format!(
+                    "[type={}, code={}] {:?}",
+                    icmp_type,
+                    code,
+                    data.as_bstr()
+                ));
+                Color::Blue
+            }
+            _ => Color::Blue,
+        }
+    }
+
     #[inline]
     fn print_debugging(&self, packet: Raw) {
         match packet {
@@ -569,7 +1321,7 @@ impl Format {
                     self.colorify(Color::Blue, format!("arp: {:?}", arp_pkt))
                 );
             }
-            Ether::IPv4(ip_hdr, ipv4::IPv4::TCP(tcp_hdr, tcp)) => {
+            Ether::IPv4(ip_hdr, ipv4::IPv4::TCP(tcp_hdr, tcp, _)) => {
                 println!("{}ipv4: {:?}", "\t".repeat(indent), ip_hdr);
                 println!("{}tcp: {:?}", "\t".repeat(indent + 1), tcp_hdr);
                 println!(
@@ -596,7 +1348,7 @@ impl Format {
                 println!("{}ipv4: {:?}", "\t".repeat(indent), ip_hdr);
                 println!("{}unknown: {:?}", "\t".repeat(indent + 1), data);
             }
-            Ether::IPv6(ip_hdr, ipv6::IPv6::TCP(tcp_hdr, tcp)) => {
+            Ether::IPv6(ip_hdr, ipv6::IPv6::TCP(tcp_hdr, tcp, _)) => {
                 println!("{}ipv6: {:?}", "\t".repeat(indent), ip_hdr);
                 println!("{}tcp: {:?}", "\t".repeat(indent + 1), tcp_hdr);
                 println!(
@@ -614,6 +1366,10 @@ impl Format {
                     self.print_debugging_udp(udp)
                 );
             }
+            Ether::IPv6(ip_hdr, ipv6::IPv6::ICMPv6(icmp)) => {
+                println!("{}ipv6: {:?}", "\t".repeat(indent), ip_hdr);
+                println!("{}icmpv6: {:?}", "\t".repeat(indent + 1), icmp);
+            }
             Ether::IPv6(ip_hdr, ipv6::IPv6::Unknown(data)) => {
                 println!("{}ipv6: {:?}", "\t".repeat(indent), ip_hdr);
                 println!("{}unknown: {:?}", "\t".repeat(indent + 1), data);
@@ -621,6 +1377,27 @@ impl Format {
             Ether::Cjdns(cjdns_pkt) => {
                 println!("{}cjdns: {:?}", "\t".repeat(indent), cjdns_pkt);
             }
+            Ether::Llc(llc_pkt) => {
+                println!(
+                    "{}{}",
+                    "\t".repeat(indent),
+                    self.colorify(Color::Blue, format!("llc: {:?}", llc_pkt))
+                );
+            }
+            Ether::Lacp(lacp_pkt) => {
+                println!(
+                    "{}{}",
+                    "\t".repeat(indent),
+                    self.colorify(Color::Blue, format!("lacp: {:?}", lacp_pkt))
+                );
+            }
+            Ether::Eapol(eapol_pkt) => {
+                println!(
+                    "{}{}",
+                    "\t".repeat(indent),
+                    self.colorify(Color::Purple, format!("eapol: {:?}", eapol_pkt))
+                );
+            }
             Ether::Unknown(data) => {
                 println!("{}unknown: {:?}", "\t".repeat(indent), data);
             }
@@ -636,6 +1413,16 @@ impl Format {
                 self.colorify(Color::Red, format!("http: {http:?}"))
             }
             TLS(client_hello) => self.colorify(Color::Green, format!("tls: {:?}", client_hello)),
+            SSH(ssh) => self.colorify(Color::Green, format!("ssh: {:?}", ssh)),
+            FTP(ftp) => self.colorify(Color::Red, format!("ftp: {:?}", ftp)),
+            Mail(mail) => self.colorify(Color::Red, format!("mail: {:?}", mail)),
+            Telnet(telnet) => self.colorify(Color::Red, format!("telnet: {:?}", telnet)),
+            MySQL(mysql) => self.colorify(Color::Red, format!("mysql: {:?}", mysql)),
+            Postgres(messages) => self.colorify(Color::Red, format!("postgres: {:?}", messages)),
+            Redis(redis) => self.colorify(Color::Red, format!("redis: {:?}", redis)),
+            MongoDB(mongodb) => self.colorify(Color::Red, format!("mongodb: {:?}", mongodb)),
+            IRC(messages) => self.colorify(Color::Blue, format!("irc: {:?}", messages)),
+            BGP(messages) => self.colorify(Color::Blue, format!("bgp: {:?}", messages)),
             Text(text) => self.colorify(Color::Blue, format!("remaining: {:?}", text)),
             Binary(x) => self.colorify(Color::Yellow, format!("remaining: {:?}", x)),
             Empty => self.colorify(GREY, String::new()),
@@ -648,8 +1435,10 @@ impl Format {
         match udp {
             DHCP(dhcp) => self.colorify(Color::Green, format!("dhcp: {:?}", dhcp)),
             DNS(dns) => self.colorify(Color::Green, format!("dns: {:?}", dns)),
+            NBNS(nbns) => self.colorify(Color::Yellow, format!("nbns: {:?}", nbns)),
             SSDP(ssdp) => self.colorify(Color::Purple, format!("ssdp: {:?}", ssdp)),
             Dropbox(dropbox) => self.colorify(Color::Purple, format!("dropbox: {:?}", dropbox)),
+            QUIC(quic) => self.colorify(Color::Green, format!("quic: {:?}", quic)),
             Text(text) => self.colorify(Color::Blue, format!("remaining: {:?}", text)),
             Binary(x) => self.colorify(Color::Yellow, format!("remaining: {:?}", x)),
         }
@@ -678,6 +1467,390 @@ impl Filter {
     }
 }
 
+fn format_compact_alert(alert: &Alert) -> String {
+    match alert {
+        Alert::Arp(analysis::arp::Alert::MappingChanged { ip, old, new }) => format!(
+            "[alert/arp] {} moved from {} to {}",
+            ip,
+            display_macadr_buf(*old),
+            display_macadr_buf(*new)
+        ),
+        Alert::Arp(analysis::arp::Alert::IpConflict { ip, macs }) => format!(
+            "[alert/arp] {} is claimed by multiple macs: {}, {}",
+            ip,
+            display_macadr_buf(macs[0]),
+            display_macadr_buf(macs[1])
+        ),
+        Alert::Arp(analysis::arp::Alert::GratuitousFlood { mac, count }) => format!(
+            "[alert/arp] gratuitous arp flood from {} ({} packets)",
+            display_macadr_buf(*mac),
+            count
+        ),
+        Alert::Dns(analysis::dns::Alert::WrongSource {
+            client,
+            server,
+            source,
+            id,
+            questions,
+        }) => format!(
+            "[alert/dns] {} answered a query of {} to {} (id: {:#06x}, questions: {:?})",
+            source,
+            client,
+            server,
+            id,
+            display_dns_questions(questions)
+        ),
+        Alert::Dns(analysis::dns::Alert::QuestionMismatch {
+            client,
+            server,
+            id,
+            questions,
+            answered,
+        }) => format!(
+            "[alert/dns] {} answered a query of {} with different questions (id: {:#06x}, questions: {:?}, answered: {:?})",
+            server,
+            client,
+            id,
+            display_dns_questions(questions),
+            display_dns_questions(answered)
+        ),
+        Alert::Dns(analysis::dns::Alert::RacingReply {
+            client,
+            server,
+            id,
+            questions,
+            first,
+            second,
+        }) => format!(
+            "[alert/dns] conflicting answers from {} to {} (id: {:#06x}, questions: {:?}, first: {:?}, second: {:?})",
+            server,
+            client,
+            id,
+            display_dns_questions(questions),
+            display_dns_answers(first),
+            display_dns_answers(second)
+        ),
+        Alert::Rogue(analysis::rogue::Alert::DhcpServer {
+            server,
+            mac,
+            router,
+            dns,
+        }) => format!(
+            "[alert/dhcp] rogue dhcp server {}{}",
+            server,
+            DhcpKvListWriter::new()
+                .append("mac", &mac.map(|mac| Unquoted(display_macadr_buf(mac))))
+                .append("router", router)
+                .append("dns", dns)
+                .finalize()
+        ),
+        Alert::Rogue(analysis::rogue::Alert::DhcpOptions {
+            server,
+            router,
+            dns,
+            expected_router,
+            expected_dns,
+        }) => format!(
+            "[alert/dhcp] dhcp server {} changed its options{}",
+            server,
+            DhcpKvListWriter::new()
+                .append("router", router)
+                .append("dns", dns)
+                .append("expected router", expected_router)
+                .append("expected dns", expected_dns)
+                .finalize()
+        ),
+        Alert::Rogue(analysis::rogue::Alert::Router {
+            router,
+            mac,
+            prefixes,
+            dns,
+        }) => format!(
+            "[alert/ra] rogue router advertisement from {}{}",
+            router,
+            DhcpKvListWriter::new()
+                .append("mac", &mac.map(|mac| Unquoted(display_macadr_buf(mac))))
+                .append("prefixes", &Some(display_prefixes(prefixes)))
+                .append("dns", &Some(dns))
+                .finalize()
+        ),
+        Alert::Rogue(analysis::rogue::Alert::RouterOptions {
+            router,
+            prefixes,
+            dns,
+            expected_prefixes,
+            expected_dns,
+        }) => format!(
+            "[alert/ra] router {} changed its advertisement{}",
+            router,
+            DhcpKvListWriter::new()
+                .append("prefixes", &Some(display_prefixes(prefixes)))
+                .append("dns", &Some(dns))
+                .append(
+                    "expected prefixes",
+                    &Some(display_prefixes(expected_prefixes))
+                )
+                .append("expected dns", &Some(expected_dns))
+                .finalize()
+        ),
+    }
+}
+
+fn format_compact_flow(record: &flows::Record) -> String {
+    let flow = &record.flow;
+    let protocol = match flow.protocol {
+        flows::Protocol::TCP => "tcp",
+        flows::Protocol::UDP => "udp",
+    };
+    // the state is only interesting if the reason doesn't already tell
+    let state = match record.reason {
+        flows::EndReason::Closed | flows::EndReason::Reset => None,
+        _ => flow.tcp_state.map(|state| Unquoted(format!("{:?}", state))),
+    };
+    let duration = flow
+        .last_seen
+        .duration_since(flow.first_seen)
+        .unwrap_or_default();
+
+    format!(
+        "[flow/{}   ] {:<21} -> {:<21} {:?}, {:.1}s, {} pkts/{} B ->, <- {} pkts/{} B{}",
+        protocol,
+        flow.client.to_string(),
+        flow.server.to_string(),
+        record.reason,
+        duration.as_secs_f64(),
+        flow.client_packets,
+        flow.client_bytes,
+        flow.server_packets,
+        flow.server_bytes,
+        DhcpKvListWriter::new()
+            .append("state", &state)
+            .append(
+                "app",
+                &flow
+                    .application
+                    .as_ref()
+                    .map(|app| Unquoted(app.name().to_string()))
+            )
+            .append(
+                "host",
+                &flow.application.as_ref().and_then(|app| app.hostname())
+            )
+            .finalize()
+    )
+}
+
+fn format_compact_flow_summary(summary: &flows::Summary) -> String {
+    let applications = summary
+        .applications
+        .iter()
+        .map(|(name, count)| format!("{}: {}", name, count))
+        .collect::<Vec<_>>();
+
+    format!(
+        "[flow/summary] {} flows (tcp: {}, udp: {}), {} packets, {} bytes{}",
+        summary.flows,
+        summary.tcp,
+        summary.udp,
+        summary.packets,
+        summary.bytes,
+        if applications.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", applications.join(", "))
+        }
+    )
+}
+
+fn display_body(body: &http::Body) -> String {
+    let mut summary = format!("{} bytes", body.data.len());
+    if let Some(content_type) = &body.content_type {
+        summary.push_str(&format!(", {content_type:?}"));
+    }
+    if !body.encodings.is_empty() {
+        summary.push_str(&format!(", decoded from {}", body.encodings.join("+")));
+    }
+    if body.truncated {
+        summary.push_str(", truncated");
+    }
+
+    if body.text {
+        format!("[{summary}] {:?}", body.data)
+    } else {
+        format!("[{summary}]")
+    }
+}
+
+fn format_compact_http_transaction(transaction: &analysis::http::Transaction) -> String {
+    format!(
+        "[http/log  ] {:<21} -> {:<21} {} {}{} {}{}",
+        transaction.client.to_string(),
+        transaction.server.to_string(),
+        transaction.method,
+        transaction
+            .host
+            .as_ref()
+            .map(|host| format!("http://{}", host))
+            .unwrap_or_default(),
+        transaction.path,
+        transaction
+            .status
+            .map_or_else(|| String::from("-"), |status| status.to_string()),
+        DhcpKvListWriter::new()
+            .append("type", &transaction.content_type)
+            .append("length", &transaction.content_length)
+            .append(
+                "ttfb",
+                &transaction
+                    .ttfb
+                    .map(|ttfb| Unquoted(format!("{:.1}ms", ttfb.as_secs_f64() * 1000.0)))
+            )
+            .finalize()
+    )
+}
+
+fn format_compact_dns_transaction(transaction: &analysis::dns::Transaction) -> String {
+    format!(
+        "[dns/log   ] {:<21} -> {:<21} {:?} {}{}",
+        transaction.client.to_string(),
+        transaction.server.to_string(),
+        display_dns_questions(&transaction.questions),
+        transaction
+            .code
+            .map_or_else(|| String::from("-"), |code| format!("{:?}", code)),
+        DhcpKvListWriter::new()
+            .append(
+                "answers",
+                &transaction
+                    .code
+                    .map(|_| display_dns_answers(&transaction.answers))
+            )
+            .append(
+                "time",
+                &transaction
+                    .response_time
+                    .map(|time| { Unquoted(format!("{:.1}ms", time.as_secs_f64() * 1000.0)) })
+            )
+            .finalize()
+    )
+}
+
+#[inline]
+fn display_dns_questions(questions: &[(dns::QueryType, String)]) -> Unquoted {
+    let questions = questions
+        .iter()
+        .map(|(qtype, name)| format!("{:?} {}", qtype, name))
+        .collect::<Vec<_>>();
+    Unquoted(questions.join(", "))
+}
+
+#[inline]
+fn display_dns_answers(answers: &[(String, dns::Record)]) -> Unquoted {
+    let answers = answers
+        .iter()
+        .map(|(_, record)| format!("{:?}", record))
+        .collect::<Vec<_>>();
+    Unquoted(format!("[{}]", answers.join(", ")))
+}
+
+fn format_compact_credential(credential: &analysis::credentials::Credential) -> String {
+    format!(
+        "[creds/{:<6}] {:<21} -> {:<21} {}{}",
+        credential.protocol,
+        credential.client.to_string(),
+        credential.server.to_string(),
+        credential.mechanism,
+        DhcpKvListWriter::new()
+            .append("host", &credential.host)
+            .append("username", &credential.username)
+            .append("secret", &credential.secret)
+            .finalize()
+    )
+}
+
+fn format_compact_inventory(report: &analysis::inventory::Report) -> String {
+    const HEADER: [&str; 7] = [
+        "MAC",
+        "IP",
+        "VENDOR",
+        "HOSTNAME",
+        "OS",
+        "SERVICES",
+        "DHCP FINGERPRINT",
+    ];
+
+    let rows = report
+        .hosts
+        .iter()
+        .map(|host| {
+            [
+                host.mac.map(display_macadr_buf).unwrap_or_default(),
+                join(&host.ips),
+                host.vendor.unwrap_or_default().to_string(),
+                join(&host.hostnames),
+                host.os.clone().unwrap_or_default(),
+                join(&host.services),
+                host.dhcp_fingerprint.clone().unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = HEADER.map(str::len);
+    for row in &rows {
+        for (width, column) in widths.iter_mut().zip(row) {
+            *width = cmp::max(*width, column.len());
+        }
+    }
+    let format_row = |row: &[&str]| {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(column, width)| format!("{:width$}", column, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        line.trim_end().to_string()
+    };
+
+    let mut out = format!("[inventory] {} hosts\n", report.hosts.len());
+    out.push_str(&format_row(&HEADER));
+    // user agents and snis are too long for a column, list them below the host
+    let offset = widths[0] + 2;
+    for (row, host) in rows.iter().zip(&report.hosts) {
+        out.push('\n');
+        out.push_str(&format_row(&row.each_ref().map(String::as_str)));
+        for agent in &host.user_agents {
+            out.push_str(&align(offset, &format!("user-agent: {:?}", agent)));
+        }
+        if !host.snis.is_empty() {
+            out.push_str(&align(offset, &format!("sni: {}", join(&host.snis))));
+        }
+    }
+    out
+}
+
+#[inline]
+fn join<T: Display>(values: impl IntoIterator<Item = T>) -> String {
+    values
+        .into_iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[inline]
+fn display_nbns_name(name: &nbns::Name) -> String {
+    format!("{}<{:02x}>", name.name, name.suffix)
+}
+
+#[inline]
+fn display_prefixes(prefixes: &[icmpv6::Prefix]) -> Unquoted {
+    let prefixes = prefixes
+        .iter()
+        .map(|p| format!("{}/{}", p.prefix, p.length))
+        .collect::<Vec<_>>();
+    Unquoted(format!("[{}]", prefixes.join(", ")))
+}
+
 #[inline]
 fn align(len: usize, a: &str) -> String {
     format!("\n{}{}", " ".repeat(len), &a)
@@ -698,6 +1871,11 @@ fn display_macadr_buf(mac: [u8; 6]) -> String {
     string
 }
 
+#[inline]
+fn display_bridge_id(id: &stp::BridgeId) -> String {
+    format!("{}/{}", id.priority, display_macadr_buf(id.mac))
+}
+
 #[inline]
 fn display_kv_list(list: &[(&str, Option<&str>)]) -> String {
     list.iter()
@@ -706,6 +1884,431 @@ fn display_kv_list(list: &[(&str, Option<&str>)]) -> String {
         .map_or_else(String::new, |extra| format!(" ({})", extra))
 }
 
+/// One line per certificate, chains with an expired or self-signed certificate are highlighted
+fn display_certificates(
+    out: &mut String,
+    offset: usize,
+    certificates: &[tls::Certificate],
+) -> Color {
+    let mut color = Color::Green;
+    for cert in certificates {
+        let alt_names = cert.alt_names.join(",");
+        let extra = display_kv_list(&[
+            ("subject", Some(&cert.subject)),
+            (
+                "issuer",
+                Some(cert.issuer.as_str()).filter(|_| !cert.self_signed),
+            ),
+            (
+                "alt_names",
+                Some(alt_names.as_str()).filter(|x| !x.is_empty()),
+            ),
+            ("not_before", Some(&cert.not_before)),
+            ("not_after", Some(&cert.not_after)),
+            ("key", Some(&cert.key)),
+            ("self_signed", cert.self_signed.then_some("yes")),
+            ("expired", cert.expired.then_some("yes")),
+            ("sha256", Some(&cert.sha256)),
+        ]);
+        out.push_str(&align(offset, &format!("[cert]{extra}")));
+
+        if cert.expired || cert.self_signed {
+            color = Color::Yellow;
+        }
+    }
+    color
+}
+
+/// Values in kv lists are debug formatted, this skips the quotes
+struct Unquoted(String);
+
+impl Debug for Unquoted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_QUERY_LENGTH) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn truncate_bytes(data: &[u8]) -> Vec<u8> {
+    match data.get(..MAX_QUERY_LENGTH) {
+        Some(start) if data.len() > MAX_QUERY_LENGTH => [start, b"..."].concat(),
+        _ => data.to_vec(),
+    }
+}
+
+fn display_postgres(message: &postgres::Message) -> String {
+    match message {
+        postgres::Message::Startup(startup) => format!(
+            "startup{}",
+            display_kv_list(&[
+                ("user", startup.user.as_deref()),
+                ("database", startup.database.as_deref()),
+                ("application_name", startup.application_name.as_deref()),
+            ])
+        ),
+        postgres::Message::SslRequest => "SSLRequest".to_string(),
+        postgres::Message::GssEncRequest => "GSSENCRequest".to_string(),
+        postgres::Message::Query(query) => format!("query {:?}", truncate(query)),
+        postgres::Message::Password(password) => format!("password {:?}", password),
+        postgres::Message::Terminate => "terminate".to_string(),
+        postgres::Message::Authentication(kind) => format!(
+            "authentication {:?}",
+            postgres::Message::authentication(*kind)
+        ),
+        postgres::Message::Error(error) => format!(
+            "{} {}{}",
+            error.severity.as_deref().unwrap_or("ERROR"),
+            error.code.as_deref().unwrap_or_default(),
+            error
+                .message
+                .as_ref()
+                .map(|x| format!(" {:?}", x))
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+/// Resp values in the notation of redis-cli, nested values are shortened
+fn display_resp(value: &redis::Value) -> String {
+    match value {
+        redis::Value::Simple(text) => format!("+{}", escape_controls(text)),
+        redis::Value::Error(text) => format!("-{}", escape_controls(text)),
+        redis::Value::Integer(number) => format!(":{}", number),
+        redis::Value::Bulk(Some(data)) => format!("{:?}", truncate_bytes(data).as_bstr()),
+        redis::Value::Bulk(None) | redis::Value::Array(None) => "(nil)".to_string(),
+        redis::Value::Array(Some(values)) => {
+            let mut elements = values
+                .iter()
+                .take(MAX_REDIS_VALUES)
+                .map(display_resp)
+                .collect::<Vec<_>>();
+            if values.len() > MAX_REDIS_VALUES {
+                elements.push(format!("({} more)", values.len() - MAX_REDIS_VALUES));
+            }
+            format!("[{}]", elements.join(", "))
+        }
+    }
+}
+
+/// Option negotiation is grouped by verb, the options of the terminal are shown with their values
+fn display_telnet(telnet: &telnet::Telnet) -> String {
+    let commands = &telnet.commands;
+    let option = |option: &u8| {
+        telnet::option_name(*option)
+            .map(String::from)
+            .unwrap_or_else(|| option.to_string())
+    };
+    let join = |verb: fn(&telnet::Command) -> Option<&u8>| {
+        let options = commands
+            .iter()
+            .filter_map(verb)
+            .map(option)
+            .collect::<Vec<_>>()
+            .join(",");
+        Some(options).filter(|x| !x.is_empty())
+    };
+    let will = join(|x| match x {
+        telnet::Command::Will(option) => Some(option),
+        _ => None,
+    });
+    let wont = join(|x| match x {
+        telnet::Command::Wont(option) => Some(option),
+        _ => None,
+    });
+    let r#do = join(|x| match x {
+        telnet::Command::Do(option) => Some(option),
+        _ => None,
+    });
+    let dont = join(|x| match x {
+        telnet::Command::Dont(option) => Some(option),
+        _ => None,
+    });
+
+    let mut terminal = None;
+    let mut window = None;
+    let mut environment = Vec::new();
+    for command in commands {
+        match command {
+            telnet::Command::TerminalType(name) => terminal = Some(name.as_str()),
+            telnet::Command::WindowSize { width, height } => {
+                window = Some(format!("{}x{}", width, height))
+            }
+            telnet::Command::Environment(variables) => {
+                environment.extend(variables.iter().map(|(name, value)| match value {
+                    Some(value) => format!("{}={}", name, value),
+                    None => name.clone(),
+                }))
+            }
+            _ => (),
+        }
+    }
+    let environment = environment.join(",");
+
+    display_kv_list(&[
+        ("will", will.as_deref()),
+        ("wont", wont.as_deref()),
+        ("do", r#do.as_deref()),
+        ("dont", dont.as_deref()),
+        ("terminal", terminal),
+        ("window", window.as_deref()),
+        (
+            "environment",
+            Some(environment.as_str()).filter(|x| !x.is_empty()),
+        ),
+        ("prompt", telnet.prompt.map(|x| x.name())),
+        ("input", telnet.input.map(|x| x.name())),
+    ])
+}
+
+/// Updates are shown as the routes they announce and withdraw, one line each
+fn display_bgp(message: &bgp::Message) -> Vec<String> {
+    match message {
+        bgp::Message::Open(open) => {
+            let router_id = open.router_id.to_string();
+            let capabilities = open
+                .capabilities
+                .iter()
+                .map(|x| match x {
+                    bgp::Capability::Multiprotocol { afi, safi } => format!("mp({afi}/{safi})"),
+                    bgp::Capability::RouteRefresh => "route-refresh".to_string(),
+                    bgp::Capability::FourOctetAs(_) => "as4".to_string(),
+                    bgp::Capability::Unknown(code, _) => code.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            vec![format!(
+                "[bgp] OPEN AS{}{}",
+                open.asn,
+                display_kv_list(&[
+                    ("hold_time", Some(&open.hold_time.to_string())),
+                    ("router_id", Some(&router_id)),
+                    (
+                        "capabilities",
+                        Some(capabilities.as_str()).filter(|x| !x.is_empty()),
+                    ),
+                ])
+            )]
+        }
+        bgp::Message::Update(update) if update.is_end_of_rib() => {
+            vec!["[bgp] UPDATE end-of-rib".to_string()]
+        }
+        bgp::Message::Update(update) => {
+            let display_prefixes = |prefixes: &[bgp::Prefix]| {
+                prefixes
+                    .iter()
+                    .map(|x| format!("{}/{}", x.network, x.length))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            };
+
+            let mut lines = Vec::new();
+            if !update.withdrawn.is_empty() {
+                lines.push(format!(
+                    "[bgp] withdraw {}",
+                    display_prefixes(&update.withdrawn)
+                ));
+            }
+
+            let next_hop = update.next_hop.map(|x| x.to_string());
+            let as_path = update
+                .as_path
+                .iter()
+                .map(|segment| {
+                    let join = |asns: &[u32], separator| {
+                        asns.iter()
+                            .map(u32::to_string)
+                            .collect::<Vec<_>>()
+                            .join(separator)
+                    };
+                    match segment {
+                        bgp::Segment::Sequence(asns) => join(asns, " "),
+                        bgp::Segment::Set(asns) => format!("{{{}}}", join(asns, ",")),
+                        bgp::Segment::ConfedSequence(asns) => format!("({})", join(asns, " ")),
+                        bgp::Segment::ConfedSet(asns) => format!("[{}]", join(asns, ",")),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            let origin = update.origin.as_ref().map(|x| format!("{:?}", x));
+            let med = update.med.map(|x| x.to_string());
+            let local_pref = update.local_pref.map(|x| x.to_string());
+            let communities = update
+                .communities
+                .iter()
+                .map(|&x| match x {
+                    0xffff_ff01 => "no-export".to_string(),
+                    0xffff_ff02 => "no-advertise".to_string(),
+                    0xffff_ff03 => "no-export-subconfed".to_string(),
+                    _ => format!("{}:{}", x >> 16, x & 0xffff),
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            let attributes = display_kv_list(&[
+                ("next_hop", next_hop.as_deref()),
+                ("as_path", Some(as_path.as_str()).filter(|x| !x.is_empty())),
+                ("origin", origin.as_deref()),
+                ("med", med.as_deref()),
+                ("local_pref", local_pref.as_deref()),
+                (
+                    "communities",
+                    Some(communities.as_str()).filter(|x| !x.is_empty()),
+                ),
+            ]);
+
+            if !update.nlri.is_empty() {
+                lines.push(format!(
+                    "[bgp] announce {}{}",
+                    display_prefixes(&update.nlri),
+                    attributes
+                ));
+            } else if lines.is_empty() {
+                // eg. routes of other address families in multiprotocol attributes
+                let other = update
+                    .other_attributes
+                    .iter()
+                    .map(u8::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                lines.push(format!(
+                    "[bgp] UPDATE{}",
+                    display_kv_list(&[("attributes", Some(&other))])
+                ));
+            }
+            lines
+        }
+        bgp::Message::Notification(notification) => {
+            let mut line = format!("[bgp] NOTIFICATION {:?}", notification.error());
+            if let Some(suberror) = notification.suberror() {
+                line.push_str(&format!(" {:?}", suberror));
+            }
+            line.push_str(&display_kv_list(&[(
+                "reason",
+                notification.reason.as_deref(),
+            )]));
+            vec![line]
+        }
+        bgp::Message::Keepalive => vec!["[bgp] KEEPALIVE".to_string()],
+        bgp::Message::RouteRefresh { afi, safi } => {
+            vec![format!("[bgp] ROUTE-REFRESH {afi}/{safi}")]
+        }
+        bgp::Message::Unknown(kind, data) => {
+            vec![format!("[bgp] type {} {:?}", kind, data.as_bstr())]
+        }
+    }
+}
+
+/// Chat log style, the text is escaped because irc uses control characters for formatting
+fn display_irc(message: &irc::Message) -> String {
+    let nick = escape_controls(message.nick().unwrap_or_default());
+    // some servers send the channel of a join as trailing parameter
+    let target = escape_controls(
+        message
+            .target()
+            .or(message.trailing.as_deref())
+            .unwrap_or_default(),
+    );
+    let text = message.text().unwrap_or_default();
+    let reason = match message.trailing.as_deref() {
+        Some(reason) if !reason.is_empty() => format!(" ({})", escape_controls(reason)),
+        _ => String::new(),
+    };
+
+    match message.command.as_str() {
+        "PRIVMSG" => match text
+            .strip_prefix("\x01ACTION ")
+            .map(|x| x.trim_end_matches('\x01'))
+        {
+            Some(action) => format!("{} * {} {}", target, nick, escape_controls(action)),
+            None => format!("{} <{}> {}", target, nick, escape_controls(text)),
+        },
+        "NOTICE" => format!("{} -{}- {}", target, nick, escape_controls(text)),
+        "JOIN" => format!("{} --> {} joined", target, nick),
+        "PART" => format!("{} <-- {} left{}", target, nick, reason),
+        "QUIT" => format!("<-- {} quit{}", nick, reason),
+        "NICK" if message.prefix.is_some() => format!(
+            "{} is now known as {}",
+            nick,
+            escape_controls(message.target().or(message.text()).unwrap_or_default())
+        ),
+        _ => escape_controls(&message.line()),
+    }
+}
+
+fn escape_controls(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_control() {
+                c.escape_default().to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+fn display_data_port(data_port: &Option<ftp::DataPort>) -> String {
+    let Some(data_port) = data_port else {
+        return String::new();
+    };
+    let data = match data_port.ip {
+        Some(ip) => SocketAddr::new(ip, data_port.port).to_string(),
+        None => data_port.port.to_string(),
+    };
+    display_kv_list(&[("data", Some(&data))])
+}
+
+/// One line per algorithm list, offering weak algorithms is highlighted
+fn display_kex_init(out: &mut String, offset: usize, kex_init: &ssh::KexInit) -> Color {
+    let weak = kex_init.weak.join(",");
+    out.push_str("[ssh] KexInit");
+    out.push_str(&display_kv_list(&[
+        (
+            if kex_init.server {
+                "hassh_server"
+            } else {
+                "hassh"
+            },
+            Some(&kex_init.hassh),
+        ),
+        ("weak", Some(weak.as_str()).filter(|x| !x.is_empty())),
+    ]));
+
+    let (encryption, mac, compression) = if kex_init.server {
+        (
+            &kex_init.encryption_server_to_client,
+            &kex_init.mac_server_to_client,
+            &kex_init.compression_server_to_client,
+        )
+    } else {
+        (
+            &kex_init.encryption_client_to_server,
+            &kex_init.mac_client_to_server,
+            &kex_init.compression_client_to_server,
+        )
+    };
+    for (key, list) in [
+        ("kex", &kex_init.kex_algorithms),
+        ("host_key", &kex_init.host_key_algorithms),
+        ("encryption", encryption),
+        ("mac", mac),
+        ("compression", compression),
+    ] {
+        out.push_str(&align(offset, &format!("{key}: {:?}", list.join(","))));
+    }
+
+    if kex_init.weak.is_empty() {
+        Color::Green
+    } else {
+        Color::Yellow
+    }
+}
+
 struct DhcpKvListWriter<'a> {
     elements: Vec<(&'a str, String)>,
 }
@@ -730,3 +2333,91 @@ impl<'a> DhcpKvListWriter<'a> {
             .map_or_else(String::new, |extra| format!(" ({})", extra))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::tcp::TCP;
+    use crate::structs::tls::{ClientHello, TLS};
+    use pktparse::ip::IPProtocol;
+    use pktparse::ipv4::IPv4Header;
+    use pktparse::tcp::TcpHeader;
+    use std::net::Ipv4Addr;
+
+    fn client_hello(server: Ipv4Addr, hostname: &str) -> Raw {
+        let client_hello = ClientHello {
+            version: Some("tls1.2"),
+            session_id: None,
+            hostname: Some(hostname.to_string()),
+            alpn: Vec::new(),
+            supported_versions: Vec::new(),
+            groups: Vec::new(),
+            key_shares: Vec::new(),
+            signature_algorithms: Vec::new(),
+            ech: false,
+            grease: false,
+            resumption: None,
+            ja3: String::new(),
+            ja4: String::new(),
+        };
+        Raw::Tun(Ether::IPv4(
+            IPv4Header {
+                version: 4,
+                ihl: 5,
+                tos: 0,
+                length: 0,
+                id: 0,
+                flags: 0,
+                fragment_offset: 0,
+                ttl: 64,
+                protocol: IPProtocol::TCP,
+                chksum: 0,
+                source_addr: "192.168.1.23".parse().unwrap(),
+                dest_addr: server,
+            },
+            ipv4::IPv4::TCP(
+                TcpHeader {
+                    source_port: 40000,
+                    dest_port: 443,
+                    sequence_no: 0,
+                    ack_no: 0,
+                    data_offset: 5,
+                    reserved: 0,
+                    flag_urg: false,
+                    flag_ack: true,
+                    flag_psh: true,
+                    flag_rst: false,
+                    flag_syn: false,
+                    flag_fin: false,
+                    window: 0,
+                    checksum: 0,
+                    urgent_pointer: 0,
+                    options: None,
+                },
+                TCP::TLS(TLS::ClientHello(client_hello)),
+                Vec::new(),
+            ),
+        ))
+    }
+
+    #[test]
+    fn escape_learned_names() {
+        let mut format = Format::new(
+            Layout::Compact,
+            false,
+            true,
+            Arc::new(osfp::Database::default()),
+            Fingerprints::default(),
+            None,
+        );
+        let server = Ipv4Addr::new(192, 0, 2, 1);
+        format.learn(
+            SystemTime::UNIX_EPOCH,
+            &client_hello(server, "\x1b]0;owned\x07example.com"),
+        );
+        assert_eq!(
+            format.display_socket(server, IpAddr::V4(server), 443),
+            "192.0.2.1:443 (\\u{1b}]0;owned\\u{7}example.com)"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 #![allow(clippy::upper_case_acronyms)]
 
+pub mod analysis;
+pub mod bounded;
 pub mod centrifuge;
 pub mod errors;
 pub mod link;
@@ -103,6 +105,7 @@ mod tests {
                         options: None,
                     },
                     Text(String::from_utf8(HTML.to_vec()).unwrap()),
+                    Vec::new(),
                 ),
             ),
         ));
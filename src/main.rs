@@ -1,16 +1,21 @@
 mod cli;
 mod fmt;
+mod redact;
 
 use crate::cli::Args;
 use clap::{CommandFactory, Parser};
 use env_logger::Env;
+use sniffglue::analysis;
 use sniffglue::centrifuge;
 use sniffglue::errors::*;
 use sniffglue::link::DataLink;
 use sniffglue::sandbox;
 use sniffglue::sniff;
 use sniffglue::structs;
+use std::collections::BTreeMap;
 use std::io::{self, IsTerminal, stdout};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, mpsc};
 use std::thread;
 
@@ -41,8 +46,43 @@ fn main() -> Result<()> {
         fmt::Layout::Compact
     };
 
+    let mut os_signatures = analysis::osfp::Database::default();
+    if let Some(path) = &args.os_signatures {
+        os_signatures.load(path)?;
+    }
+    let os_signatures = Arc::new(os_signatures);
+    let fingerprints = match &args.tls_fingerprints {
+        Some(path) => analysis::fingerprints::Fingerprints::load(path)?,
+        None => analysis::fingerprints::Fingerprints::default(),
+    };
+
+    let certificate_dir = args
+        .dump_certificates
+        .as_deref()
+        .map(sandbox::output::OutputDir::open)
+        .transpose()?
+        .map(Arc::new);
+    let object_dir = args
+        .dump_http_objects
+        .as_deref()
+        .map(sandbox::output::OutputDir::open)
+        .transpose()?
+        .map(Arc::new);
+
+    let redact = (args.redact || args.redact_addresses)
+        .then(|| redact::Redact::new(args.redact_addresses))
+        .transpose()?;
+
     let colors = io::stdout().is_terminal();
-    let config = fmt::Config::new(layout, args.verbose, colors);
+    let config = fmt::Config::new(
+        layout,
+        args.verbose,
+        colors,
+        args.passive_dns,
+        os_signatures.clone(),
+        fingerprints,
+        redact,
+    );
 
     let cap = if args.read {
         if args.threads.is_none() {
@@ -70,35 +110,70 @@ fn main() -> Result<()> {
         cap
     };
 
+    let mut analysis = analysis::Analysis::new(&analysis::Config {
+        arp_watch: args.arp_watch,
+        rogue_watch: args.rogue_watch,
+        flows: args.flows,
+        http_log: args.http_log,
+        dns_log: args.dns_log,
+        inventory: args.inventory,
+        credentials: args.credentials,
+        os_signatures,
+        certificate_dir: certificate_dir.clone(),
+        object_dir: object_dir.clone(),
+    });
+    let print_packets = analysis.prints_packets();
+
+    if analysis.reports_on_exit() {
+        cap.stop_on_interrupt()?;
+    }
+
     let threads = args.threads.unwrap_or_else(num_cpus::get);
     debug!("Using {} threads", threads);
 
     let datalink = DataLink::from_linktype(cap.datalink())?;
 
     let filter = config.filter();
+    let mut format = config.format();
     let (tx, rx) = mpsc::sync_channel(256);
     let cap = Arc::new(Mutex::new(cap));
-
-    sandbox::activate_stage2(args.insecure_disable_seccomp)
+    let counter = Arc::new(AtomicU64::new(0));
+
+    let output_dirs = certificate_dir
+        .iter()
+        .chain(object_dir.iter())
+        .map(|dir| &**dir)
+        .collect::<Vec<_>>();
+    centrifuge::x509::init();
+    sandbox::activate_stage2(args.insecure_disable_seccomp, &output_dirs)
         .context("Failed to init sandbox stage2")?;
 
     for _ in 0..threads {
         let cap = cap.clone();
         let datalink = datalink.clone();
-        let filter = filter.clone();
         let tx = tx.clone();
+        let counter = counter.clone();
         thread::spawn(move || {
             loop {
                 let packet = {
                     let mut cap = cap.lock().unwrap();
-                    cap.next_pkt()
+                    // numbered while the capture is locked, so the original order can be restored
+                    cap.next_pkt().map(|packet| {
+                        packet.map(|packet| (counter.fetch_add(1, Ordering::Relaxed), packet))
+                    })
                 };
 
-                if let Ok(Some(packet)) = packet {
-                    let packet = centrifuge::parse(&datalink, &packet.data);
-                    if filter.matches(&packet) {
-                        tx.send(packet).unwrap()
-                    }
+                if let Ok(Some((index, packet))) = packet {
+                    let timestamp = packet.timestamp;
+                    // a packet that crashes the parser still takes its place in the order,
+                    // otherwise every packet after it would wait for it forever
+                    let packet = panic::catch_unwind(AssertUnwindSafe(|| {
+                        centrifuge::parse(&datalink, &packet.data)
+                    }))
+                    .map_err(|_| warn!("Failed to parse packet #{}, skipping", index))
+                    .ok();
+                    // packets are filtered after the stateful parsers have seen them
+                    tx.send((index, timestamp, packet)).unwrap()
                 } else {
                     debug!("End of packet stream, shutting down reader thread");
                     break;
@@ -108,9 +183,39 @@ fn main() -> Result<()> {
     }
     drop(tx);
 
-    let format = config.format();
-    for packet in rx.iter() {
-        format.print(packet);
+    // parsers that follow a connection need the packets in the order they were captured
+    let mut quic = centrifuge::quic::Reassembly::default();
+    let mut ftp = centrifuge::ftp::Expectations::default();
+    let mut telnet = centrifuge::telnet::Sessions::default();
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+    for (index, timestamp, packet) in rx.iter() {
+        pending.insert(index, (timestamp, packet));
+        while let Some((timestamp, packet)) = pending.remove(&next) {
+            next += 1;
+            let Some(mut packet) = packet else { continue };
+
+            if let Some(initial) = centrifuge::quic::incomplete(&mut packet) {
+                quic.reassemble(timestamp, initial);
+            }
+            if let Some((src, dst, tcp)) = centrifuge::tcp::connection(&mut packet) {
+                ftp.track(timestamp, src, dst, tcp);
+                telnet.track(timestamp, src, dst, tcp);
+            }
+            analysis::certificates::check_expiry(timestamp, &mut packet);
+            for event in analysis.process(timestamp, &packet) {
+                format.print_event(event);
+            }
+            format.learn(timestamp, &packet);
+
+            if print_packets && filter.matches(&packet) {
+                format.print(packet);
+            }
+        }
+    }
+
+    for event in analysis.finish() {
+        format.print_event(event);
     }
 
     Ok(())
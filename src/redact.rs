@@ -0,0 +1,802 @@
+use crate::analysis::credentials::{self, PASSWORD_FIELDS};
+use crate::analysis::{self, Alert, Event};
+use crate::centrifuge::irc;
+use crate::structs::arp::ARP;
+use crate::structs::bgp;
+use crate::structs::dhcp::DHCP;
+use crate::structs::dns::{self, DNS, Record};
+use crate::structs::ether::Ether;
+use crate::structs::ftp::{self, FTP};
+use crate::structs::http::{Http, Request, Response};
+use crate::structs::icmpv6::{self, ICMPv6};
+use crate::structs::ipv4::IPv4;
+use crate::structs::ipv6::IPv6;
+use crate::structs::lacp;
+use crate::structs::llc::LLC;
+use crate::structs::mail::Mail;
+use crate::structs::nbns::NBNS;
+use crate::structs::postgres;
+use crate::structs::raw::Raw;
+use crate::structs::redis::Redis;
+use crate::structs::stp::BPDU;
+use crate::structs::tcp::TCP;
+use crate::structs::telnet::Prompt;
+use crate::structs::tls::TLS;
+use crate::structs::udp::UDP;
+use crate::structs::{dhcp, tls};
+use bstr::{BString, ByteSlice};
+use data_encoding::{BASE64, HEXLOWER};
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+use sniffglue::errors::*;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Query string and form fields that are masked in addition to the password fields
+const TOKEN_FIELDS: &[&str] = &[
+    "token",
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "api_key",
+    "apikey",
+    "session",
+    "sessionid",
+    "sid",
+];
+
+/// Replaces secrets and optionally addresses with pseudonyms before anything is printed
+///
+/// The pseudonyms are derived from a random key, the same value always gets the same
+/// pseudonym during a run so packets and flows can still be correlated.
+pub struct Redact {
+    key: [u8; 32],
+    addresses: bool,
+}
+
+impl Redact {
+    pub fn new(addresses: bool) -> Result<Redact> {
+        let mut key = [0; 32];
+        getrandom::fill(&mut key).map_err(|err| anyhow!("Failed to generate key: {}", err))?;
+        Ok(Redact { key, addresses })
+    }
+
+    fn digest(&self, domain: &[u8], data: &[u8]) -> [u8; 32] {
+        let mut mac = <Hmac<Sha256> as KeyInit>::new_from_slice(&self.key).unwrap();
+        mac.update(domain);
+        mac.update(&[0]);
+        mac.update(data);
+        mac.finalize().into_bytes().into()
+    }
+
+    fn secret(&self, data: &[u8]) -> String {
+        let digest = self.digest(b"secret", data);
+        format!("[redacted:{}]", HEXLOWER.encode(&digest[..4]))
+    }
+
+    fn name(&self, name: &mut String) {
+        let digest = self.digest(b"name", name.as_bytes());
+        *name = format!("host-{}", HEXLOWER.encode(&digest[..4]));
+    }
+
+    fn mac(&self, mac: &mut [u8; 6]) {
+        // broadcast and multicast addresses don't identify a host
+        if !self.addresses || mac[0] & 1 == 1 || *mac == [0; 6] {
+            return;
+        }
+        let digest = self.digest(b"mac", mac);
+        mac.copy_from_slice(&digest[..6]);
+        // locally administered unicast
+        mac[0] = (mac[0] & 0xfc) | 0x02;
+    }
+
+    fn ipv4(&self, addr: &mut Ipv4Addr) {
+        if !self.addresses
+            || addr.is_unspecified()
+            || addr.is_loopback()
+            || addr.is_multicast()
+            || addr.is_broadcast()
+        {
+            return;
+        }
+        let digest = self.digest(b"ipv4", &addr.octets());
+        *addr = Ipv4Addr::new(10, digest[0], digest[1], digest[2]);
+    }
+
+    fn ipv6(&self, addr: &mut Ipv6Addr) {
+        if !self.addresses || addr.is_unspecified() || addr.is_loopback() || addr.is_multicast() {
+            return;
+        }
+        let digest = self.digest(b"ipv6", &addr.octets());
+        let mut octets = [0; 16];
+        if addr.is_unicast_link_local() {
+            octets[..2].copy_from_slice(&[0xfe, 0x80]);
+            octets[8..].copy_from_slice(&digest[..8]);
+        } else {
+            octets[0] = 0xfd;
+            octets[1..].copy_from_slice(&digest[..15]);
+        }
+        *addr = Ipv6Addr::from(octets);
+    }
+
+    fn ip(&self, addr: &mut IpAddr) {
+        match addr {
+            IpAddr::V4(addr) => self.ipv4(addr),
+            IpAddr::V6(addr) => self.ipv6(addr),
+        }
+    }
+
+    fn socket(&self, addr: &mut SocketAddr) {
+        let mut ip = addr.ip();
+        self.ip(&mut ip);
+        addr.set_ip(ip);
+    }
+
+    pub fn packet(&self, packet: &mut Raw) {
+        match packet {
+            Raw::Ether(frame, ether) => {
+                self.mac(&mut frame.source_mac.0);
+                self.mac(&mut frame.dest_mac.0);
+                self.ether(ether);
+            }
+            Raw::Tun(ether) | Raw::Sll(ether) => self.ether(ether),
+            Raw::Unknown(_) => (),
+        }
+    }
+
+    fn ether(&self, ether: &mut Ether) {
+        match ether {
+            Ether::Arp(ARP::Request(arp) | ARP::Reply(arp)) => {
+                self.mac(&mut arp.src_mac.0);
+                self.mac(&mut arp.dest_mac.0);
+                self.ipv4(&mut arp.src_addr);
+                self.ipv4(&mut arp.dest_addr);
+            }
+            Ether::IPv4(header, ipv4) => {
+                self.ipv4(&mut header.source_addr);
+                self.ipv4(&mut header.dest_addr);
+                match ipv4 {
+                    IPv4::TCP(header, tcp, _) => self.tcp(header.dest_port, tcp),
+                    IPv4::UDP(_, udp) => self.udp(udp),
+                    _ => (),
+                }
+            }
+            Ether::IPv6(header, ipv6) => {
+                self.ipv6(&mut header.source_addr);
+                self.ipv6(&mut header.dest_addr);
+                match ipv6 {
+                    IPv6::TCP(header, tcp, _) => self.tcp(header.dest_port, tcp),
+                    IPv6::UDP(_, udp) => self.udp(udp),
+                    IPv6::ICMPv6(icmp) => self.icmpv6(icmp),
+                    IPv6::Unknown(_) => (),
+                }
+            }
+            Ether::Llc(LLC::STP(BPDU::Config(bpdu) | BPDU::RST(bpdu))) => {
+                self.mac(&mut bpdu.root.mac);
+                self.mac(&mut bpdu.bridge.mac);
+            }
+            Ether::Lacp(lacp) => self.lacp(lacp),
+            _ => (),
+        }
+    }
+
+    fn lacp(&self, lacp: &mut lacp::LACP) {
+        self.mac(&mut lacp.actor.system);
+        self.mac(&mut lacp.partner.system);
+    }
+
+    fn icmpv6(&self, icmp: &mut ICMPv6) {
+        match icmp {
+            ICMPv6::NeighborSolicitation(addr) | ICMPv6::NeighborAdvertisement(addr) => {
+                self.ipv6(addr)
+            }
+            ICMPv6::RouterAdvertisement(ra) => {
+                if let Some(mac) = &mut ra.source_mac {
+                    self.mac(mac);
+                }
+                self.prefixes(&mut ra.prefixes);
+                ra.dns.iter_mut().for_each(|addr| self.ipv6(addr));
+            }
+            _ => (),
+        }
+    }
+
+    fn prefixes(&self, prefixes: &mut [icmpv6::Prefix]) {
+        for prefix in prefixes {
+            self.ipv6(&mut prefix.prefix);
+        }
+    }
+
+    fn tcp(&self, dest_port: u16, tcp: &mut TCP) {
+        match tcp {
+            TCP::HTTP(Http::Request(request)) => self.http_request(request),
+            TCP::HTTP(Http::Response(response)) => self.http_response(response),
+            TCP::TLS(TLS::ClientHello(hello)) => self.client_hello(hello),
+            TCP::TLS(TLS::ServerHello(hello)) => {
+                if let Some(session_id) = &mut hello.session_id {
+                    *session_id = self.secret(session_id.as_bytes());
+                }
+            }
+            TCP::FTP(FTP::Command(command)) => self.ftp_command(command),
+            TCP::FTP(FTP::Reply(reply)) => self.ftp_reply(reply),
+            TCP::Mail(Mail::Commands(_, commands)) => {
+                for command in commands {
+                    let line = command.line();
+                    if let Some(redacted) = self.command(&line) {
+                        let prefix = line.len() - command.argument.as_ref().map_or(0, |x| x.len());
+                        command.argument = Some(redacted[prefix..].to_string());
+                    }
+                }
+            }
+            TCP::IRC(messages) => {
+                for message in messages {
+                    if let Some(redacted) = self
+                        .command(&message.line())
+                        .and_then(|line| irc::message(&line))
+                    {
+                        *message = redacted;
+                    }
+                }
+            }
+            TCP::BGP(messages) => {
+                for message in messages {
+                    match message {
+                        bgp::Message::Open(open) => self.ipv4(&mut open.router_id),
+                        bgp::Message::Update(update) => {
+                            if let Some(next_hop) = &mut update.next_hop {
+                                self.ipv4(next_hop);
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            TCP::Postgres(messages) => {
+                for message in messages {
+                    if let postgres::Message::Password(password) = message {
+                        *password = self.secret(password.as_bytes());
+                    }
+                }
+            }
+            TCP::Redis(Redis::Commands(commands)) => {
+                for command in commands {
+                    self.redis_command(&mut command.args);
+                }
+            }
+            // keystrokes are masked one by one, a pseudonym for each of them could be counted
+            TCP::Telnet(telnet) if telnet.input == Some(Prompt::Password) => {
+                telnet.text = telnet
+                    .text
+                    .chars()
+                    .map(|c| if c.is_control() { c } else { '*' })
+                    .collect();
+            }
+            TCP::Text(text) if credentials::service(dest_port).is_some_and(|x| x != "telnet") => {
+                *text = self.commands(text);
+            }
+            _ => (),
+        }
+    }
+
+    fn udp(&self, udp: &mut UDP) {
+        match udp {
+            UDP::DHCP(dhcp) => self.dhcp(dhcp),
+            UDP::DNS(DNS::Response(response)) => self.dns_answers(&mut response.answers),
+            UDP::NBNS(
+                NBNS::Response(_, addrs)
+                | NBNS::Registration(_, addrs)
+                | NBNS::Release(_, addrs)
+                | NBNS::Refresh(_, addrs),
+            ) => addrs.iter_mut().for_each(|addr| self.ipv4(addr)),
+            UDP::QUIC(quic) => {
+                if let Some(hello) = &mut quic.client_hello {
+                    self.client_hello(hello);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn dhcp(&self, dhcp: &mut DHCP) {
+        let packet: &mut dhcp::Packet = match dhcp {
+            DHCP::ACK(packet)
+            | DHCP::DECLINE(packet)
+            | DHCP::DISCOVER(packet)
+            | DHCP::INFORM(packet)
+            | DHCP::NAK(packet)
+            | DHCP::OFFER(packet)
+            | DHCP::RELEASE(packet)
+            | DHCP::REQUEST(packet)
+            | DHCP::UNKNOWN(packet) => packet,
+        };
+        self.ipv4(&mut packet.ciaddr);
+        self.ipv4(&mut packet.yiaddr);
+        self.ipv4(&mut packet.siaddr);
+        self.mac(&mut packet.chaddr);
+        if let Some(hostname) = &mut packet.hostname {
+            self.name(hostname);
+        }
+        for addr in [
+            &mut packet.server_identifier,
+            &mut packet.requested_ip_address,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            self.ipv4(addr);
+        }
+        for addrs in [&mut packet.router, &mut packet.domain_name_server]
+            .into_iter()
+            .flatten()
+        {
+            addrs.iter_mut().for_each(|addr| self.ipv4(addr));
+        }
+    }
+
+    fn dns_answers(&self, answers: &mut [(String, dns::Record)]) {
+        for (_, record) in answers {
+            match record {
+                Record::A(addr) => self.ipv4(addr),
+                Record::AAAA(addr) => self.ipv6(addr),
+                _ => (),
+            }
+        }
+    }
+
+    fn client_hello(&self, hello: &mut tls::ClientHello) {
+        if let Some(session_id) = &mut hello.session_id {
+            *session_id = self.secret(session_id.as_bytes());
+        }
+    }
+
+    fn http_request(&self, request: &mut Request) {
+        self.headers(&mut request.headers);
+        request.path = self.path(&request.path);
+        if let Some(auth) = &mut request.auth {
+            *auth = self.authorization(auth);
+        }
+        if let Some(cookies) = &mut request.cookies {
+            *cookies = self.cookies(cookies);
+        }
+
+        let form = request.decoded().is_some_and(|body| {
+            body.content_type.as_deref().is_some_and(|x| {
+                x.to_ascii_lowercase()
+                    .starts_with("application/x-www-form-urlencoded")
+            })
+        });
+        if form && let Some(Some(body)) = request.decoded.get_mut() {
+            body.data = self.query(&body.data.to_str_lossy()).into();
+            request.body = Some(body.data.clone());
+        }
+    }
+
+    fn http_response(&self, response: &mut Response) {
+        self.headers(&mut response.headers);
+    }
+
+    fn headers(&self, headers: &mut [(String, BString)]) {
+        for (key, value) in headers {
+            let value_str = value.to_str_lossy();
+            let redacted = match key.to_ascii_lowercase().as_str() {
+                "authorization" | "proxy-authorization" => self.authorization(&value_str),
+                "cookie" => self.cookies(&value_str),
+                "set-cookie" => {
+                    // only the first pair is the cookie, the rest are attributes
+                    match value_str.split_once(';') {
+                        Some((cookie, attributes)) => {
+                            format!("{};{}", self.cookies(cookie), attributes)
+                        }
+                        None => self.cookies(&value_str),
+                    }
+                }
+                "x-api-key" | "x-auth-token" | "x-csrf-token" | "x-xsrf-token" => {
+                    self.secret(value)
+                }
+                "referer" | "location" => self.path(&value_str),
+                _ => continue,
+            };
+            *value = redacted.into();
+        }
+    }
+
+    /// Keep the scheme, eg. `Basic [redacted:...]`
+    fn authorization(&self, value: &str) -> String {
+        value
+            .split("; ")
+            .map(|value| match value.trim().split_once(' ') {
+                Some((scheme, param)) => format!("{} {}", scheme, self.secret(param.as_bytes())),
+                None => self.secret(value.as_bytes()),
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Keep the names of the cookies, eg. `session=[redacted:...]`
+    fn cookies(&self, value: &str) -> String {
+        value
+            .split(';')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, value)) => format!("{}={}", name, self.secret(value.as_bytes())),
+                None => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    fn path(&self, path: &str) -> String {
+        match path.split_once('?') {
+            Some((path, query)) => format!("{}?{}", path, self.query(query)),
+            None => path.to_string(),
+        }
+    }
+
+    /// Mask the values of password and token fields
+    fn query(&self, query: &str) -> String {
+        query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => {
+                    let name = key.to_ascii_lowercase();
+                    if PASSWORD_FIELDS.contains(&name.as_str())
+                        || TOKEN_FIELDS.contains(&name.as_str())
+                    {
+                        format!("{}={}", key, self.secret(value.as_bytes()))
+                    } else {
+                        pair.to_string()
+                    }
+                }
+                None => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Mask the arguments of login commands of ftp, pop3, imap, smtp and irc
+    fn ftp_command(&self, command: &mut ftp::Command) {
+        match command.command.as_str() {
+            "PASS" | "ACCT" => {
+                if let Some(argument) = &mut command.argument {
+                    *argument = self.secret(argument.as_bytes());
+                }
+            }
+            "PORT" | "EPRT" => {
+                if self.addresses
+                    && let Some(data_port) = &mut command.data_port
+                    && let Some(ip) = &mut data_port.ip
+                {
+                    self.ip(ip);
+                    command.argument = Some(match (command.command.as_str(), ip) {
+                        ("PORT", IpAddr::V4(ip)) => port_argument(*ip, data_port.port),
+                        (_, IpAddr::V4(ip)) => format!("|1|{}|{}|", ip, data_port.port),
+                        (_, IpAddr::V6(ip)) => format!("|2|{}|{}|", ip, data_port.port),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn ftp_reply(&self, reply: &mut ftp::Reply) {
+        if self.addresses
+            && let Some(data_port) = &mut reply.data_port
+            && let Some(IpAddr::V4(ip)) = &mut data_port.ip
+        {
+            self.ipv4(ip);
+            reply.lines = vec![format!(
+                "Entering Passive Mode ({})",
+                port_argument(*ip, data_port.port)
+            )];
+        }
+    }
+
+    /// The password of `AUTH` and `HELLO` is the last argument after the optional username
+    fn redis_command(&self, args: &mut [Vec<u8>]) {
+        let upper = |x: &[u8]| x.to_ascii_uppercase();
+        let password = match args.first().map(|x| upper(x)).as_deref() {
+            Some(b"AUTH") if args.len() <= 3 => args.len() - 1,
+            Some(b"HELLO") => match args.iter().position(|x| upper(x) == b"AUTH") {
+                Some(i) if i + 2 < args.len() => i + 2,
+                _ => return,
+            },
+            _ => return,
+        };
+        if password > 0 {
+            args[password] = self.secret(&args[password]).into_bytes();
+        }
+    }
+
+    fn commands(&self, text: &str) -> String {
+        text.split_inclusive('\n')
+            .map(|line| {
+                let content = line.trim_end_matches(['\r', '\n']);
+                let ending = &line[content.len()..];
+                match self.command(content) {
+                    Some(redacted) => redacted + ending,
+                    None => line.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    fn command(&self, line: &str) -> Option<String> {
+        let words = line.split(' ').collect::<Vec<_>>();
+        let upper = words
+            .iter()
+            .map(|x| x.to_ascii_uppercase())
+            .collect::<Vec<_>>();
+        let upper = upper.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+
+        // keep the first `n` words
+        let keep = |n: usize| {
+            let (keep, secret) = words.split_at(n.min(words.len()));
+            let secret = secret.join(" ");
+            let mut out = keep.join(" ");
+            if !secret.is_empty() {
+                if !out.is_empty() {
+                    out.push(' ');
+                }
+                out.push_str(&self.secret(secret.as_bytes()));
+            }
+            Some(out)
+        };
+
+        match upper.as_slice() {
+            ["PASS", ..] => keep(1),
+            ["APOP", ..] => keep(2),
+            [_, "LOGIN", ..] => keep(3),
+            ["AUTH", _, ..] => keep(2),
+            [_, "AUTHENTICATE", _, ..] => keep(3),
+            ["OPER", ..] => keep(2),
+            ["PRIVMSG", "NICKSERV", identify, ..]
+                if identify.trim_start_matches(':') == "IDENTIFY" =>
+            {
+                keep(3)
+            }
+            ["NICKSERV" | "NS", "IDENTIFY", ..] => keep(2),
+            // continuation of a sasl exchange
+            [_] if is_base64_response(words[0]) => keep(0),
+            _ => None,
+        }
+    }
+
+    pub fn event(&self, event: &mut Event) {
+        match event {
+            Event::Alert(Alert::Arp(alert)) => match alert {
+                analysis::arp::Alert::MappingChanged { ip, old, new } => {
+                    self.ipv4(ip);
+                    self.mac(old);
+                    self.mac(new);
+                }
+                analysis::arp::Alert::IpConflict { ip, macs } => {
+                    self.ipv4(ip);
+                    macs.iter_mut().for_each(|mac| self.mac(mac));
+                }
+                analysis::arp::Alert::GratuitousFlood { mac, .. } => self.mac(mac),
+            },
+            Event::Alert(Alert::Dns(alert)) => match alert {
+                analysis::dns::Alert::WrongSource {
+                    client,
+                    server,
+                    source,
+                    ..
+                } => {
+                    self.socket(client);
+                    self.socket(server);
+                    self.socket(source);
+                }
+                analysis::dns::Alert::QuestionMismatch { client, server, .. } => {
+                    self.socket(client);
+                    self.socket(server);
+                }
+                analysis::dns::Alert::RacingReply {
+                    client,
+                    server,
+                    first,
+                    second,
+                    ..
+                } => {
+                    self.socket(client);
+                    self.socket(server);
+                    self.dns_answers(first);
+                    self.dns_answers(second);
+                }
+            },
+            Event::Alert(Alert::Rogue(alert)) => self.rogue(alert),
+            Event::Flow(record) => {
+                self.socket(&mut record.flow.client);
+                self.socket(&mut record.flow.server);
+            }
+            Event::FlowSummary(_) => (),
+            Event::Http(transaction) => {
+                self.socket(&mut transaction.client);
+                self.socket(&mut transaction.server);
+                transaction.path = self.path(&transaction.path);
+            }
+            Event::Dns(transaction) => {
+                self.socket(&mut transaction.client);
+                self.socket(&mut transaction.server);
+                self.dns_answers(&mut transaction.answers);
+            }
+            Event::Inventory(report) => {
+                for host in &mut report.hosts {
+                    if let Some(mac) = &mut host.mac {
+                        self.mac(mac);
+                    }
+                    host.ips = std::mem::take(&mut host.ips)
+                        .into_iter()
+                        .map(|mut ip| {
+                            self.ip(&mut ip);
+                            ip
+                        })
+                        .collect();
+                    host.hostnames = std::mem::take(&mut host.hostnames)
+                        .into_iter()
+                        .map(|mut name| {
+                            self.name(&mut name);
+                            name
+                        })
+                        .collect();
+                }
+            }
+            Event::Credential(credential) => {
+                self.socket(&mut credential.client);
+                self.socket(&mut credential.server);
+                if let Some(secret) = &mut credential.secret {
+                    *secret = self.secret(secret.as_bytes());
+                }
+            }
+        }
+    }
+
+    fn rogue(&self, alert: &mut analysis::rogue::Alert) {
+        use analysis::rogue::Alert::*;
+        let ipv4s = |addrs: &mut Option<Vec<Ipv4Addr>>| {
+            addrs.iter_mut().flatten().for_each(|addr| self.ipv4(addr))
+        };
+        match alert {
+            DhcpServer {
+                server,
+                mac,
+                router,
+                dns,
+            } => {
+                self.ipv4(server);
+                if let Some(mac) = mac {
+                    self.mac(mac);
+                }
+                ipv4s(router);
+                ipv4s(dns);
+            }
+            DhcpOptions {
+                server,
+                router,
+                dns,
+                expected_router,
+                expected_dns,
+            } => {
+                self.ipv4(server);
+                ipv4s(router);
+                ipv4s(dns);
+                ipv4s(expected_router);
+                ipv4s(expected_dns);
+            }
+            Router {
+                router,
+                mac,
+                prefixes,
+                dns,
+            } => {
+                self.ipv6(router);
+                if let Some(mac) = mac {
+                    self.mac(mac);
+                }
+                self.prefixes(prefixes);
+                dns.iter_mut().for_each(|addr| self.ipv6(addr));
+            }
+            RouterOptions {
+                router,
+                prefixes,
+                dns,
+                expected_prefixes,
+                expected_dns,
+            } => {
+                self.ipv6(router);
+                self.prefixes(prefixes);
+                self.prefixes(expected_prefixes);
+                dns.iter_mut().for_each(|addr| self.ipv6(addr));
+                expected_dns.iter_mut().for_each(|addr| self.ipv6(addr));
+            }
+        }
+    }
+}
+
+/// Base64 lines that are sent during sasl authentication, commands like QUIT are only letters
+fn is_base64_response(word: &str) -> bool {
+    word.len() >= 4
+        && BASE64.decode(word.as_bytes()).is_ok()
+        && (!word.chars().all(|c| c.is_ascii_alphabetic())
+            || (word.chars().any(|c| c.is_ascii_lowercase())
+                && word.chars().any(|c| c.is_ascii_uppercase())))
+}
+
+/// The `h1,h2,h3,h4,p1,p2` notation of ftp
+fn port_argument(ip: Ipv4Addr, port: u16) -> String {
+    let [h1, h2, h3, h4] = ip.octets();
+    format!("{},{},{},{},{},{}", h1, h2, h3, h4, port >> 8, port & 0xff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redact(addresses: bool) -> Redact {
+        Redact {
+            key: [0x42; 32],
+            addresses,
+        }
+    }
+
+    #[test]
+    fn login_commands() {
+        let redact = redact(false);
+        let secret = redact.secret(b"hunter2");
+        assert_eq!(
+            redact.commands("USER alice\r\nPASS hunter2\r\n"),
+            format!("USER alice\r\nPASS {secret}\r\n")
+        );
+        assert_eq!(
+            redact.commands("a1 LOGIN alice hunter2\r\n"),
+            format!("a1 LOGIN alice {secret}\r\n")
+        );
+        assert_eq!(redact.commands("QUIT\r\n"), "QUIT\r\n");
+        assert_eq!(
+            redact.commands("aHVudGVyMg==\r\n"),
+            format!("{}\r\n", redact.secret(b"aHVudGVyMg=="))
+        );
+    }
+
+    #[test]
+    fn http_headers() {
+        let redact = redact(false);
+        let mut headers = vec![
+            ("Host".to_string(), BString::from("example.com")),
+            ("Cookie".to_string(), BString::from("a=1; b=2")),
+            ("Authorization".to_string(), BString::from("Bearer abc")),
+        ];
+        redact.headers(&mut headers);
+        assert_eq!(headers[0].1, "example.com");
+        assert_eq!(
+            headers[1].1,
+            format!("a={}; b={}", redact.secret(b"1"), redact.secret(b"2"))
+        );
+        assert_eq!(headers[2].1, format!("Bearer {}", redact.secret(b"abc")));
+        assert_eq!(
+            redact.path("/login?user=bob&password=x"),
+            format!("/login?user=bob&password={}", redact.secret(b"x"))
+        );
+    }
+
+    #[test]
+    fn pseudonyms() {
+        let redact = redact(true);
+        let mut a = Ipv4Addr::new(192, 168, 1, 23);
+        let mut b = Ipv4Addr::new(192, 168, 1, 23);
+        let mut broadcast = Ipv4Addr::BROADCAST;
+        redact.ipv4(&mut a);
+        redact.ipv4(&mut b);
+        redact.ipv4(&mut broadcast);
+        assert_eq!(a, b);
+        assert_ne!(a, Ipv4Addr::new(192, 168, 1, 23));
+        assert_eq!(a.octets()[0], 10);
+        assert_eq!(broadcast, Ipv4Addr::BROADCAST);
+
+        let mut mac = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+        redact.mac(&mut mac);
+        assert_ne!(mac, [0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(mac[0] & 0x03, 0x02);
+
+        let mut addr = Ipv4Addr::new(192, 168, 1, 23);
+        Redact::new(false).unwrap().ipv4(&mut addr);
+        assert_eq!(addr, Ipv4Addr::new(192, 168, 1, 23));
+    }
+}
@@ -0,0 +1,88 @@
+use crate::errors::*;
+use nix::errno::Errno;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1;
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+
+const LANDLOCK_ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const LANDLOCK_ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+/// Every filesystem access right of the first landlock abi
+const LANDLOCK_ACCESS_FS_ABI_1: u64 = (1 << 13) - 1;
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+fn check(ret: libc::c_long) -> nix::Result<libc::c_long> {
+    Errno::result(ret)
+}
+
+/// Only allow creating regular files beneath the output directories, paths that resolve to anywhere
+/// else are denied. This is skipped with a warning if the kernel doesn't support landlock.
+pub fn restrict(output_dirs: &[RawFd]) -> Result<()> {
+    let abi = check(unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<RulesetAttr>(),
+            0usize,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        )
+    });
+    match abi {
+        Ok(abi) => debug!("landlock abi version: {}", abi),
+        Err(Errno::ENOSYS | Errno::EOPNOTSUPP) => {
+            warn!(
+                "landlock is not supported by the kernel, output directories are only protected by seccomp"
+            );
+            return Ok(());
+        }
+        Err(err) => return Err(err).context("Failed to query landlock abi version"),
+    }
+
+    let attr = RulesetAttr {
+        handled_access_fs: LANDLOCK_ACCESS_FS_ABI_1,
+    };
+    let ruleset = check(unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &attr as *const RulesetAttr,
+            std::mem::size_of::<RulesetAttr>(),
+            0u32,
+        )
+    })
+    .context("Failed to create landlock ruleset")?;
+    let ruleset = unsafe { OwnedFd::from_raw_fd(ruleset as RawFd) };
+
+    for fd in output_dirs {
+        let rule = PathBeneathAttr {
+            allowed_access: LANDLOCK_ACCESS_FS_WRITE_FILE | LANDLOCK_ACCESS_FS_MAKE_REG,
+            parent_fd: *fd,
+        };
+        check(unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset.as_raw_fd(),
+                LANDLOCK_RULE_PATH_BENEATH,
+                &rule as *const PathBeneathAttr,
+                0u32,
+            )
+        })
+        .context("Failed to add landlock rule for output directory")?;
+    }
+
+    check(unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) }.into())
+        .context("Failed to set no_new_privs")?;
+    check(unsafe { libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0u32) })
+        .context("Failed to enforce landlock ruleset")?;
+
+    info!("landlock is active");
+    Ok(())
+}
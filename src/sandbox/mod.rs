@@ -10,6 +10,9 @@ use nix::unistd::{getgroups, setgroups};
 
 pub mod config;
 #[cfg(target_os = "linux")]
+pub mod landlock;
+pub mod output;
+#[cfg(target_os = "linux")]
 pub mod seccomp;
 
 pub fn activate_stage1(disable_seccomp: bool) -> Result<()> {
@@ -122,7 +125,8 @@ fn apply_config(config: config::Config) -> Result<()> {
     Ok(())
 }
 
-pub fn activate_stage2(disable_seccomp: bool) -> Result<()> {
+/// Files can still be created in the output directories afterwards
+pub fn activate_stage2(disable_seccomp: bool, output_dirs: &[&output::OutputDir]) -> Result<()> {
     let config = if let Some(config_path) = config::find() {
         config::load(&config_path)?
     } else {
@@ -134,7 +138,16 @@ pub fn activate_stage2(disable_seccomp: bool) -> Result<()> {
 
     if !disable_seccomp {
         #[cfg(target_os = "linux")]
-        seccomp::activate_stage2()?;
+        {
+            let output_dirs = output_dirs
+                .iter()
+                .map(|dir| dir.as_raw_fd())
+                .collect::<Vec<_>>();
+            if !output_dirs.is_empty() {
+                landlock::restrict(&output_dirs)?;
+            }
+            seccomp::activate_stage2(&output_dirs)?;
+        }
     }
 
     info!("stage 2/2 is active");
@@ -0,0 +1,53 @@
+use crate::errors::*;
+use nix::errno::Errno;
+use nix::fcntl::{self, OFlag};
+use nix::sys::stat::Mode;
+use std::fs::File;
+use std::io::Write;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::path::Path;
+
+/// A directory that is opened before the sandbox is activated, afterwards files can only be created inside of it
+#[derive(Debug)]
+pub struct OutputDir {
+    fd: OwnedFd,
+}
+
+impl OutputDir {
+    pub fn open(path: &Path) -> Result<OutputDir> {
+        let fd = fcntl::open(
+            path,
+            OFlag::O_RDONLY | OFlag::O_DIRECTORY | OFlag::O_CLOEXEC,
+            Mode::empty(),
+        )
+        .with_context(|| format!("Failed to open output directory: {:?}", path))?;
+        Ok(OutputDir { fd })
+    }
+
+    #[inline]
+    pub fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+
+    /// Create a new file in this directory, returns false if the file already exists
+    pub fn create(&self, name: &str, data: &[u8]) -> Result<bool> {
+        if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+            bail!("Invalid file name for output directory: {:?}", name);
+        }
+
+        let fd = match fcntl::openat(
+            &self.fd,
+            name,
+            OFlag::O_WRONLY | OFlag::O_CREAT | OFlag::O_EXCL | OFlag::O_CLOEXEC,
+            Mode::from_bits_truncate(0o644),
+        ) {
+            Ok(fd) => fd,
+            Err(Errno::EEXIST) => return Ok(false),
+            Err(err) => return Err(err).with_context(|| format!("Failed to create {:?}", name)),
+        };
+        File::from(fd)
+            .write_all(data)
+            .with_context(|| format!("Failed to write {:?}", name))?;
+        Ok(true)
+    }
+}
@@ -1,5 +1,6 @@
 use crate::errors::*;
-use syscallz::{self, Action, Context, Syscall};
+use std::os::fd::RawFd;
+use syscallz::{self, Action, Cmp, Comparator, Context, Syscall};
 
 pub fn activate_stage1() -> Result<()> {
     let mut ctx = Context::init()?;
@@ -134,6 +135,9 @@ pub fn activate_stage1() -> Result<()> {
     ))]
     ctx.allow_syscall(Syscall::newfstatat)?;
     ctx.allow_syscall(Syscall::seccomp)?; // needed for stage2
+    ctx.allow_syscall(Syscall::landlock_create_ruleset)?; // needed for stage2
+    ctx.allow_syscall(Syscall::landlock_add_rule)?; // needed for stage2
+    ctx.allow_syscall(Syscall::landlock_restrict_self)?; // needed for stage2
     ctx.allow_syscall(Syscall::getrandom)?;
     #[cfg(not(any(
         target_arch = "aarch64",
@@ -172,7 +176,7 @@ pub fn activate_stage1() -> Result<()> {
     Ok(())
 }
 
-pub fn activate_stage2() -> Result<()> {
+pub fn activate_stage2(output_dirs: &[RawFd]) -> Result<()> {
     let mut ctx = Context::init()?;
 
     ctx.allow_syscall(Syscall::futex)?;
@@ -232,7 +236,6 @@ pub fn activate_stage2() -> Result<()> {
     ctx.allow_syscall(Syscall::exit_group)?;
     ctx.allow_syscall(Syscall::set_robust_list)?;
     // ctx.allow_syscall(Syscall::openat)?;
-    // ctx.allow_syscall(Syscall::getrandom)?;
     ctx.allow_syscall(Syscall::clock_gettime)?;
     #[cfg(target_arch = "arm")]
     ctx.allow_syscall(Syscall::clock_gettime64)?;
@@ -241,12 +244,48 @@ pub fn activate_stage2() -> Result<()> {
     ctx.allow_syscall(Syscall::membarrier)?;
     ctx.allow_syscall(Syscall::restart_syscall)?;
     ctx.allow_syscall(Syscall::rt_sigaction)?;
+    // returning from the ctrl-c handler that stops the capture when reports are printed at exit
+    ctx.allow_syscall(Syscall::rt_sigreturn)?;
     ctx.allow_syscall(Syscall::clone3)?;
     ctx.allow_syscall(Syscall::rseq)?;
     ctx.allow_syscall(Syscall::gettid)?;
 
-    // /proc/sys/vm/overcommit_memory
-    ctx.set_action_for_syscall(Action::Errno(1), Syscall::openat)?;
+    if output_dirs.is_empty() {
+        // /proc/sys/vm/overcommit_memory
+        ctx.set_action_for_syscall(Action::Errno(1), Syscall::openat)?;
+    } else {
+        // new files can only be created relative to the directories opened before stage2,
+        // landlock prevents absolute paths and `..` from leaving them
+        let flags = (libc::O_ACCMODE | libc::O_CREAT | libc::O_EXCL) as u64;
+        let create = (libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL) as u64;
+        for fd in output_dirs {
+            ctx.set_rule_for_syscall(
+                Action::Allow,
+                Syscall::openat,
+                &[
+                    Comparator::new(0, Cmp::Eq, *fd as u64, None),
+                    Comparator::new(2, Cmp::MaskedEq, flags, Some(create)),
+                ],
+            )?;
+        }
+        // debug builds of std check that a file is still open before closing it
+        ctx.set_rule_for_syscall(
+            Action::Allow,
+            Syscall::fcntl,
+            &[Comparator::new(1, Cmp::Eq, libc::F_GETFD as u64, None)],
+        )?;
+        // /proc/sys/vm/overcommit_memory
+        ctx.set_rule_for_syscall(
+            Action::Errno(1),
+            Syscall::openat,
+            &[Comparator::new(
+                0,
+                Cmp::MaskedEq,
+                0xffff_ffff,
+                Some(libc::AT_FDCWD as u32 as u64),
+            )],
+        )?;
+    }
     #[cfg(not(any(
         target_arch = "x86_64",
         target_arch = "aarch64",
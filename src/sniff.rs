@@ -1,6 +1,20 @@
 use crate::errors::*;
 use std::ffi::CStr;
 use std::ffi::CString;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static HANDLE: AtomicPtr<pcap_sys::pcap> = AtomicPtr::new(ptr::null_mut());
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    let handle = HANDLE.load(Ordering::SeqCst);
+    if !handle.is_null() {
+        unsafe { pcap_sys::pcap_breakloop(handle) };
+    }
+}
 
 pub struct Cap {
     handle: *mut pcap_sys::pcap,
@@ -70,9 +84,35 @@ impl Cap {
         unsafe { pcap_sys::pcap_datalink(self.handle) }
     }
 
+    /// End the packet stream on ctrl-c instead of exiting, so state can be reported at exit.
+    /// The handler resets itself, a second ctrl-c terminates immediately.
+    pub fn stop_on_interrupt(&self) -> Result<()> {
+        HANDLE.store(self.handle, Ordering::SeqCst);
+
+        let ret = unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_interrupt as *const () as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESETHAND;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(libc::SIGINT, &action, ptr::null_mut())
+        };
+        if ret != 0 {
+            bail!(
+                "Failed to install signal handler: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+
+        Ok(())
+    }
+
     pub fn next_pkt(&mut self) -> Result<Option<Packet>> {
         use std::mem::MaybeUninit;
 
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+
         let mut header = MaybeUninit::<*mut pcap_sys::pcap_pkthdr>::uninit();
         let mut packet = MaybeUninit::<*const libc::c_uchar>::uninit();
 
@@ -87,13 +127,16 @@ impl Cap {
 
                 use std::slice;
                 let packet = unsafe { slice::from_raw_parts(packet, (*header).caplen as _) };
+                let ts = unsafe { (*header).ts };
 
                 Ok(Some(Packet {
+                    timestamp: timestamp(ts),
                     data: packet.to_vec(),
                 }))
             }
             0 => bail!("timeout expired"),
             pcap_sys::PCAP_ERROR_BREAK => Ok(None),
+            _ if INTERRUPTED.load(Ordering::SeqCst) => Ok(None),
             _ => unreachable!(),
         }
     }
@@ -101,12 +144,46 @@ impl Cap {
 
 impl Drop for Cap {
     fn drop(&mut self) {
+        let _ = HANDLE.compare_exchange(
+            self.handle,
+            ptr::null_mut(),
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
         unsafe { pcap_sys::pcap_close(self.handle) };
     }
 }
 
+/// Capture files can contain any timestamp, anything that can't be represented is read as the epoch
+fn timestamp(ts: libc::timeval) -> SystemTime {
+    let (Ok(secs), Ok(micros)) = (u64::try_from(ts.tv_sec), u64::try_from(ts.tv_usec)) else {
+        return UNIX_EPOCH;
+    };
+    UNIX_EPOCH
+        .checked_add(Duration::from_secs(secs))
+        .and_then(|time| time.checked_add(Duration::from_micros(micros)))
+        .unwrap_or(UNIX_EPOCH)
+}
+
 pub struct Packet {
+    pub timestamp: SystemTime,
     pub data: Vec<u8>,
 }
 
 unsafe impl Send for Cap {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_timestamps() {
+        let ts = |tv_sec, tv_usec| timestamp(libc::timeval { tv_sec, tv_usec });
+        assert_eq!(
+            ts(1_700_000_000, 250_000),
+            UNIX_EPOCH + Duration::from_millis(1_700_000_000_250)
+        );
+        assert_eq!(ts(-1, 0), UNIX_EPOCH);
+        assert_eq!(ts(0, -1), UNIX_EPOCH);
+    }
+}
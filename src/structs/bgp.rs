@@ -0,0 +1,130 @@
+use serde::Serialize;
+use std::net::Ipv4Addr;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Message {
+    Open(Open),
+    Update(Update),
+    Notification(Notification),
+    Keepalive,
+    RouteRefresh { afi: u16, safi: u8 },
+    Unknown(u8, Vec<u8>),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Open {
+    pub version: u8,
+    /// The 4-byte asn if the capability was sent instead of the `AS_TRANS` placeholder
+    pub asn: u32,
+    pub hold_time: u16,
+    pub router_id: Ipv4Addr,
+    pub capabilities: Vec<Capability>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Capability {
+    Multiprotocol { afi: u16, safi: u8 },
+    RouteRefresh,
+    FourOctetAs(u32),
+    Unknown(u8, Vec<u8>),
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Update {
+    pub withdrawn: Vec<Prefix>,
+    pub origin: Option<Origin>,
+    pub as_path: Vec<Segment>,
+    pub next_hop: Option<Ipv4Addr>,
+    pub med: Option<u32>,
+    pub local_pref: Option<u32>,
+    pub communities: Vec<u32>,
+    /// Type codes of path attributes that aren't decoded, eg. multiprotocol routes
+    pub other_attributes: Vec<u8>,
+    /// The announced routes
+    pub nlri: Vec<Prefix>,
+}
+
+impl Update {
+    /// An update without routes marks the end of the initial routing table
+    pub fn is_end_of_rib(&self) -> bool {
+        self.withdrawn.is_empty() && self.nlri.is_empty() && self.origin.is_none()
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Prefix {
+    pub network: Ipv4Addr,
+    pub length: u8,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Origin {
+    IGP,
+    EGP,
+    Incomplete,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Segment {
+    Set(Vec<u32>),
+    Sequence(Vec<u32>),
+    ConfedSequence(Vec<u32>),
+    ConfedSet(Vec<u32>),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Notification {
+    pub code: u8,
+    pub subcode: u8,
+    /// The shutdown communication of a cease, rfc8203
+    pub reason: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl Notification {
+    pub fn error(&self) -> &'static str {
+        match self.code {
+            1 => "Message Header Error",
+            2 => "OPEN Message Error",
+            3 => "UPDATE Message Error",
+            4 => "Hold Timer Expired",
+            5 => "Finite State Machine Error",
+            6 => "Cease",
+            _ => "Unknown Error",
+        }
+    }
+
+    pub fn suberror(&self) -> Option<&'static str> {
+        let name = match (self.code, self.subcode) {
+            (1, 1) => "Connection Not Synchronized",
+            (1, 2) => "Bad Message Length",
+            (1, 3) => "Bad Message Type",
+            (2, 1) => "Unsupported Version Number",
+            (2, 2) => "Bad Peer AS",
+            (2, 3) => "Bad BGP Identifier",
+            (2, 4) => "Unsupported Optional Parameter",
+            (2, 6) => "Unacceptable Hold Time",
+            (2, 7) => "Unsupported Capability",
+            (3, 1) => "Malformed Attribute List",
+            (3, 2) => "Unrecognized Well-known Attribute",
+            (3, 3) => "Missing Well-known Attribute",
+            (3, 4) => "Attribute Flags Error",
+            (3, 5) => "Attribute Length Error",
+            (3, 6) => "Invalid ORIGIN Attribute",
+            (3, 8) => "Invalid NEXT_HOP Attribute",
+            (3, 9) => "Optional Attribute Error",
+            (3, 10) => "Invalid Network Field",
+            (3, 11) => "Malformed AS_PATH",
+            (6, 1) => "Maximum Number of Prefixes Reached",
+            (6, 2) => "Administrative Shutdown",
+            (6, 3) => "Peer De-configured",
+            (6, 4) => "Administrative Reset",
+            (6, 5) => "Connection Rejected",
+            (6, 6) => "Other Configuration Change",
+            (6, 7) => "Connection Collision Resolution",
+            (6, 8) => "Out of Resources",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
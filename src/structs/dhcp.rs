@@ -22,9 +22,11 @@ pub struct Packet {
     pub chaddr: [u8; 6],
 
     pub hostname: Option<String>,
+    pub server_identifier: Option<Ipv4Addr>,
     pub requested_ip_address: Option<Ipv4Addr>,
     pub router: Option<Vec<Ipv4Addr>>,
     pub domain_name_server: Option<Vec<Ipv4Addr>>,
+    pub parameter_request_list: Option<Vec<u8>>,
 }
 
 impl Packet {
@@ -36,9 +38,11 @@ impl Packet {
             chaddr,
 
             hostname: None,
+            server_identifier: None,
             requested_ip_address: None,
             router: None,
             domain_name_server: None,
+            parameter_request_list: None,
         }
     }
 }
@@ -2,7 +2,7 @@ use serde::Serialize;
 use std::net::{Ipv4Addr, Ipv6Addr};
 
 // https://github.com/tailhook/dns-parser/pull/34
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum QueryType {
     A,
     NS,
@@ -57,6 +57,33 @@ impl From<dns_parser::QueryType> for QueryType {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ResponseCode {
+    NoError,
+    FormatError,
+    ServerFailure,
+    NameError,
+    NotImplemented,
+    Refused,
+    Reserved(u8),
+}
+
+impl From<dns_parser::ResponseCode> for ResponseCode {
+    #[inline]
+    fn from(code: dns_parser::ResponseCode) -> ResponseCode {
+        use dns_parser::ResponseCode::*;
+        match code {
+            NoError => ResponseCode::NoError,
+            FormatError => ResponseCode::FormatError,
+            ServerFailure => ResponseCode::ServerFailure,
+            NameError => ResponseCode::NameError,
+            NotImplemented => ResponseCode::NotImplemented,
+            Refused => ResponseCode::Refused,
+            Reserved(code) => ResponseCode::Reserved(code),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize)]
 pub enum DNS {
     Request(Request),
@@ -65,12 +92,13 @@ pub enum DNS {
 
 #[derive(Debug, PartialEq, Serialize)]
 pub struct Request {
+    pub id: u16,
     pub questions: Vec<(QueryType, String)>,
 }
 
 impl Request {
-    pub fn new(questions: Vec<(QueryType, String)>) -> Request {
-        Request { questions }
+    pub fn new(id: u16, questions: Vec<(QueryType, String)>) -> Request {
+        Request { id, questions }
     }
 
     pub fn wrap(self) -> DNS {
@@ -80,12 +108,25 @@ impl Request {
 
 #[derive(Debug, PartialEq, Serialize)]
 pub struct Response {
+    pub id: u16,
+    pub code: ResponseCode,
+    pub questions: Vec<(QueryType, String)>,
     pub answers: Vec<(String, Record)>,
 }
 
 impl Response {
-    pub fn new(answers: Vec<(String, Record)>) -> Response {
-        Response { answers }
+    pub fn new(
+        id: u16,
+        code: ResponseCode,
+        questions: Vec<(QueryType, String)>,
+        answers: Vec<(String, Record)>,
+    ) -> Response {
+        Response {
+            id,
+            code,
+            questions,
+            answers,
+        }
     }
 
     pub fn wrap(self) -> DNS {
@@ -93,7 +134,7 @@ impl Response {
     }
 }
 
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum Record {
     A(Ipv4Addr),
     AAAA(Ipv6Addr),
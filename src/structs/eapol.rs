@@ -0,0 +1,54 @@
+use crate::structs::NoiseLevel;
+use bstr::BString;
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum EAPOL {
+    EAP(EAP),
+    Start,
+    Logoff,
+    Key(u8),
+    Unknown(u8, Vec<u8>),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum EAP {
+    Request(Message),
+    Response(Message),
+    Success(u8),
+    Failure(u8),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Message {
+    pub id: u8,
+    pub method: Method,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Method {
+    Identity(BString),
+    Notification(BString),
+    Nak(Vec<u8>),
+    MD5,
+    TLS,
+    TTLS,
+    PEAP,
+    MSCHAPv2,
+    FAST,
+    Other(u8),
+}
+
+impl EAPOL {
+    pub fn noise_level(&self) -> NoiseLevel {
+        match *self {
+            // identity responses leak usernames
+            EAPOL::EAP(EAP::Response(Message {
+                method: Method::Identity(_),
+                ..
+            })) => NoiseLevel::Zero,
+            EAPOL::Unknown(_, _) => NoiseLevel::AlmostMaximum,
+            _ => NoiseLevel::One,
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use crate::structs::NoiseLevel;
 use crate::structs::arp;
 use crate::structs::cjdns;
+use crate::structs::eapol;
 use crate::structs::ipv4;
 use crate::structs::ipv6;
+use crate::structs::lacp;
+use crate::structs::llc;
 use serde::Serialize;
 
 #[derive(Debug, PartialEq, Serialize)]
@@ -11,6 +14,9 @@ pub enum Ether {
     IPv4(pktparse::ipv4::IPv4Header, ipv4::IPv4),
     IPv6(pktparse::ipv6::IPv6Header, ipv6::IPv6),
     Cjdns(cjdns::CjdnsEthPkt),
+    Llc(llc::LLC),
+    Lacp(lacp::LACP),
+    Eapol(eapol::EAPOL),
     Unknown(Vec<u8>),
 }
 
@@ -22,6 +28,9 @@ impl Ether {
             IPv4(_, ref ipv4) => ipv4.noise_level(),
             IPv6(_, ref ipv6) => ipv6.noise_level(),
             Cjdns(_) => NoiseLevel::Two,
+            Llc(ref llc) => llc.noise_level(),
+            Lacp(_) => NoiseLevel::Two,
+            Eapol(ref eapol) => eapol.noise_level(),
             Unknown(_) => NoiseLevel::Maximum,
         }
     }
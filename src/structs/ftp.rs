@@ -0,0 +1,52 @@
+use serde::Serialize;
+use std::net::IpAddr;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum FTP {
+    Command(Command),
+    Reply(Reply),
+    /// Payload of a data connection that was negotiated on a control connection
+    Data(Data),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Command {
+    /// Uppercase, eg. `USER` or `RETR`
+    pub command: String,
+    pub argument: Option<String>,
+    /// Where the client listens for an active data connection, from `PORT` or `EPRT`
+    pub data_port: Option<DataPort>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Reply {
+    pub code: u16,
+    /// The text of every line, without the reply code
+    pub lines: Vec<String>,
+    /// Where the server listens for a passive data connection, from `227` or `229` replies
+    pub data_port: Option<DataPort>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DataPort {
+    /// `EPSV` replies only contain the port, the address of the control connection is used
+    pub ip: Option<IpAddr>,
+    pub port: u16,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Data {
+    /// The command that started the transfer, if it was captured
+    pub command: Option<String>,
+    /// The path of `RETR`, `STOR` and similar commands
+    pub file: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// Commands that transfer a file or a listing over the data connection
+pub fn is_transfer(command: &str) -> bool {
+    matches!(
+        command,
+        "RETR" | "STOR" | "STOU" | "APPE" | "LIST" | "NLST" | "MLSD"
+    )
+}
@@ -1,7 +1,9 @@
+use crate::centrifuge::http::{MAX_BODY_LENGTH, decode};
 use crate::structs::CentrifugeError;
 use bstr::BString;
 use httparse::Header;
 use serde::Serialize;
+use std::cell::OnceCell;
 use std::convert::TryFrom;
 use std::str;
 
@@ -23,6 +25,9 @@ pub struct Request {
     pub auth: Option<String>,
     pub cookies: Option<String>,
     pub body: Option<BString>,
+    /// Only decoded once the body is displayed or inspected, see `decoded()`
+    #[serde(skip)]
+    pub decoded: OnceCell<Option<Body>>,
 }
 
 #[derive(Debug, PartialEq, Serialize)]
@@ -32,6 +37,63 @@ pub struct Response {
     pub version: u8,
     pub headers: Vec<(String, BString)>,
     pub body: Option<BString>,
+    /// Only decoded once the body is displayed or inspected, see `decoded()`
+    #[serde(skip)]
+    pub decoded: OnceCell<Option<Body>>,
+}
+
+/// Message body after removing the transfer and content codings
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Body {
+    /// Codings that were removed, in the order they were decoded
+    pub encodings: Vec<String>,
+    pub content_type: Option<String>,
+    /// The content type is textual and the body can be displayed
+    pub text: bool,
+    /// The body was cut off by the end of the segment or the size limit
+    pub truncated: bool,
+    pub data: BString,
+}
+
+impl Request {
+    /// Value of the first header with this name, if any
+    pub fn header(&self, name: &str) -> Option<&BString> {
+        header(&self.headers, name)
+    }
+
+    /// The body without its transfer and content codings
+    pub fn decoded(&self) -> Option<&Body> {
+        decoded(&self.decoded, self.body.as_ref(), &self.headers)
+    }
+}
+
+impl Response {
+    /// Value of the first header with this name, if any
+    pub fn header(&self, name: &str) -> Option<&BString> {
+        header(&self.headers, name)
+    }
+
+    /// The body without its transfer and content codings
+    pub fn decoded(&self) -> Option<&Body> {
+        decoded(&self.decoded, self.body.as_ref(), &self.headers)
+    }
+}
+
+/// Value of the first header with this name, if any
+pub fn header<'a>(headers: &'a [(String, BString)], name: &str) -> Option<&'a BString> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+fn decoded<'a>(
+    cell: &'a OnceCell<Option<Body>>,
+    body: Option<&BString>,
+    headers: &[(String, BString)],
+) -> Option<&'a Body> {
+    cell.get_or_init(|| body.map(|body| decode(body, headers, MAX_BODY_LENGTH)))
+        .as_ref()
 }
 
 fn append_if_header(mem: &mut Option<String>, expected: &str, header: &Header) {
@@ -71,6 +133,7 @@ impl TryFrom<httparse::Request<'_, '_>> for Request {
             auth: None,
             cookies: None,
             body: None,
+            decoded: OnceCell::new(),
         };
 
         for header in req.headers {
@@ -107,6 +170,7 @@ impl TryFrom<httparse::Response<'_, '_>> for Response {
             reason: reason.to_string(),
             headers: Vec::new(),
             body: None,
+            decoded: OnceCell::new(),
         };
 
         for header in req.headers {
@@ -0,0 +1,51 @@
+use crate::structs::NoiseLevel;
+use serde::Serialize;
+use std::net::Ipv6Addr;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum ICMPv6 {
+    EchoRequest,
+    EchoReply,
+    RouterSolicitation,
+    RouterAdvertisement(RouterAdvertisement),
+    NeighborSolicitation(Ipv6Addr),
+    NeighborAdvertisement(Ipv6Addr),
+    Other {
+        icmp_type: u8,
+        code: u8,
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RouterAdvertisement {
+    pub hop_limit: u8,
+    pub managed: bool,
+    pub other: bool,
+    pub router_lifetime: u16,
+    pub source_mac: Option<[u8; 6]>,
+    pub prefixes: Vec<Prefix>,
+    pub mtu: Option<u32>,
+    pub dns: Vec<Ipv6Addr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Prefix {
+    pub prefix: Ipv6Addr,
+    pub length: u8,
+}
+
+impl ICMPv6 {
+    pub fn noise_level(&self) -> NoiseLevel {
+        use self::ICMPv6::*;
+        match *self {
+            RouterAdvertisement(_) => NoiseLevel::Zero,
+            RouterSolicitation => NoiseLevel::One,
+            EchoRequest => NoiseLevel::One,
+            EchoReply => NoiseLevel::One,
+            NeighborSolicitation(_) => NoiseLevel::One,
+            NeighborAdvertisement(_) => NoiseLevel::One,
+            Other { .. } => NoiseLevel::Two,
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use pktparse::{ipv4, ipv6};
 use std::fmt::Display;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr};
 
 pub trait IPHeader {
     type Addr: Display;
 
     fn source_addr(&self) -> Self::Addr;
     fn dest_addr(&self) -> Self::Addr;
+
+    fn source_ip(&self) -> IpAddr;
+    fn dest_ip(&self) -> IpAddr;
 }
 
 impl IPHeader for ipv4::IPv4Header {
@@ -21,6 +24,16 @@ impl IPHeader for ipv4::IPv4Header {
     fn dest_addr(&self) -> Self::Addr {
         self.dest_addr
     }
+
+    #[inline]
+    fn source_ip(&self) -> IpAddr {
+        IpAddr::V4(self.source_addr)
+    }
+
+    #[inline]
+    fn dest_ip(&self) -> IpAddr {
+        IpAddr::V4(self.dest_addr)
+    }
 }
 
 impl IPHeader for ipv6::IPv6Header {
@@ -35,4 +48,14 @@ impl IPHeader for ipv6::IPv6Header {
     fn dest_addr(&self) -> Self::Addr {
         format!("[{}]", self.dest_addr)
     }
+
+    #[inline]
+    fn source_ip(&self) -> IpAddr {
+        IpAddr::V6(self.source_addr)
+    }
+
+    #[inline]
+    fn dest_ip(&self) -> IpAddr {
+        IpAddr::V6(self.dest_addr)
+    }
 }
@@ -4,7 +4,12 @@ use serde::Serialize;
 
 #[derive(Debug, PartialEq, Serialize)]
 pub enum IPv4 {
-    TCP(pktparse::tcp::TcpHeader, tcp::TCP),
+    /// The options are only kept for syn packets, for os fingerprinting
+    TCP(
+        pktparse::tcp::TcpHeader,
+        tcp::TCP,
+        #[serde(skip)] Vec<tcp::TcpOption>,
+    ),
     UDP(pktparse::udp::UdpHeader, udp::UDP),
     ICMP(pktparse::icmp::IcmpHeader, icmp::ICMP),
     Unknown(Vec<u8>),
@@ -14,7 +19,7 @@ impl IPv4 {
     pub fn noise_level(&self) -> NoiseLevel {
         use self::IPv4::*;
         match *self {
-            TCP(ref header, ref tcp) => tcp.noise_level(header),
+            TCP(ref header, ref tcp, _) => tcp.noise_level(header),
             UDP(_, ref udp) => udp.noise_level(),
             ICMP(ref header, ref icmp) => icmp.noise_level(header),
             Unknown(_) => NoiseLevel::Maximum,
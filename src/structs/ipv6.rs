@@ -1,12 +1,19 @@
 use crate::structs::NoiseLevel;
+use crate::structs::icmpv6;
 use crate::structs::tcp;
 use crate::structs::udp;
 use serde::Serialize;
 
 #[derive(Debug, PartialEq, Serialize)]
 pub enum IPv6 {
-    TCP(pktparse::tcp::TcpHeader, tcp::TCP),
+    /// The options are only kept for syn packets, for os fingerprinting
+    TCP(
+        pktparse::tcp::TcpHeader,
+        tcp::TCP,
+        #[serde(skip)] Vec<tcp::TcpOption>,
+    ),
     UDP(pktparse::udp::UdpHeader, udp::UDP),
+    ICMPv6(icmpv6::ICMPv6),
     Unknown(Vec<u8>),
 }
 
@@ -14,8 +21,9 @@ impl IPv6 {
     pub fn noise_level(&self) -> NoiseLevel {
         use self::IPv6::*;
         match *self {
-            TCP(ref header, ref tcp) => tcp.noise_level(header),
+            TCP(ref header, ref tcp, _) => tcp.noise_level(header),
             UDP(_, ref udp) => udp.noise_level(),
+            ICMPv6(ref icmp) => icmp.noise_level(),
             Unknown(_) => NoiseLevel::Maximum,
         }
     }
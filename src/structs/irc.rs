@@ -0,0 +1,56 @@
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Message {
+    /// The sender, missing in messages sent by the client
+    pub prefix: Option<String>,
+    /// Uppercase, eg. `PRIVMSG` or a numeric reply like `353`
+    pub command: String,
+    pub params: Vec<String>,
+    /// The last parameter that was sent after a colon and may contain spaces
+    pub trailing: Option<String>,
+}
+
+impl Message {
+    /// The nick of a `nick!user@host` prefix
+    pub fn nick(&self) -> Option<&str> {
+        let prefix = self.prefix.as_deref()?;
+        Some(prefix.split(['!', '@']).next().unwrap_or(prefix))
+    }
+
+    /// The channel or nick a message is sent to
+    pub fn target(&self) -> Option<&str> {
+        self.params.first().map(String::as_str)
+    }
+
+    /// The text of a message, this is usually the trailing parameter
+    pub fn text(&self) -> Option<&str> {
+        self.trailing
+            .as_deref()
+            .or_else(|| self.params.get(1).map(String::as_str))
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.command.chars().all(|c| c.is_ascii_digit())
+    }
+
+    /// The message as it was sent by the client, without the line break
+    pub fn line(&self) -> String {
+        let mut line = String::new();
+        if let Some(prefix) = &self.prefix {
+            line.push(':');
+            line.push_str(prefix);
+            line.push(' ');
+        }
+        line.push_str(&self.command);
+        for param in &self.params {
+            line.push(' ');
+            line.push_str(param);
+        }
+        if let Some(trailing) = &self.trailing {
+            line.push_str(" :");
+            line.push_str(trailing);
+        }
+        line
+    }
+}
@@ -0,0 +1,40 @@
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct LACP {
+    pub version: u8,
+    pub actor: Info,
+    pub partner: Info,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Info {
+    pub system_priority: u16,
+    pub system: [u8; 6],
+    pub key: u16,
+    pub port_priority: u16,
+    pub port: u16,
+    pub state: u8,
+}
+
+const STATE_FLAGS: [&str; 8] = [
+    "activity",
+    "timeout",
+    "aggregation",
+    "sync",
+    "collecting",
+    "distributing",
+    "defaulted",
+    "expired",
+];
+
+impl Info {
+    pub fn state_flags(&self) -> Vec<&'static str> {
+        STATE_FLAGS
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| self.state & (1 << i) != 0)
+            .map(|(_, flag)| *flag)
+            .collect()
+    }
+}
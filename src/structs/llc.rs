@@ -0,0 +1,35 @@
+use crate::structs::NoiseLevel;
+use crate::structs::stp;
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum LLC {
+    STP(stp::BPDU),
+    Snap(Snap),
+    Unknown(Header, Vec<u8>),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Header {
+    pub dsap: u8,
+    pub ssap: u8,
+    pub control: u8,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Snap {
+    pub oui: [u8; 3],
+    pub protocol: u16,
+    pub data: Vec<u8>,
+}
+
+impl LLC {
+    pub fn noise_level(&self) -> NoiseLevel {
+        use self::LLC::*;
+        match *self {
+            STP(ref bpdu) => bpdu.noise_level(),
+            Snap(_) => NoiseLevel::AlmostMaximum,
+            Unknown(_, _) => NoiseLevel::AlmostMaximum,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Protocol {
+    SMTP,
+    POP3,
+    IMAP,
+}
+
+impl Protocol {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Protocol::SMTP => "smtp",
+            Protocol::POP3 => "pop3",
+            Protocol::IMAP => "imap",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Mail {
+    /// Clients may send multiple commands at once with smtp pipelining
+    Commands(Protocol, Vec<Command>),
+    /// Pipelined commands are answered with multiple replies
+    Replies(Protocol, Vec<Reply>),
+    /// The headers of a message that is sent with smtp `DATA`
+    Message(Message),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Command {
+    /// Imap commands start with a tag that is repeated in the reply
+    pub tag: Option<String>,
+    /// Uppercase, eg. `MAIL` or `LOGIN`
+    pub command: String,
+    pub argument: Option<String>,
+    /// The sender or recipient of smtp `MAIL FROM` and `RCPT TO`
+    pub address: Option<String>,
+    /// The account of a login attempt
+    pub username: Option<String>,
+}
+
+impl Command {
+    /// The command line as it was sent, without the line break
+    pub fn line(&self) -> String {
+        let mut line = String::new();
+        if let Some(tag) = &self.tag {
+            line.push_str(tag);
+            line.push(' ');
+        }
+        line.push_str(&self.command);
+        if let Some(argument) = &self.argument {
+            line.push(' ');
+            line.push_str(argument);
+        }
+        line
+    }
+
+    /// The connection switches to tls after the server accepted this command
+    pub fn is_starttls(&self) -> bool {
+        matches!(self.command.as_str(), "STARTTLS" | "STLS")
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Reply {
+    /// `*` for untagged imap responses and `+` for continuation requests
+    pub tag: Option<String>,
+    /// The smtp reply code, `+OK` or `-ERR` for pop3 and the response type for imap
+    pub status: String,
+    /// The text of every line, without the status
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Message {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub subject: Option<String>,
+}
@@ -28,18 +28,35 @@ impl NoiseLevel {
 }
 
 pub mod arp;
+pub mod bgp;
 pub mod cjdns;
 pub mod dhcp;
 pub mod dns;
 pub mod dropbox;
+pub mod eapol;
 pub mod ether;
+pub mod ftp;
 pub mod http;
 pub mod icmp;
+pub mod icmpv6;
 pub mod ip;
 pub mod ipv4;
 pub mod ipv6;
+pub mod irc;
+pub mod lacp;
+pub mod llc;
+pub mod mail;
+pub mod mongodb;
+pub mod mysql;
+pub mod nbns;
+pub mod postgres;
+pub mod quic;
 pub mod raw;
+pub mod redis;
 pub mod ssdp;
+pub mod ssh;
+pub mod stp;
 pub mod tcp;
+pub mod telnet;
 pub mod tls;
 pub mod udp;
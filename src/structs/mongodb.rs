@@ -0,0 +1,33 @@
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct MongoDB {
+    pub request_id: i32,
+    /// The request id of the request a reply answers
+    pub response_to: i32,
+    pub op: Op,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Op {
+    /// `OP_MSG` or the legacy `OP_QUERY` of a client
+    Command(Command),
+    /// `OP_MSG` or the legacy `OP_REPLY` of a server
+    Reply(Reply),
+    Other(i32),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Command {
+    /// The first field of the command document, eg. `find` or `insert`
+    pub name: String,
+    /// The value of the first field names the collection for crud commands
+    pub collection: Option<String>,
+    pub database: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Reply {
+    pub ok: Option<f64>,
+    pub error: Option<String>,
+}
@@ -0,0 +1,37 @@
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum MySQL {
+    /// The greeting of the server
+    Handshake(Handshake),
+    Login(Login),
+    /// The client switches to tls
+    SslRequest,
+    /// `COM_QUERY` and `COM_STMT_PREPARE`, queries that continue in the next segment are truncated
+    Query(String),
+    /// `COM_INIT_DB`, selects the default database
+    InitDb(String),
+    Quit,
+    Error(Error),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Handshake {
+    pub server_version: String,
+    pub connection_id: u32,
+    pub auth_plugin: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Login {
+    pub username: String,
+    pub database: Option<String>,
+    pub auth_plugin: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Error {
+    pub code: u16,
+    pub state: Option<String>,
+    pub message: String,
+}
@@ -0,0 +1,27 @@
+use serde::Serialize;
+use std::net::Ipv4Addr;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum NBNS {
+    Query(Name),
+    Response(Name, Vec<Ipv4Addr>),
+    Registration(Name, Vec<Ipv4Addr>),
+    Release(Name, Vec<Ipv4Addr>),
+    Refresh(Name, Vec<Ipv4Addr>),
+    Other { opcode: u8, response: bool },
+}
+
+/// A netbios name, the last byte of the 16 byte name is used as a type suffix
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Name {
+    pub name: String,
+    pub suffix: u8,
+}
+
+impl Name {
+    /// Whether this is a workstation or server name, as opposed to a domain or group name
+    #[inline]
+    pub fn is_host(&self) -> bool {
+        matches!(self.suffix, 0x00 | 0x20)
+    }
+}
@@ -0,0 +1,53 @@
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Message {
+    Startup(Startup),
+    /// The client switches to tls
+    SslRequest,
+    /// The client switches to gssapi encryption
+    GssEncRequest,
+    /// A simple query or the statement of an extended query, truncated if it continues in the next segment
+    Query(String),
+    /// A cleartext or md5 password
+    Password(String),
+    Terminate,
+    /// The server asks for a password or accepts the login
+    Authentication(u32),
+    Error(Error),
+}
+
+impl Message {
+    /// The name of the authentication request of a server
+    pub fn authentication(kind: u32) -> &'static str {
+        match kind {
+            0 => "ok",
+            2 => "kerberos",
+            3 => "cleartext",
+            5 => "md5",
+            7 => "gss",
+            8 => "gss-continue",
+            9 => "sspi",
+            10 => "sasl",
+            11 => "sasl-continue",
+            12 => "sasl-final",
+            _ => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Startup {
+    pub user: Option<String>,
+    pub database: Option<String>,
+    pub application_name: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Error {
+    /// Eg. `ERROR` or `FATAL`
+    pub severity: Option<String>,
+    /// The sqlstate, eg. `28P01` for a wrong password
+    pub code: Option<String>,
+    pub message: Option<String>,
+}
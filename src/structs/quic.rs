@@ -0,0 +1,40 @@
+use crate::structs::tls::ClientHello;
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum PacketType {
+    Initial,
+    ZeroRtt,
+    Handshake,
+    Retry,
+    VersionNegotiation,
+}
+
+/// The first long header packet of a datagram, following packets are only used for the crypto stream
+#[derive(Debug, PartialEq, Serialize)]
+pub struct QUIC {
+    pub version: u32,
+    pub packet_type: PacketType,
+    pub dcid: String,
+    pub scid: String,
+    /// Recovered from the initial packets, possibly reassembled from multiple datagrams
+    pub client_hello: Option<ClientHello>,
+    /// Decrypted crypto frames of the client's initial packets
+    #[serde(skip)]
+    pub crypto: Vec<CryptoFrame>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CryptoFrame {
+    pub offset: u64,
+    pub data: Vec<u8>,
+}
+
+pub fn version_name(version: u32) -> Option<&'static str> {
+    match version {
+        0x0000_0000 => Some("negotiation"),
+        0x0000_0001 => Some("v1"),
+        0x6b33_43cf => Some("v2"),
+        _ => None,
+    }
+}
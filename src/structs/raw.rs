@@ -20,4 +20,26 @@ impl Raw {
             Unknown(_) => NoiseLevel::Maximum,
         }
     }
+
+    #[inline]
+    pub fn ether(&self) -> Option<&ether::Ether> {
+        use self::Raw::*;
+        match *self {
+            Ether(_, ref ether) => Some(ether),
+            Tun(ref ether) => Some(ether),
+            Sll(ref ether) => Some(ether),
+            Unknown(_) => None,
+        }
+    }
+
+    #[inline]
+    pub fn ether_mut(&mut self) -> Option<&mut ether::Ether> {
+        use self::Raw::*;
+        match *self {
+            Ether(_, ref mut ether) => Some(ether),
+            Tun(ref mut ether) => Some(ether),
+            Sll(ref mut ether) => Some(ether),
+            Unknown(_) => None,
+        }
+    }
 }
@@ -0,0 +1,34 @@
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Redis {
+    /// Clients may send multiple commands at once with pipelining
+    Commands(Vec<Command>),
+    Replies(Vec<Value>),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Command {
+    /// The command name followed by its arguments
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command {
+    /// Uppercase, eg. `SET`
+    pub fn name(&self) -> String {
+        self.args
+            .first()
+            .map(|x| String::from_utf8_lossy(x).to_ascii_uppercase())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Value {
+    Simple(String),
+    Error(String),
+    Integer(i64),
+    /// A missing bulk string is sent as null
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<Value>>),
+}
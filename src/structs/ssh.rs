@@ -0,0 +1,71 @@
+use serde::Serialize;
+
+/// The cleartext start of an ssh connection, banner and key exchange init may share a segment
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SSH {
+    pub banner: Option<Banner>,
+    pub kex_init: Option<KexInit>,
+}
+
+/// The identification string, eg. `SSH-2.0-OpenSSH_9.6p1 Ubuntu-3`
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Banner {
+    pub protocol: String,
+    pub software: String,
+    pub comments: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct KexInit {
+    /// Sent from port 22, a kexinit on any other port isn't shown
+    pub server: bool,
+    pub kex_algorithms: Vec<String>,
+    pub host_key_algorithms: Vec<String>,
+    pub encryption_client_to_server: Vec<String>,
+    pub encryption_server_to_client: Vec<String>,
+    pub mac_client_to_server: Vec<String>,
+    pub mac_server_to_client: Vec<String>,
+    pub compression_client_to_server: Vec<String>,
+    pub compression_server_to_client: Vec<String>,
+    pub first_kex_follows: bool,
+    /// hassh for clients and hasshServer for servers
+    pub hassh: String,
+    /// Offered algorithms that are broken or deprecated
+    pub weak: Vec<String>,
+}
+
+/// Algorithms that are broken or deprecated by rfc9142 and openssh
+pub fn is_weak(algorithm: &str) -> bool {
+    matches!(
+        algorithm,
+        "diffie-hellman-group1-sha1"
+            | "diffie-hellman-group14-sha1"
+            | "diffie-hellman-group-exchange-sha1"
+            | "rsa1024-sha1"
+            | "ssh-dss"
+            | "ssh-rsa"
+            | "ssh-rsa-cert-v01@openssh.com"
+            | "ssh-dss-cert-v01@openssh.com"
+            | "3des-cbc"
+            | "aes128-cbc"
+            | "aes192-cbc"
+            | "aes256-cbc"
+            | "rijndael-cbc@lysator.liu.se"
+            | "blowfish-cbc"
+            | "cast128-cbc"
+            | "idea-cbc"
+            | "des-cbc"
+            | "arcfour"
+            | "arcfour128"
+            | "arcfour256"
+            | "hmac-md5"
+            | "hmac-md5-96"
+            | "hmac-md5-etm@openssh.com"
+            | "hmac-md5-96-etm@openssh.com"
+            | "hmac-sha1-96"
+            | "hmac-sha1-96-etm@openssh.com"
+            | "hmac-ripemd160"
+            | "hmac-ripemd160@openssh.com"
+            | "none"
+    )
+}
@@ -0,0 +1,59 @@
+use crate::structs::NoiseLevel;
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum BPDU {
+    Config(ConfigBpdu),
+    TopologyChange,
+    RST(ConfigBpdu),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BridgeId {
+    pub priority: u16,
+    pub mac: [u8; 6],
+}
+
+/// Timers are in units of 1/256 seconds
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ConfigBpdu {
+    pub version: u8,
+    pub flags: u8,
+    pub root: BridgeId,
+    pub root_path_cost: u32,
+    pub bridge: BridgeId,
+    pub port_id: u16,
+    pub message_age: u16,
+    pub max_age: u16,
+    pub hello_time: u16,
+    pub forward_delay: u16,
+}
+
+impl ConfigBpdu {
+    #[inline]
+    pub fn topology_change(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    /// Only set in RST BPDUs
+    pub fn port_role(&self) -> Option<&'static str> {
+        match (self.flags >> 2) & 0x03 {
+            1 => Some("alternate/backup"),
+            2 => Some("root"),
+            3 => Some("designated"),
+            _ => None,
+        }
+    }
+}
+
+impl BPDU {
+    pub fn noise_level(&self) -> NoiseLevel {
+        match *self {
+            BPDU::Config(ref bpdu) | BPDU::RST(ref bpdu) if bpdu.topology_change() => {
+                NoiseLevel::One
+            }
+            BPDU::TopologyChange => NoiseLevel::One,
+            _ => NoiseLevel::Two,
+        }
+    }
+}
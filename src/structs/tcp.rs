@@ -1,5 +1,15 @@
 use crate::structs::NoiseLevel;
+use crate::structs::bgp;
+use crate::structs::ftp;
 use crate::structs::http;
+use crate::structs::irc;
+use crate::structs::mail;
+use crate::structs::mongodb;
+use crate::structs::mysql;
+use crate::structs::postgres;
+use crate::structs::redis;
+use crate::structs::ssh;
+use crate::structs::telnet;
 use crate::structs::tls;
 use serde::Serialize;
 
@@ -7,6 +17,16 @@ use serde::Serialize;
 pub enum TCP {
     TLS(tls::TLS),
     HTTP(http::Http),
+    SSH(ssh::SSH),
+    FTP(ftp::FTP),
+    Mail(mail::Mail),
+    Telnet(telnet::Telnet),
+    MySQL(mysql::MySQL),
+    Postgres(Vec<postgres::Message>),
+    Redis(redis::Redis),
+    MongoDB(mongodb::MongoDB),
+    IRC(Vec<irc::Message>),
+    BGP(Vec<bgp::Message>),
 
     Text(String),
     Binary(Vec<u8>),
@@ -28,6 +48,44 @@ impl TCP {
         } else {
             // data packet
             match *self {
+                TLS(tls::TLS::ChangeCipherSpec) => NoiseLevel::One,
+                FTP(ftp::FTP::Data(_)) => NoiseLevel::One,
+                // server replies and keepalives
+                IRC(ref messages)
+                    if messages.iter().all(|x| {
+                        x.is_numeric() || matches!(x.command.as_str(), "PING" | "PONG")
+                    }) =>
+                {
+                    NoiseLevel::One
+                }
+                MySQL(mysql::MySQL::Quit) => NoiseLevel::One,
+                Postgres(ref messages)
+                    if messages.iter().all(|x| *x == postgres::Message::Terminate) =>
+                {
+                    NoiseLevel::One
+                }
+                Redis(redis::Redis::Replies(_)) => NoiseLevel::One,
+                Redis(redis::Redis::Commands(ref commands))
+                    if commands.iter().all(|x| x.name() == "PING") =>
+                {
+                    NoiseLevel::One
+                }
+                // replies without an error and handshakes
+                MongoDB(ref mongodb) => match mongodb.op {
+                    mongodb::Op::Reply(ref reply) if reply.error.is_none() => NoiseLevel::One,
+                    mongodb::Op::Command(ref command)
+                        if matches!(command.name.as_str(), "hello" | "isMaster" | "ping") =>
+                    {
+                        NoiseLevel::One
+                    }
+                    mongodb::Op::Other(_) => NoiseLevel::One,
+                    _ => NoiseLevel::Zero,
+                },
+                // option negotiation
+                Telnet(ref telnet) if telnet.text.is_empty() => NoiseLevel::One,
+                BGP(ref messages) if messages.iter().all(|x| *x == bgp::Message::Keepalive) => {
+                    NoiseLevel::One
+                }
                 Text(ref text) if text.len() <= 8 => NoiseLevel::AlmostMaximum,
                 Binary(_) => NoiseLevel::AlmostMaximum,
                 Empty => NoiseLevel::AlmostMaximum,
@@ -36,3 +94,19 @@ impl TCP {
         }
     }
 }
+
+/// pktparse discards all options if it doesn't know one of them, eg. timestamps
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum TcpOption {
+    /// Contains the padding after the option
+    EndOfOptions(Vec<u8>),
+    NoOperation,
+    MaximumSegmentSize(u16),
+    WindowScale(u8),
+    SackPermitted,
+    Sack(Vec<(u32, u32)>),
+    Timestamp(u32, u32),
+    Unknown(u8, Vec<u8>),
+    /// The remaining bytes if an option exceeds the header
+    Malformed(Vec<u8>),
+}
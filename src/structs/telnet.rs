@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Telnet {
+    /// Option negotiation and other commands, in the order they were sent
+    pub commands: Vec<Command>,
+    /// The session text without commands
+    pub text: String,
+    /// The server asks for a username or password
+    pub prompt: Option<Prompt>,
+    /// The client answers a prompt, this is only known by following the session
+    pub input: Option<Prompt>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Prompt {
+    Login,
+    Password,
+}
+
+impl Prompt {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Prompt::Login => "login",
+            Prompt::Password => "password",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum Command {
+    Will(u8),
+    Wont(u8),
+    Do(u8),
+    Dont(u8),
+    TerminalType(String),
+    WindowSize {
+        width: u16,
+        height: u16,
+    },
+    /// Variables like `USER` and `DISPLAY`, a variable may be sent without a value
+    Environment(Vec<(String, Option<String>)>),
+    Subnegotiation(u8, Vec<u8>),
+    /// Eg. go ahead or interrupt process
+    Other(u8),
+}
+
+/// The name of an option, rfc855 and the rfcs of the individual options
+pub fn option_name(option: u8) -> Option<&'static str> {
+    let name = match option {
+        0 => "binary",
+        1 => "echo",
+        3 => "suppress-go-ahead",
+        5 => "status",
+        6 => "timing-mark",
+        24 => "terminal-type",
+        31 => "window-size",
+        32 => "terminal-speed",
+        33 => "remote-flow-control",
+        34 => "linemode",
+        35 => "x-display-location",
+        36 => "environ",
+        37 => "authentication",
+        38 => "encrypt",
+        39 => "new-environ",
+        _ => return None,
+    };
+    Some(name)
+}
@@ -1,59 +1,210 @@
-use data_encoding::BASE64;
 use serde::Serialize;
-use tls_parser::{TlsClientHelloContents, TlsServerHelloContents, TlsVersion};
 
 #[derive(Debug, PartialEq, Serialize)]
 pub enum TLS {
     ClientHello(ClientHello),
     ServerHello(ServerHello),
+    /// A certificate chain that didn't arrive together with the server hello
+    Certificate(Vec<Certificate>),
+    Alert(Alert),
+    /// Everything that follows is encrypted
+    ChangeCipherSpec,
 }
 
-fn tls_version(ver: TlsVersion) -> Option<&'static str> {
-    match ver {
-        TlsVersion::Ssl30 => Some("ssl3.0"),
-        TlsVersion::Tls10 => Some("tls1.0"),
-        TlsVersion::Tls11 => Some("tls1.1"),
-        TlsVersion::Tls12 => Some("tls1.2"),
-        TlsVersion::Tls13 => Some("tls1.3"),
+pub fn version_name(version: u16) -> Option<&'static str> {
+    match version {
+        0x0300 => Some("ssl3.0"),
+        0x0301 => Some("tls1.0"),
+        0x0302 => Some("tls1.1"),
+        0x0303 => Some("tls1.2"),
+        0x0304 => Some("tls1.3"),
         _ => None,
     }
 }
 
+pub fn alert_level(level: u8) -> Option<&'static str> {
+    match level {
+        1 => Some("warning"),
+        2 => Some("fatal"),
+        _ => None,
+    }
+}
+
+pub fn alert_description(description: u8) -> String {
+    let name = match description {
+        0 => "close_notify",
+        10 => "unexpected_message",
+        20 => "bad_record_mac",
+        21 => "decryption_failed",
+        22 => "record_overflow",
+        30 => "decompression_failure",
+        40 => "handshake_failure",
+        41 => "no_certificate",
+        42 => "bad_certificate",
+        43 => "unsupported_certificate",
+        44 => "certificate_revoked",
+        45 => "certificate_expired",
+        46 => "certificate_unknown",
+        47 => "illegal_parameter",
+        48 => "unknown_ca",
+        49 => "access_denied",
+        50 => "decode_error",
+        51 => "decrypt_error",
+        60 => "export_restriction",
+        70 => "protocol_version",
+        71 => "insufficient_security",
+        80 => "internal_error",
+        86 => "inappropriate_fallback",
+        90 => "user_canceled",
+        100 => "no_renegotiation",
+        109 => "missing_extension",
+        110 => "unsupported_extension",
+        111 => "certificate_unobtainable",
+        112 => "unrecognized_name",
+        113 => "bad_certificate_status_response",
+        114 => "bad_certificate_hash_value",
+        115 => "unknown_psk_identity",
+        116 => "certificate_required",
+        120 => "no_application_protocol",
+        121 => "ech_required",
+        _ => return format!("unknown({})", description),
+    };
+    name.to_string()
+}
+
+/// Supported groups, including the post-quantum hybrids
+pub fn group_name(group: u16) -> String {
+    let name = match group {
+        0x0017 => "secp256r1",
+        0x0018 => "secp384r1",
+        0x0019 => "secp521r1",
+        0x001a => "brainpoolP256r1",
+        0x001b => "brainpoolP384r1",
+        0x001c => "brainpoolP512r1",
+        0x001d => "x25519",
+        0x001e => "x448",
+        0x001f => "brainpoolP256r1tls13",
+        0x0020 => "brainpoolP384r1tls13",
+        0x0021 => "brainpoolP512r1tls13",
+        0x0100 => "ffdhe2048",
+        0x0101 => "ffdhe3072",
+        0x0102 => "ffdhe4096",
+        0x0103 => "ffdhe6144",
+        0x0104 => "ffdhe8192",
+        0x0200 => "MLKEM512",
+        0x0201 => "MLKEM768",
+        0x0202 => "MLKEM1024",
+        0x11eb => "SecP256r1MLKEM768",
+        0x11ec => "X25519MLKEM768",
+        0x11ed => "SecP384r1MLKEM1024",
+        0x6399 => "X25519Kyber768Draft00",
+        0x639a => "SecP256r1Kyber768Draft00",
+        _ => return format!("0x{:04x}", group),
+    };
+    name.to_string()
+}
+
+pub fn signature_scheme_name(scheme: u16) -> String {
+    let name = match scheme {
+        0x0201 => "rsa_pkcs1_sha1",
+        0x0202 => "dsa_sha1",
+        0x0203 => "ecdsa_sha1",
+        0x0301 => "rsa_pkcs1_sha224",
+        0x0302 => "dsa_sha224",
+        0x0303 => "ecdsa_sha224",
+        0x0402 => "dsa_sha256",
+        0x0502 => "dsa_sha384",
+        0x0602 => "dsa_sha512",
+        0x0401 => "rsa_pkcs1_sha256",
+        0x0501 => "rsa_pkcs1_sha384",
+        0x0601 => "rsa_pkcs1_sha512",
+        0x0403 => "ecdsa_secp256r1_sha256",
+        0x0503 => "ecdsa_secp384r1_sha384",
+        0x0603 => "ecdsa_secp521r1_sha512",
+        0x0804 => "rsa_pss_rsae_sha256",
+        0x0805 => "rsa_pss_rsae_sha384",
+        0x0806 => "rsa_pss_rsae_sha512",
+        0x0807 => "ed25519",
+        0x0808 => "ed448",
+        0x0809 => "rsa_pss_pss_sha256",
+        0x080a => "rsa_pss_pss_sha384",
+        0x080b => "rsa_pss_pss_sha512",
+        0x081a => "ecdsa_brainpoolP256r1tls13_sha256",
+        0x081b => "ecdsa_brainpoolP384r1tls13_sha384",
+        0x081c => "ecdsa_brainpoolP512r1tls13_sha512",
+        0x0904 => "mldsa44",
+        0x0905 => "mldsa65",
+        0x0906 => "mldsa87",
+        _ => return format!("0x{:04x}", scheme),
+    };
+    name.to_string()
+}
+
 #[derive(Debug, PartialEq, Serialize)]
 pub struct ClientHello {
+    /// The highest offered version, tls1.3 is only announced in the supported_versions extension
     pub version: Option<&'static str>,
     pub session_id: Option<String>,
     pub hostname: Option<String>,
-}
-
-impl ClientHello {
-    pub fn new(ch: &TlsClientHelloContents, hostname: Option<String>) -> ClientHello {
-        let session_id = ch.session_id.map(|id| BASE64.encode(id));
-
-        ClientHello {
-            version: tls_version(ch.version),
-            session_id,
-            hostname,
-        }
-    }
+    pub alpn: Vec<String>,
+    pub supported_versions: Vec<&'static str>,
+    pub groups: Vec<String>,
+    /// Groups the client sent a key share for
+    pub key_shares: Vec<String>,
+    pub signature_algorithms: Vec<String>,
+    /// Encrypted client hello, the hostname is only the public name of the server
+    pub ech: bool,
+    /// Reserved values (rfc8701) that are sent to prevent ossification
+    pub grease: bool,
+    /// How the client offers to resume a previous session: psk, ticket or session_id
+    pub resumption: Option<&'static str>,
+    pub ja3: String,
+    pub ja4: String,
 }
 
 #[derive(Debug, PartialEq, Serialize)]
 pub struct ServerHello {
+    /// The negotiated version, tls1.3 is only announced in the supported_versions extension
     pub version: Option<&'static str>,
     pub session_id: Option<String>,
     pub cipher: Option<&'static str>,
+    /// Only sent in the server hello up to tls1.2, it's encrypted afterwards
+    pub alpn: Option<String>,
+    pub key_share: Option<String>,
+    /// The server asks the client for a new hello with a different key share
+    pub hello_retry: bool,
+    /// psk for tls1.3, session for an abbreviated tls1.2 handshake that either used the
+    /// session id or a ticket, the server hello alone doesn't tell them apart
+    pub resumed: Option<&'static str>,
+    pub ja3s: String,
+    pub ja4s: String,
+    /// Certificates sent in the same segment, only readable up to tls1.2
+    pub certificates: Vec<Certificate>,
 }
 
-impl ServerHello {
-    pub fn new(sh: &TlsServerHelloContents) -> ServerHello {
-        let cipher = sh.cipher.get_ciphersuite().map(|cs| cs.name);
-        let session_id = sh.session_id.map(|id| BASE64.encode(id));
+/// Alerts that are sent after the handshake are encrypted, their level and description is unknown
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Alert {
+    pub level: Option<&'static str>,
+    pub description: Option<String>,
+}
 
-        ServerHello {
-            version: tls_version(sh.version),
-            session_id,
-            cipher,
-        }
-    }
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Certificate {
+    pub subject: String,
+    pub issuer: String,
+    pub alt_names: Vec<String>,
+    pub not_before: String,
+    pub not_after: String,
+    /// Whether the certificate was expired when it was captured, this is set by the analysis stage
+    pub expired: bool,
+    /// The end of the validity as unix timestamp
+    #[serde(skip)]
+    pub expires: i64,
+    /// Type and size of the public key, like rsa2048 or ec256
+    pub key: String,
+    pub self_signed: bool,
+    pub sha256: String,
+    #[serde(skip)]
+    pub der: Vec<u8>,
 }
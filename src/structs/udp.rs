@@ -2,6 +2,8 @@ use crate::structs::NoiseLevel;
 use crate::structs::dhcp;
 use crate::structs::dns;
 use crate::structs::dropbox;
+use crate::structs::nbns;
+use crate::structs::quic;
 use crate::structs::ssdp;
 use serde::Serialize;
 
@@ -9,8 +11,10 @@ use serde::Serialize;
 pub enum UDP {
     DHCP(dhcp::DHCP),
     DNS(dns::DNS),
+    NBNS(nbns::NBNS),
     SSDP(ssdp::SSDP),
     Dropbox(dropbox::DropboxBeacon),
+    QUIC(quic::QUIC),
 
     Text(String),
     Binary(Vec<u8>),
@@ -22,8 +26,11 @@ impl UDP {
         match *self {
             DHCP(_) => NoiseLevel::Zero,
             DNS(_) => NoiseLevel::Zero,
+            NBNS(_) => NoiseLevel::Two,
             SSDP(_) => NoiseLevel::Two,
             Dropbox(_) => NoiseLevel::Two,
+            QUIC(ref quic) if quic.client_hello.is_some() => NoiseLevel::Zero,
+            QUIC(_) => NoiseLevel::Two,
             Text(_) => NoiseLevel::Two,
             Binary(_) => NoiseLevel::AlmostMaximum,
         }